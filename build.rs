@@ -0,0 +1,13 @@
+// 启用 grpc 特性时根据 proto/grader.proto 生成 gRPC 服务端和客户端代码；
+// 使用 protox 解析 proto 文件，构建时不需要安装 protoc
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/grader.proto");
+        let descriptors = protox::compile(["proto/grader.proto"], ["proto"]).expect("failed to parse proto/grader.proto");
+        tonic_prost_build::configure()
+            .compile_fds(descriptors)
+            .expect("failed to generate the gRPC service");
+    }
+}
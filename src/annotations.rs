@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use serde::Serialize;
+use crate::build_cache::CACHE_DIR;
+use crate::diagnostics::Diagnostic;
+use crate::{ExerciseResult, ExerciseStatus};
+
+// 注解文件的目录，位于课程目录下的构建缓存中；目录结构与习题路径相同，
+// 如习题 exercises/a/ok.rs 的注解为 .grader-cache/annotations/exercises/a/ok.rs.json
+const ANNOTATIONS_DIR: &str = "annotations";
+
+// 一道习题的注解文件，供编辑器插件读取并在代码中标出评测器报告的问题
+#[derive(Serialize)]
+struct Annotations<'a> {
+    exercise: &'a str,
+    status: ExerciseStatus,
+    annotations: Vec<Annotation<'a>>,
+}
+
+// 一条注解：编译器或 clippy 诊断的主要代码片段，行列号从 1 开始
+#[derive(Serialize)]
+struct Annotation<'a> {
+    // 相对于课程目录的源文件路径
+    path: &'a str,
+    line: usize,
+    column: usize,
+    end_line: usize,
+    end_column: usize,
+    // error 或 warning
+    severity: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'a str>,
+    message: &'a str,
+}
+
+// 注解文件的目录；构建缓存未启用时也放在这里，并同样不提交到课程仓库
+pub fn dir() -> PathBuf {
+    Path::new(CACHE_DIR).join(ANNOTATIONS_DIR)
+}
+
+// 写出习题的注解文件；没有诊断时也写出，编辑器据此清除上次的标记
+pub fn write(dir: &Path, result: &ExerciseResult) -> Result<(), String> {
+    let path = dir.join(file_name(&result.name));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let cache = Path::new(CACHE_DIR);
+    if !cache.join(".gitignore").exists() {
+        let _ = fs::write(cache.join(".gitignore"), "*\n");
+    }
+    let annotations = Annotations {
+        exercise: &result.name,
+        status: result.status,
+        annotations: result.diagnostics.iter().map(annotation).collect(),
+    };
+    let content = serde_json::to_string_pretty(&annotations).map_err(|e| e.to_string())?;
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn annotation(diagnostic: &Diagnostic) -> Annotation<'_> {
+    Annotation {
+        path: &diagnostic.file,
+        line: diagnostic.line,
+        column: diagnostic.column,
+        end_line: diagnostic.end_line,
+        end_column: diagnostic.end_column,
+        severity: &diagnostic.level,
+        code: diagnostic.code.as_deref(),
+        message: &diagnostic.message,
+    }
+}
+
+// 注解文件相对于注解目录的路径：习题路径加上 .json，绝对路径和 .. 等不会指到注解目录以外
+fn file_name(name: &str) -> PathBuf {
+    let mut path: PathBuf = Path::new(name)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part),
+            _ => None,
+        })
+        .collect();
+    let file = format!("{}.json", path.file_name().unwrap_or_default().to_string_lossy());
+    path.set_file_name(file);
+    path
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use time::{OffsetDateTime, UtcOffset};
+use crate::paths;
+
+// 历史报告的归档目录，位于状态目录中
+const ARCHIVE_DIR: &str = "reports/archive";
+
+// 历史报告归档目录
+pub fn archive_dir() -> PathBuf {
+    paths::state_dir().join(ARCHIVE_DIR)
+}
+
+// 保存新报告之前，把已有的报告移到 reports/archive/<时间戳>.json（时间戳取原报告的修改时间，UTC），
+// 并只保留最近 retention 份；retention 为 0 时不归档，直接覆盖
+pub fn archive(report_file: &Path, retention: usize) -> Result<Option<PathBuf>, String> {
+    if retention == 0 || !report_file.exists() {
+        return Ok(None);
+    }
+    let dir = archive_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let modified = fs::metadata(report_file)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("Failed to read {}: {}", report_file.display(), e))?;
+    let stamp = timestamp(OffsetDateTime::from(modified));
+    // 同一秒内多次运行时加序号，避免覆盖
+    let mut target = dir.join(format!("{}.json", stamp));
+    let mut n = 1;
+    while target.exists() {
+        target = dir.join(format!("{}-{}.json", stamp, n));
+        n += 1;
+    }
+    fs::rename(report_file, &target)
+        .map_err(|e| format!("Failed to archive {} to {}: {}", report_file.display(), target.display(), e))?;
+    prune(retention)?;
+    Ok(Some(target))
+}
+
+// 归档的报告，从旧到新排列
+pub fn archived() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(archive_dir()) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    reports.sort_by_key(|path| order_key(path));
+    reports
+}
+
+// 文件名为 <时间戳>.json 或 <时间戳>-<序号>.json，按时间戳和序号排序
+fn order_key(path: &Path) -> (String, u32) {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match stem.split_once('-') {
+        Some((stamp, n)) => (stamp.to_string(), n.parse().unwrap_or(0)),
+        None => (stem.to_string(), 0),
+    }
+}
+
+// 删除超出保留份数的最旧报告
+fn prune(retention: usize) -> Result<(), String> {
+    let reports = archived();
+    let excess = reports.len().saturating_sub(retention);
+    for report in &reports[..excess] {
+        fs::remove_file(report).map_err(|e| format!("Failed to remove {}: {}", report.display(), e))?;
+    }
+    Ok(())
+}
+
+fn timestamp(at: OffsetDateTime) -> String {
+    let at = at.to_offset(UtcOffset::UTC);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        at.year(),
+        u8::from(at.month()),
+        at.day(),
+        at.hour(),
+        at.minute(),
+        at.second()
+    )
+}
@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use glob::Pattern;
+use serde::Deserialize;
+use crate::build_cache::CACHE_DIR;
+use crate::placeholders::PlaceholderAction;
+
+// 快照时跳过的目录：版本库、构建产物和评测器自己的构建缓存（共用构建缓存时依赖的中间产物都写在这里）
+const SKIPPED_DIRS: [&str; 3] = [".git", "target", CACHE_DIR];
+
+// 产物检查：评测一道习题前后对比习题目录（程序不在沙箱中运行时为整个课程目录）中的文件，
+// 找出程序（或测试）在沙箱之外新建的文件
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ArtifactsConfig {
+    // 允许新建的文件，glob 语法，相对于当前目录
+    pub allow: Vec<String>,
+    // 发现不在允许列表中的文件时警告还是判为失败
+    pub action: PlaceholderAction,
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        ArtifactsConfig {
+            allow: vec!["**/Cargo.lock".to_string()],
+            action: PlaceholderAction::Warn,
+        }
+    }
+}
+
+impl ArtifactsConfig {
+    // 过滤掉允许列表（全局配置加上习题元数据中的补充）中的文件
+    pub fn unexpected(&self, files: Vec<PathBuf>, extra_allow: &[String]) -> Result<Vec<PathBuf>, String> {
+        let mut patterns = Vec::new();
+        for raw in self.allow.iter().chain(extra_allow) {
+            patterns.push(Pattern::new(raw).map_err(|e| format!("Invalid artifact pattern '{}': {}", raw, e))?);
+        }
+        Ok(files
+            .into_iter()
+            .filter(|file| !patterns.iter().any(|pattern| pattern.matches_path(file)))
+            .collect())
+    }
+}
+
+// 某一时刻目录中的全部文件（跳过 SKIPPED_DIRS 中的目录），路径相对于当前目录
+pub struct Snapshot {
+    dir: PathBuf,
+    files: HashSet<PathBuf>,
+}
+
+impl Snapshot {
+    pub fn take(dir: &Path) -> Snapshot {
+        let mut files = HashSet::new();
+        collect(dir, &mut files);
+        Snapshot {
+            dir: dir.to_path_buf(),
+            files,
+        }
+    }
+
+    // 快照之后新出现的文件，按路径排序
+    pub fn new_files(&self) -> Vec<PathBuf> {
+        let mut files = HashSet::new();
+        collect(&self.dir, &mut files);
+        let mut created: Vec<PathBuf> = files.difference(&self.files).cloned().collect();
+        created.sort();
+        created
+    }
+}
+
+// 收集目录中的文件路径；不跟随符号链接，读取失败的目录直接跳过
+fn collect(dir: &Path, files: &mut HashSet<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.iter().any(|skipped| entry.file_name() == *skipped) {
+                collect(&path, files);
+            }
+        } else {
+            files.insert(path.strip_prefix(".").unwrap_or(&path).to_path_buf());
+        }
+    }
+}
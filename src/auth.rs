@@ -0,0 +1,170 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::Deserialize;
+use crate::paths;
+use crate::submit::ServerConfig;
+
+// 设备码授权的 grant type（RFC 8628）
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+// 未登录时可以通过环境变量直接提供 token
+const TOKEN_ENV: &str = "GRADER_TOKEN";
+
+#[derive(Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    #[serde(default = "default_interval")]
+    interval: u64,
+    #[serde(default = "default_expires_in")]
+    expires_in: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+fn default_expires_in() -> u64 {
+    900
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    // 服务器下发的报告签名密钥（十六进制），用于签名评测报告；服务器不要求签名时没有
+    #[serde(default)]
+    signing_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenError {
+    error: String,
+}
+
+fn token_path() -> Result<PathBuf, String> {
+    config_file("token")
+}
+
+fn signing_key_path() -> Result<PathBuf, String> {
+    config_file("signing_key")
+}
+
+fn config_file(name: &str) -> Result<PathBuf, String> {
+    paths::project_dirs()
+        .map(|dirs| dirs.config_dir().join(name))
+        .ok_or_else(|| "Could not determine the user config directory".to_string())
+}
+
+// 读取已保存的 token，环境变量优先
+pub fn load_token() -> Result<Option<String>, String> {
+    if let Ok(token) = env::var(TOKEN_ENV) {
+        if !token.is_empty() {
+            return Ok(Some(token));
+        }
+    }
+    let path = token_path()?;
+    match fs::read_to_string(&path) {
+        Ok(token) => Ok(Some(token.trim().to_string())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+// 读取登录时保存的报告签名密钥，未登录或服务器未下发时返回 None
+pub fn load_signing_key() -> Result<Option<Vec<u8>>, String> {
+    let path = signing_key_path()?;
+    match fs::read_to_string(&path) {
+        Ok(key) => parse_hex(key.trim())
+            .map(Some)
+            .ok_or_else(|| format!("Invalid signing key in {}; run `login` again", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn save_token(token: &str) -> Result<PathBuf, String> {
+    save_private(&token_path()?, token)
+}
+
+// 写入只允许当前用户读取的文件
+fn save_private(path: &Path, content: &str) -> Result<PathBuf, String> {
+    let path = path.to_path_buf();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    // token 和签名密钥只允许当前用户读取
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o600));
+    }
+    Ok(path)
+}
+
+// 通过 OAuth 设备码流程登录课程服务器，并保存获得的 token
+pub fn login(server: &ServerConfig) -> Result<(), String> {
+    let device: DeviceCodeResponse = ureq::post(&server.endpoint("oauth/device/code"))
+        .send_form(&[("client_id", server.client_id.as_str())])
+        .map_err(|e| format!("Failed to start device login: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Invalid device code response: {}", e))?;
+
+    println!("Open {} in your browser and enter the code: {}", device.verification_uri, device.user_code);
+    println!("Waiting for authorization...");
+
+    let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = Duration::from_secs(device.interval);
+    while Instant::now() < deadline {
+        thread::sleep(interval);
+        let response = ureq::post(&server.endpoint("oauth/token")).send_form(&[
+            ("grant_type", DEVICE_CODE_GRANT),
+            ("device_code", device.device_code.as_str()),
+            ("client_id", server.client_id.as_str()),
+        ]);
+        match response {
+            Ok(response) => {
+                let token: TokenResponse = response
+                    .into_json()
+                    .map_err(|e| format!("Invalid token response: {}", e))?;
+                let path = save_token(&token.access_token)?;
+                println!("Logged in. Token saved to {}", path.display());
+                // 重新登录时换用新的密钥；服务器不再下发时删除旧密钥，报告不再签名
+                let key_path = signing_key_path()?;
+                match token.signing_key.filter(|key| parse_hex(key).is_some()) {
+                    Some(key) => {
+                        save_private(&key_path, &key)?;
+                    }
+                    None => {
+                        let _ = fs::remove_file(&key_path);
+                    }
+                }
+                return Ok(());
+            }
+            Err(ureq::Error::Status(_, response)) => {
+                let error: TokenError = response
+                    .into_json()
+                    .map_err(|e| format!("Invalid token error response: {}", e))?;
+                match error.error.as_str() {
+                    "authorization_pending" => {}
+                    "slow_down" => interval += Duration::from_secs(5),
+                    other => return Err(format!("Login failed: {}", other)),
+                }
+            }
+            Err(e) => return Err(format!("Login failed: {}", e)),
+        }
+    }
+    Err("Login timed out: the device code expired".to_string())
+}
@@ -0,0 +1,82 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+// 课程目录下的构建缓存，各 Cargo 项目习题共用，公共依赖只编译一次
+pub const CACHE_DIR: &str = ".grader-cache";
+// cargo 的中间产物目录（依赖的编译结果等）
+pub const BUILD_DIR_ENV: &str = "CARGO_BUILD_BUILD_DIR";
+// cargo 的最终产物目录（可执行文件等）
+const TARGET_DIR_ENV: &str = "CARGO_TARGET_DIR";
+// cargo 通过这个包装程序调用 rustc
+const RUSTC_WRAPPER_ENV: &str = "RUSTC_WRAPPER";
+const SCCACHE: &str = "sccache";
+
+// 启用后构建缓存的绝对路径；未启用时各习题照常构建到自己的 target 目录
+static CACHE: OnceLock<PathBuf> = OnceLock::new();
+
+// 启用构建缓存：本进程之后运行的 cargo 共用缓存中的中间产物目录
+pub fn enable() -> io::Result<()> {
+    let dir = env::current_dir()?.join(CACHE_DIR);
+    fs::create_dir_all(&dir)?;
+    // 缓存不应提交到课程仓库
+    fs::write(dir.join(".gitignore"), "*\n")?;
+    env::set_var(BUILD_DIR_ENV, dir.join("build"));
+    let _ = CACHE.set(dir);
+    Ok(())
+}
+
+// 共用的中间产物目录，未启用构建缓存时为 None
+pub fn build_dir() -> Option<PathBuf> {
+    CACHE.get().map(|dir| dir.join("build"))
+}
+
+// 习题的最终产物目录。启用构建缓存时每道习题在缓存中有单独的目录：
+// 同名的包（如都叫 exercise）并行评测时可执行文件互不覆盖，习题目录中也不会留下 target
+pub fn target_dir(exercise_dir: &Path) -> PathBuf {
+    match CACHE.get() {
+        Some(dir) => {
+            let name = exercise_dir.display().to_string().replace(['/', '\\', ':'], "-");
+            dir.join("target").join(name.trim_start_matches('-'))
+        }
+        None => exercise_dir.join("target"),
+    }
+}
+
+// 为在习题目录中运行的 cargo 命令指定最终产物目录
+pub fn configure(command: &mut std::process::Command, exercise_dir: &Path) {
+    if CACHE.get().is_some() {
+        command.env(TARGET_DIR_ENV, target_dir(exercise_dir));
+    }
+}
+
+// 让本进程之后运行的 cargo 通过 sccache 调用 rustc，不同课程目录、不同学生的仓库之间也能复用编译结果；
+// 直接用 rustc 编译的单文件习题没有 crate 级的编译参数，sccache 无法缓存，不做包装。
+// 已经设置了 RUSTC_WRAPPER 时沿用已有的设置；未安装 sccache 时返回 false
+pub fn enable_sccache() -> bool {
+    if env::var_os(RUSTC_WRAPPER_ENV).is_some_and(|wrapper| !wrapper.is_empty()) {
+        return true;
+    }
+    let available = Command::new(SCCACHE)
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if available {
+        env::set_var(RUSTC_WRAPPER_ENV, SCCACHE);
+    }
+    available
+}
+
+// 删除构建缓存，返回是否存在
+pub fn remove() -> io::Result<bool> {
+    match fs::remove_dir_all(CACHE_DIR) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
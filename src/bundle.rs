@@ -0,0 +1,308 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chacha20poly1305::aead::rand_core::RngCore;
+use sha2::Sha256;
+use crate::paths;
+
+// 教师密钥通过环境变量提供，学生环境中没有它就无法解密
+pub const KEY_ENV: &str = "GRADER_INSTRUCTOR_KEY";
+
+// 文件头：MAGIC、密钥派生算法（1 字节）、迭代次数（u32 小端）、盐、nonce，之后是密文；
+// 派生参数记录在包中，日后调整迭代次数或换用其他算法时旧包仍能解密
+const MAGIC: &[u8] = b"RBGBUNDLE2";
+// 旧版本的包，密钥派生方式已不再支持
+const LEGACY_MAGIC: &[u8] = b"RBGBUNDLE1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// 密钥派生算法：PBKDF2-HMAC-SHA256
+const KDF_PBKDF2_SHA256: u8 = 1;
+// 新建的包使用的 PBKDF2 迭代次数
+const KDF_ROUNDS: u32 = 600_000;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 + SALT_LEN + NONCE_LEN;
+
+// 本进程解密出的参考答案和隐藏测试所在的临时目录，评测结束或提前退出时删除
+static UNSEALED: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// 读取教师密钥
+pub fn instructor_key() -> Result<String, String> {
+    env::var(KEY_ENV)
+        .ok()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| format!("{} is not set; the solutions bundle cannot be decrypted", KEY_ENV))
+}
+
+// 将若干目录打包并加密，每个目录在包内以 label 作为顶层目录名
+pub fn seal(dirs: &[(&str, &Path)], output: &Path, passphrase: &str) -> Result<usize, String> {
+    seal_with_rounds(dirs, output, passphrase, KDF_ROUNDS)
+}
+
+// 按指定的 PBKDF2 迭代次数打包，迭代次数记录在包头中
+fn seal_with_rounds(dirs: &[(&str, &Path)], output: &Path, passphrase: &str, rounds: u32) -> Result<usize, String> {
+    let mut archive = Vec::new();
+    let mut count = 0;
+    for (label, dir) in dirs {
+        if !dir.exists() {
+            continue;
+        }
+        let mut files = Vec::new();
+        collect_files(dir, &mut files)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for file in files {
+            let relative = file.strip_prefix(dir).unwrap_or(&file);
+            let name = format!("{}/{}", label, relative.to_string_lossy().replace('\\', "/"));
+            let content = fs::read(&file).map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            archive.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(&(content.len() as u64).to_le_bytes());
+            archive.extend_from_slice(&content);
+            count += 1;
+        }
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = ChaCha20Poly1305::new(&derive_key(KDF_PBKDF2_SHA256, rounds, passphrase, &salt)?);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, archive.as_slice())
+        .map_err(|_| "Failed to encrypt bundle".to_string())?;
+
+    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    data.extend_from_slice(MAGIC);
+    data.push(KDF_PBKDF2_SHA256);
+    data.extend_from_slice(&rounds.to_le_bytes());
+    data.extend_from_slice(&salt);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    fs::write(output, data).map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    Ok(count)
+}
+
+// 解密出的临时目录；离开作用域时删除，以 std::process::exit 退出前需调用 remove_unsealed
+pub struct Unsealed {
+    dir: PathBuf,
+}
+
+impl Unsealed {
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for Unsealed {
+    fn drop(&mut self) {
+        remove_unsealed();
+    }
+}
+
+// 把打包文件解密到只有当前用户能访问的临时目录，明文不在磁盘上长期保留
+pub fn unseal_temporary(bundle: &Path, passphrase: &str) -> Result<Unsealed, String> {
+    let dir = paths::scratch_dir("unsealed");
+    create_private_dir(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    *UNSEALED.lock().unwrap_or_else(|e| e.into_inner()) = Some(dir.clone());
+    let unsealed = Unsealed { dir };
+    unseal(bundle, passphrase, &unsealed.dir)?;
+    Ok(unsealed)
+}
+
+// 删除本进程解密出的临时目录
+pub fn remove_unsealed() {
+    if let Some(dir) = UNSEALED.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        let _ = fs::remove_dir_all(dir);
+    }
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+// 解密打包文件并解压到 dest 目录
+pub fn unseal(bundle: &Path, passphrase: &str, dest: &Path) -> Result<(), String> {
+    let data = fs::read(bundle).map_err(|e| format!("Failed to read {}: {}", bundle.display(), e))?;
+    if data.starts_with(LEGACY_MAGIC) {
+        return Err(format!("{} was sealed by an older grader; re-create it with `cargotest bundle`", bundle.display()));
+    }
+    if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+        return Err(format!("{} is not a solutions bundle", bundle.display()));
+    }
+    let mut pos = MAGIC.len();
+    let kdf = take(&data, &mut pos, 1)?[0];
+    let rounds = read_u32(&data, &mut pos)?;
+    let salt = take(&data, &mut pos, SALT_LEN)?;
+    let nonce = Nonce::from_slice(take(&data, &mut pos, NONCE_LEN)?);
+    let cipher = ChaCha20Poly1305::new(&derive_key(kdf, rounds, passphrase, salt)?);
+    let archive = cipher
+        .decrypt(nonce, &data[HEADER_LEN..])
+        .map_err(|_| format!("Failed to decrypt {}: wrong instructor key?", bundle.display()))?;
+
+    let mut pos = 0;
+    while pos < archive.len() {
+        let name_len = read_u32(&archive, &mut pos)? as usize;
+        let name = String::from_utf8_lossy(take(&archive, &mut pos, name_len)?).to_string();
+        let content_len = read_u64(&archive, &mut pos)? as usize;
+        let content = take(&archive, &mut pos, content_len)?;
+
+        // 拒绝跳出目标目录的路径
+        if name.split('/').any(|part| part == ".." || part.is_empty()) {
+            return Err(format!("Invalid path in bundle: {}", name));
+        }
+        let path = dest.join(&name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+// 按包头中记录的算法和迭代次数从口令派生 256 位密钥
+fn derive_key(kdf: u8, rounds: u32, passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    if kdf != KDF_PBKDF2_SHA256 {
+        return Err(format!("Unsupported key derivation in solutions bundle ({}); update the grader", kdf));
+    }
+    let mut key = Key::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    Ok(key)
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    let end = pos.checked_add(len).filter(|end| *end <= data.len());
+    match end {
+        Some(end) => {
+            let slice = &data[*pos..end];
+            *pos = end;
+            Ok(slice)
+        }
+        None => Err("Corrupted solutions bundle".to_string()),
+    }
+}
+
+fn read_u32(data: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let bytes = take(data, pos, 4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u64(data: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let bytes = take(data, pos, 8)?;
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试中使用较少的迭代次数，避免调试构建下派生密钥过慢
+    const TEST_ROUNDS: u32 = 1_000;
+
+    // 每个测试独占的临时目录，其中 solutions/ 下有两个文件
+    fn fixture(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("grader-bundle-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("solutions/ch1")).unwrap();
+        fs::write(dir.join("solutions/a.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("solutions/ch1/b.rs"), "pub fn b() -> u8 { 1 }\n").unwrap();
+        dir
+    }
+
+    fn sealed(name: &str) -> (PathBuf, PathBuf) {
+        let dir = fixture(name);
+        let bundle = dir.join("solutions.bundle");
+        let count = seal_with_rounds(&[("solutions", &dir.join("solutions"))], &bundle, "secret", TEST_ROUNDS).unwrap();
+        assert_eq!(count, 2);
+        (dir, bundle)
+    }
+
+    // 修改包头中 offset 处的一个字节
+    fn tamper(bundle: &Path, offset: usize) {
+        let mut data = fs::read(bundle).unwrap();
+        data[offset] ^= 0x01;
+        fs::write(bundle, data).unwrap();
+    }
+
+    #[test]
+    fn round_trip_with_right_passphrase() {
+        let (dir, bundle) = sealed("round-trip");
+        let dest = dir.join("out");
+        unseal(&bundle, "secret", &dest).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("solutions/a.rs")).unwrap(), "fn main() {}\n");
+        assert_eq!(fs::read_to_string(dest.join("solutions/ch1/b.rs")).unwrap(), "pub fn b() -> u8 { 1 }\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let (dir, bundle) = sealed("wrong-passphrase");
+        let dest = dir.join("out");
+        let err = unseal(&bundle, "not the secret", &dest).unwrap_err();
+        assert!(err.contains("wrong instructor key"), "{}", err);
+        assert!(!dest.exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        // 盐和 nonce 各改一个字节
+        for (name, offset) in [("tampered-salt", MAGIC.len() + 5), ("tampered-nonce", HEADER_LEN - 1)] {
+            let (dir, bundle) = sealed(name);
+            tamper(&bundle, offset);
+            assert!(unseal(&bundle, "secret", &dir.join("out")).is_err(), "{}", name);
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn recorded_kdf_parameters_are_honoured() {
+        let (dir, bundle) = sealed("kdf-parameters");
+        let data = fs::read(&bundle).unwrap();
+        assert_eq!(data[MAGIC.len()], KDF_PBKDF2_SHA256);
+        let mut pos = MAGIC.len() + 1;
+        assert_eq!(read_u32(&data, &mut pos).unwrap(), TEST_ROUNDS);
+
+        // 迭代次数取自包头而不是 KDF_ROUNDS，改动后派生出的密钥不同
+        tamper(&bundle, MAGIC.len() + 1);
+        assert!(unseal(&bundle, "secret", &dir.join("out")).is_err());
+
+        // 不认识的算法明确报错
+        let mut unknown = data.clone();
+        unknown[MAGIC.len()] = 0xff;
+        fs::write(&bundle, unknown).unwrap();
+        let err = unseal(&bundle, "secret", &dir.join("out")).unwrap_err();
+        assert!(err.contains("Unsupported key derivation"), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_bundle_is_rejected() {
+        let dir = fixture("legacy");
+        let bundle = dir.join("solutions.bundle");
+        fs::write(&bundle, [LEGACY_MAGIC, &[0u8; 64]].concat()).unwrap();
+        let err = unseal(&bundle, "secret", &dir.join("out")).unwrap_err();
+        assert!(err.contains("re-create it"), "{}", err);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::hashing::hex;
+use crate::instructor::Role;
+use crate::manifest::Toolchain;
+use crate::metadata::ExerciseMeta;
+use crate::paths;
+use crate::{ExerciseResult, RunContext};
+
+// 增量评测的结果缓存，保存在当前课程的状态目录中
+pub const CACHE_FILE: &str = "result-cache.json";
+
+// 上次评测各习题时的缓存键和结果，键为习题名
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ResultCache {
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CacheEntry {
+    key: String,
+    // 评测时的文件戳，--changed-only 据此判断习题是否修改过；旧版本的缓存中没有
+    #[serde(default)]
+    stamp: String,
+    result: serde_json::Value,
+}
+
+impl ResultCache {
+    // 读取缓存；文件不存在或无法解析时从空缓存开始，所有习题都重新评测
+    pub fn load() -> ResultCache {
+        let path = paths::state_file(CACHE_FILE);
+        fs::read_to_string(path).ok().and_then(|content| serde_json::from_str(&content).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = paths::state_file(CACHE_FILE);
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // 缓存键一致时返回上次的结果
+    pub fn get(&self, name: &str, key: &str) -> Option<ExerciseResult> {
+        self.entries
+            .get(name)
+            .filter(|entry| entry.key == key)
+            .and_then(|entry| serde_json::from_value(entry.result.clone()).ok())
+    }
+
+    // 文件戳一致时返回上次的结果，不需要读取文件内容
+    pub fn get_unchanged(&self, name: &str, stamp: &str) -> Option<ExerciseResult> {
+        self.entries
+            .get(name)
+            .filter(|entry| !entry.stamp.is_empty() && entry.stamp == stamp)
+            .and_then(|entry| serde_json::from_value(entry.result.clone()).ok())
+    }
+
+    pub fn insert(&mut self, name: &str, key: String, stamp: String, result: &ExerciseResult) {
+        if let Ok(result) = serde_json::to_value(result) {
+            self.entries.insert(name.to_string(), CacheEntry { key, stamp, result });
+        }
+    }
+
+    // 文件被改动（如 touch）但内容不变、沿用了结果的习题，记下新的文件戳
+    pub fn update_stamp(&mut self, name: &str, stamp: String) {
+        if let Some(entry) = self.entries.get_mut(name) {
+            entry.stamp = stamp;
+        }
+    }
+}
+
+// 评测环境：工具链版本和运行身份（教师模式会注入隐藏测试），任何一项变化都使全部缓存失效
+pub fn environment(role: Role) -> String {
+    let toolchain = Toolchain::detect();
+    format!("{}\n{}\n{:?}", toolchain.rustc, toolchain.cargo, role)
+}
+
+// 可以缓存的习题的缓存键；使用随机输入生成器的习题每次运行的输入不同，不缓存
+pub fn exercise_key(ctx: &RunContext, exercise: &Path, environment: &str) -> Option<String> {
+    let meta = ctx.exercise_meta(exercise);
+    if meta.generator.is_some() {
+        return None;
+    }
+    cache_key(exercise, environment, &meta).ok()
+}
+
+// 习题的文件戳：评测环境、习题元数据和构建输入的路径、大小及修改时间。只读取文件的元数据，
+// 比缓存键快得多，但只改动修改时间时也会变化；不能缓存的习题没有文件戳
+pub fn exercise_stamp(ctx: &RunContext, exercise: &Path, environment: &str) -> Option<String> {
+    let meta = ctx.exercise_meta(exercise);
+    if meta.generator.is_some() {
+        return None;
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(environment.as_bytes());
+    hasher.update([0]);
+    hasher.update(format!("{:?}", meta).as_bytes());
+    hasher.update([0]);
+    let mut files = Vec::new();
+    if exercise.is_dir() {
+        collect_inputs(exercise, &mut files).ok()?;
+        files.sort();
+    } else {
+        files.push(exercise.to_path_buf());
+    }
+    for file in files {
+        let metadata = fs::metadata(&file).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+        let relative = file.strip_prefix(exercise).unwrap_or(&file);
+        hasher.update(format!("{}\0{}\0{}\0", relative.to_string_lossy().replace('\\', "/"), metadata.len(), modified.as_nanos()));
+    }
+    Some(hex(&hasher.finalize()))
+}
+
+// 习题的缓存键：评测环境（工具链、运行身份等）、习题元数据和构建输入的内容。
+// Cargo 项目计入 Cargo.lock，依赖版本变化会使缓存失效；题面等 Markdown 文档不参与构建，修改它们不会
+pub fn cache_key(exercise: &Path, environment: &str, meta: &ExerciseMeta) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(environment.as_bytes());
+    hasher.update([0]);
+    hasher.update(format!("{:?}", meta).as_bytes());
+    hasher.update([0]);
+    if exercise.is_dir() {
+        let mut files = Vec::new();
+        collect_inputs(exercise, &mut files)?;
+        files.sort();
+        for file in files {
+            let relative = file.strip_prefix(exercise).unwrap_or(&file);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(&file)?);
+            hasher.update([0]);
+        }
+    } else {
+        hasher.update(fs::read(exercise)?);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+// Cargo 项目的构建输入：除构建产物、版本库元数据和 Markdown 文档以外的全部文件
+fn collect_inputs(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_dir() {
+            if name != "target" && name != ".git" {
+                collect_inputs(&path, files)?;
+            }
+        } else if !name.ends_with(".md") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
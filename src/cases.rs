@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::metadata::ExerciseMeta;
+use crate::process::{self, RunOptions};
+use crate::sandbox::Sandbox;
+
+// 输出比对类习题的一个测试用例
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TestCase {
+    pub name: String,
+    // 标准输入内容，或相对于习题目录的输入文件
+    pub input: Option<String>,
+    pub input_file: Option<String>,
+    // 期望的标准输出，或相对于习题目录的期望输出文件
+    pub expected: Option<String>,
+    pub expected_file: Option<String>,
+    // 覆盖习题级别的单用例时间限制
+    pub time_limit_ms: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    Passed,
+    WrongAnswer,
+    RuntimeError,
+    TimedOut,
+}
+
+// 单个用例的评测结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CaseResult {
+    pub name: String,
+    pub status: CaseStatus,
+    pub duration_ms: u64,
+}
+
+// 一组用例的运行结果
+pub struct CaseRun {
+    pub results: Vec<CaseResult>,
+    // 失败用例的说明
+    pub failures: Vec<String>,
+    // 所有用例中最大的峰值内存（KB）
+    pub peak_memory_kb: Option<u64>,
+}
+
+// 逐个运行测试用例，用例文件路径已在元数据中解析为相对于当前目录的路径
+pub fn run_cases(binary: &Path, exercise_dir: &Path, meta: &ExerciseMeta) -> Result<CaseRun, String> {
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    let mut peak_memory_kb: Option<u64> = None;
+
+    for (i, case) in meta.cases.iter().enumerate() {
+        let name = if case.name.is_empty() { format!("case {}", i + 1) } else { case.name.clone() };
+        let input = load(&case.input, &case.input_file, &name, "input")?;
+        let expected = load(&case.expected, &case.expected_file, &name, "expected output")?;
+        let time_limit = case.time_limit_ms.or(meta.case_time_limit_ms).map(Duration::from_millis);
+        // 每个用例使用全新的沙箱目录
+        let sandbox = Sandbox::for_exercise(meta, exercise_dir)?;
+
+        let measured = process::run_measured(
+            &mut Command::new(binary),
+            RunOptions {
+                stdin: input.as_deref().map(str::as_bytes),
+                timeout: time_limit,
+                isolate_network: meta.isolate_network.unwrap_or(false),
+                working_dir: sandbox.as_ref().map(Sandbox::path),
+            },
+        )
+        .map_err(|e| format!("Failed to execute compiled file: {}", e))?;
+        if let Some(kb) = measured.peak_memory_kb {
+            peak_memory_kb = Some(peak_memory_kb.unwrap_or(0).max(kb));
+        }
+
+        let stdout = String::from_utf8_lossy(&measured.output.stdout);
+        let status = if measured.timed_out {
+            failures.push(format!(
+                "Case '{}' timed out after {} ms",
+                name,
+                time_limit.map(|t| t.as_millis()).unwrap_or_default()
+            ));
+            CaseStatus::TimedOut
+        } else if !measured.output.status.success() {
+            failures.push(format!(
+                "Case '{}' failed at runtime:\n{}",
+                name,
+                String::from_utf8_lossy(&measured.output.stderr)
+            ));
+            CaseStatus::RuntimeError
+        } else if expected.as_deref().map(|e| normalize(e) != normalize(&stdout)).unwrap_or(false) {
+            failures.push(format!(
+                "Case '{}' produced wrong output.\nExpected:\n{}\nActual:\n{}",
+                name,
+                expected.as_deref().unwrap_or_default(),
+                stdout
+            ));
+            CaseStatus::WrongAnswer
+        } else {
+            CaseStatus::Passed
+        };
+        results.push(CaseResult {
+            name,
+            status,
+            duration_ms: measured.duration.as_millis() as u64,
+        });
+    }
+    Ok(CaseRun {
+        results,
+        failures,
+        peak_memory_kb,
+    })
+}
+
+fn load(inline: &Option<String>, file: &Option<String>, case: &str, what: &str) -> Result<Option<String>, String> {
+    match (inline, file) {
+        (Some(text), _) => Ok(Some(text.clone())),
+        (None, Some(file)) => fs::read_to_string(file)
+            .map(Some)
+            .map_err(|e| format!("Failed to read {} for case '{}' ({}): {}", what, case, file, e)),
+        (None, None) => Ok(None),
+    }
+}
+
+// 忽略行尾空白和末尾空行
+fn normalize(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim_end().to_string()
+}
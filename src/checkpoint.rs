@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::paths;
+use crate::ExerciseResult;
+
+// 批量评测的检查点，保存在当前课程的状态目录中；运行正常结束后删除
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+// 两次写入检查点的最短间隔，避免习题很多时每评测完一道都重写整个文件
+const SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+// 被中断的评测运行中已经评测完的习题结果
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Checkpoint {
+    pub run_id: String,
+    results: Vec<serde_json::Value>,
+    #[serde(skip)]
+    saved_at: Option<Instant>,
+}
+
+impl Checkpoint {
+    pub fn new(run_id: &str) -> Checkpoint {
+        Checkpoint { run_id: run_id.to_string(), ..Checkpoint::default() }
+    }
+
+    // 读取上次被中断的运行留下的检查点，没有时返回 None
+    pub fn load() -> Result<Option<Checkpoint>, String> {
+        let path = paths::state_file(CHECKPOINT_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        let path = paths::state_file(CHECKPOINT_FILE);
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        // 先写临时文件再改名，写到一半时被中断也不会留下损坏的检查点
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, content).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        fs::rename(&partial, &path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        self.saved_at = Some(Instant::now());
+        Ok(())
+    }
+
+    // 记录一道评测完的习题；距上次写入超过间隔时写入检查点
+    pub fn record(&mut self, result: &ExerciseResult) -> Result<(), String> {
+        if let Ok(result) = serde_json::to_value(result) {
+            self.results.push(result);
+        }
+        if self.saved_at.is_none_or(|saved_at| saved_at.elapsed() >= SAVE_INTERVAL) {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    // 检查点中的习题结果
+    pub fn results(&self) -> Vec<ExerciseResult> {
+        self.results.iter().filter_map(|result| serde_json::from_value(result.clone()).ok()).collect()
+    }
+
+    // 运行正常结束，删除检查点
+    pub fn remove() {
+        let _ = fs::remove_file(paths::state_file(CHECKPOINT_FILE));
+    }
+}
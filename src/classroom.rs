@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use crate::hashing::hex;
+
+// 课堂模式配置，在 grader.toml 的 [classroom] 中配置；启用后评测前校验学生提交的 git 状态，
+// 使成绩能追溯到确切的提交代码
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ClassroomConfig {
+    // 工作区有未提交的修改时拒绝评测（否则只记录修改内容的哈希）
+    pub require_clean: bool,
+    // 要求 HEAD 提交带有有效签名（git verify-commit）
+    pub verify_signatures: bool,
+    // 评测完成后在学生仓库中留下的评测记录，未配置时不记录
+    pub record: Option<GradeRecord>,
+    // record = "branch" 时保存评测报告的分支
+    pub grades_branch: String,
+}
+
+impl Default for ClassroomConfig {
+    fn default() -> Self {
+        ClassroomConfig {
+            require_clean: false,
+            verify_signatures: false,
+            record: None,
+            grades_branch: "grades".to_string(),
+        }
+    }
+}
+
+// 评测记录的方式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GradeRecord {
+    // 在被评测的提交上创建附注标签 graded-<run_id>
+    Tag,
+    // 将评测报告提交到 grades 分支的 reports/<run_id>.json，不改动工作区
+    Branch,
+}
+
+// 报告中记录的提交信息
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubmissionInfo {
+    pub commit: String,
+    pub dirty: bool,
+    // 未提交修改（git diff HEAD 及未跟踪文件）的 SHA-256，工作区干净时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dirty_diff_hash: Option<String>,
+    // 是否校验了提交签名及其结果，未要求校验时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_verified: Option<bool>,
+}
+
+impl ClassroomConfig {
+    // 校验 exercises 目录所在的 git 检出并记录其提交信息
+    pub fn verify(&self, exercises_dir: &Path) -> Result<SubmissionInfo, String> {
+        let inside = git(exercises_dir, &["rev-parse", "--is-inside-work-tree"])
+            .map(|output| output.trim() == "true")
+            .unwrap_or(false);
+        if !inside {
+            return Err(format!("{} is not inside a git checkout", exercises_dir.display()));
+        }
+        let commit = git(exercises_dir, &["rev-parse", "HEAD"])?.trim().to_string();
+
+        let status = git(exercises_dir, &["status", "--porcelain"])?;
+        let dirty = !status.trim().is_empty();
+        if dirty && self.require_clean {
+            return Err(format!("Submission has uncommitted changes:\n{}", status));
+        }
+        let dirty_diff_hash = if dirty {
+            let diff = git(exercises_dir, &["diff", "HEAD", "--binary"])?;
+            let untracked = git(exercises_dir, &["ls-files", "--others", "--exclude-standard"])?;
+            let digest = Sha256::new()
+                .chain_update(diff.as_bytes())
+                .chain_update([0])
+                .chain_update(untracked.as_bytes())
+                .finalize();
+            Some(hex(&digest))
+        } else {
+            None
+        };
+
+        let signature_verified = if self.verify_signatures {
+            let verified = git(exercises_dir, &["verify-commit", "HEAD"]).is_ok();
+            if !verified {
+                return Err(format!("Commit {} does not have a valid signature", commit));
+            }
+            Some(verified)
+        } else {
+            None
+        };
+
+        Ok(SubmissionInfo { commit, dirty, dirty_diff_hash, signature_verified })
+    }
+}
+
+// 按配置在学生仓库中记录本次评测，返回创建的标签名或分支名
+pub fn record_grades(
+    config: &ClassroomConfig,
+    exercises_dir: &Path,
+    submission: &SubmissionInfo,
+    run_id: &str,
+    report_file: &Path,
+    summary: &str,
+) -> Result<Option<String>, String> {
+    let message = format!("Graded run {}: {}", run_id, summary);
+    match config.record {
+        None => Ok(None),
+        Some(GradeRecord::Tag) => {
+            let tag = format!("graded-{}", run_id);
+            git(exercises_dir, &["tag", "-a", &tag, "-m", &message, &submission.commit])?;
+            Ok(Some(tag))
+        }
+        Some(GradeRecord::Branch) => {
+            let report = fs::canonicalize(report_file)
+                .map_err(|e| format!("Failed to read {}: {}", report_file.display(), e))?;
+            let blob = git(exercises_dir, &["hash-object", "-w", &report.to_string_lossy()])?;
+
+            // 用临时索引构造新的树，避免影响学生的工作区和暂存区
+            let index = std::env::temp_dir().join(format!("grader-index-{}", std::process::id()));
+            let _ = fs::remove_file(&index);
+            let branch = format!("refs/heads/{}", config.grades_branch);
+            let parent = git(exercises_dir, &["rev-parse", "--verify", "--quiet", &branch])
+                .ok()
+                .map(|commit| commit.trim().to_string());
+            let result = (|| {
+                if let Some(parent) = &parent {
+                    git_with_index(exercises_dir, &["read-tree", parent], &index)?;
+                }
+                let entry = format!("100644,{},reports/{}.json", blob.trim(), run_id);
+                git_with_index(exercises_dir, &["update-index", "--add", "--cacheinfo", &entry], &index)?;
+                git_with_index(exercises_dir, &["write-tree"], &index)
+            })();
+            let _ = fs::remove_file(&index);
+            let tree = result?;
+
+            let mut args = vec!["commit-tree", tree.trim(), "-m", &message];
+            if let Some(parent) = &parent {
+                args.extend(["-p", parent.as_str()]);
+            }
+            let commit = git(exercises_dir, &args)?;
+            git(exercises_dir, &["update-ref", &branch, commit.trim()])?;
+            Ok(Some(config.grades_branch.clone()))
+        }
+    }
+}
+
+// 在 dir 中运行 git 命令并返回标准输出
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    run_git(Command::new("git").args(args).current_dir(dir), args)
+}
+
+// 使用指定的索引文件运行 git 命令
+fn git_with_index(dir: &Path, args: &[&str], index: &Path) -> Result<String, String> {
+    run_git(Command::new("git").args(args).current_dir(dir).env("GIT_INDEX_FILE", index), args)
+}
+
+fn run_git(command: &mut Command, args: &[&str]) -> Result<String, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to execute git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
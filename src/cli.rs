@@ -0,0 +1,349 @@
+use std::path::PathBuf;
+use clap::{Args, Parser, Subcommand};
+use crate::filter::ExerciseFilter;
+use crate::instructor::Role;
+use crate::report::ReportFormat;
+use crate::shard::Shard;
+use crate::tap::OutputFormat;
+
+// 命令行：全局选项加子命令，帮助文本由 clap 根据下面的 help/about 生成
+#[derive(Parser, Debug)]
+#[command(name = "cargotest", version, about = "Grades the Rust exercises in this repository")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub mode: Mode,
+    // 在 ~/.grader/courses/<name> 中运行，每门课程有独立的习题、清单和状态
+    #[arg(long, global = true, value_name = "NAME", help = "Run inside the installed course ~/.grader/courses/<NAME>")]
+    pub course: Option<String>,
+    // 运行身份，默认学生模式；教师模式也可以通过 GRADER_ROLE 开启
+    #[arg(
+        long,
+        global = true,
+        env = "GRADER_ROLE",
+        default_value = "student",
+        value_parser = Role::parse,
+        help = "Run as 'student' or 'instructor'"
+    )]
+    pub role: Role,
+    // 习题目录，可以是仓库外的绝对路径，可重复指定多个；未指定时读取 EXERCISES_DIR 环境变量，
+    // 再其次是配置中的 [[roots]]，默认为 exercises
+    #[arg(
+        long,
+        global = true,
+        env = "EXERCISES_DIR",
+        value_name = "PATH",
+        help = "Directory containing the exercises; repeat to grade several roots [default: exercises]"
+    )]
+    pub exercises_dir: Vec<String>,
+    // verbose 模式下实时输出每道题的日志，并加上习题名前缀
+    #[arg(short, long, global = true, help = "Stream each exercise's output live and keep a log of the run")]
+    pub verbose: bool,
+    // 面向屏幕阅读器的纯文本输出，不使用颜色和 Unicode 符号
+    #[arg(long, global = true, help = "Plain ASCII output without colors or symbols (screen-reader friendly)")]
+    pub ascii: bool,
+}
+
+// 各评测模式共用的选项
+#[derive(Args, Debug, Clone, Default)]
+pub struct GradeArgs {
+    // 随机种子，记录在运行清单中并通过 GRADER_SEED 传给习题程序；默认取当前时间
+    #[arg(long, help = "Random seed passed to exercise programs via GRADER_SEED")]
+    pub seed: Option<u64>,
+    // CI 分片：只评测属于第 i/n 片的习题
+    #[arg(long, value_name = "I/N", value_parser = Shard::parse, help = "Only grade shard I of N, e.g. 2/4")]
+    pub shard: Option<Shard>,
+    // 只评测路径匹配的习题：glob（如 'exercises/panics/*'），或以 re: 开头的正则表达式
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_parser = ExerciseFilter::parse,
+        help = "Only grade exercises whose path matches a glob, or a regex prefixed with re:"
+    )]
+    pub filter: Option<ExerciseFilter>,
+    // 每道习题的评测时限（秒），超时即结束评测进程及其子进程；习题元数据中的 timeout_secs 优先
+    #[arg(
+        long,
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Stop grading an exercise after this many seconds and record it as a timeout"
+    )]
+    pub timeout: Option<u64>,
+    // 在 report.json 之外额外生成的报告格式，可用逗号分隔或重复指定
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_delimiter = ',',
+        value_parser = ReportFormat::parse,
+        help = "Also write the report in these formats next to report.json: junit, html, markdown, csv, sarif"
+    )]
+    pub report_format: Vec<ReportFormat>,
+    // 标准输出格式；tap 时只在标准输出上输出 TAP，其余输出转到标准错误
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "human",
+        value_parser = OutputFormat::parse,
+        help = "Output format on stdout: human, or tap for TAP version 13 (other output goes to stderr)"
+    )]
+    pub format: OutputFormat,
+    // 把第一道失败习题的错误信息复制到剪贴板
+    #[arg(long, help = "Copy the errors of the first failed exercise to the clipboard")]
+    pub copy_errors: bool,
+    // 对参考答案目录跑完整评测流程，期望全部通过
+    #[arg(long, help = "Grade the reference solutions instead of the exercises; fails unless all pass")]
+    pub grade_solutions: bool,
+    // 有习题未通过时仍以 0 退出，适合交互使用；默认以 1 退出，便于 CI 判定失败
+    #[arg(long, help = "Exit with status 0 even if some exercises fail (failures exit 1, grader errors 2)")]
+    pub no_fail_exit: bool,
+    // 在固定工具链、依赖已 vendor 的容器中断网评测，镜像保留下来，日后可用 rerun 原样重新评测
+    #[arg(long, help = "Grade in a container with a pinned toolchain and vendored dependencies, kept for re-grading")]
+    pub hermetic: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Mode {
+    #[command(about = "Grade exercises in order, re-checking the current one whenever its files are saved")]
+    Watch {
+        // 监视整个章节：任何文件变化时只重新评测受影响的习题，并显示章节状态面板
+        #[arg(long, value_name = "DIR", help = "Watch a whole chapter and re-grade exercises as their files change")]
+        chapter: Option<PathBuf>,
+        #[command(flatten)]
+        grade: GradeArgs,
+    },
+    #[command(about = "Grade all exercises and save a report")]
+    All {
+        #[command(flatten)]
+        grade: GradeArgs,
+        // 在每个工具链下各评测一遍，生成矩阵报告
+        #[arg(long, value_name = "LIST", help = "Grade under each of these toolchains, e.g. stable,beta,nightly")]
+        toolchains: Option<String>,
+        // 同时评测的习题数和同时编译的单文件习题数，默认为 CPU 核数
+        #[arg(
+            short,
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Grade up to N exercises and compile up to N single-file exercises in parallel [default: number of CPUs]"
+        )]
+        jobs: Option<u32>,
+        // 默认增量评测：构建输入、元数据和工具链都没有变化的习题沿用上次的结果；--force 全部重新评测
+        #[arg(long, help = "Re-grade every exercise instead of reusing the results of unchanged ones")]
+        force: bool,
+        // 旧版本中开启增量评测的选项，现在增量评测默认开启，保留以兼容已有的脚本和运行清单
+        #[arg(long, hide = true, conflicts_with = "force")]
+        incremental: bool,
+        // 只按文件的修改时间和大小找出上次评测后修改过的习题，不读取未修改习题的内容，只显示修改过的习题
+        #[arg(
+            long,
+            conflicts_with_all = ["force", "toolchains"],
+            help = "Only grade and show exercises whose files changed since they were last graded"
+        )]
+        changed_only: bool,
+        // 继续被中断（崩溃或 Ctrl+C）的运行，跳过其中已经评测完的习题
+        #[arg(long, conflicts_with = "toolchains", help = "Resume an interrupted run, skipping the exercises it already graded")]
+        resume: bool,
+    },
+    #[command(about = "Grade a single exercise with detailed output")]
+    Run {
+        // 习题名（如 panic1）或路径（Cargo 项目目录或 .rs 文件）
+        #[arg(value_name = "EXERCISE", help = "Name or path of the exercise (Cargo project directory or .rs file)")]
+        exercise: String,
+        #[command(flatten)]
+        grade: GradeArgs,
+    },
+    #[command(about = "List the exercises in this course with their last known results")]
+    List {
+        // 以 JSON 输出，供编辑器插件等工具使用
+        #[arg(long, help = "Print the list as JSON")]
+        json: bool,
+    },
+    #[command(about = "Remove the target directories of all exercises and the shared build cache")]
+    Clean,
+    #[command(about = "Work with saved reports")]
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    #[command(about = "Replay a previous run from its run manifest")]
+    Rerun {
+        #[arg(
+            value_name = "RUN_ID",
+            conflicts_with = "manifest",
+            help = "Run to replay, or a unique prefix of its ID, e.g. the run_id of an archived report (default: the latest run)"
+        )]
+        run_id: Option<String>,
+        #[arg(long, help = "Run manifest to replay (default: the latest run)")]
+        manifest: Option<PathBuf>,
+    },
+    #[command(about = "Show a previous run's output exactly as it appeared, without grading again")]
+    Replay {
+        #[arg(value_name = "RUN_ID", help = "Run to show, or a unique prefix of its ID (default: the latest run)")]
+        run_id: Option<String>,
+        #[arg(long, help = "Pause between outputs as the run did (idle periods are shortened to 3 seconds)")]
+        realtime: bool,
+    },
+    #[command(about = "Log in to the course server")]
+    Login,
+    #[command(about = "Submit the latest report to the course server")]
+    Submit {
+        #[arg(long, help = "Only resend submissions queued while offline")]
+        flush: bool,
+    },
+    #[command(about = "Show the next hint for an exercise")]
+    Hint {
+        #[arg(value_name = "EXERCISE", help = "Name or path of the exercise")]
+        exercise: String,
+    },
+    #[command(about = "Show an exercise's problem statement")]
+    Show {
+        exercise: PathBuf,
+    },
+    #[command(about = "Grade an exercise and share its anonymized failure log")]
+    Share {
+        exercise: PathBuf,
+    },
+    #[command(about = "Check statements, snippets and reference solutions (instructor)")]
+    Validate,
+    #[command(about = "Encrypt the solutions and hidden tests into a bundle (instructor)")]
+    Bundle,
+    #[command(about = "Diff an exercise against its reference solution (instructor)")]
+    Diff {
+        exercise: PathBuf,
+    },
+    #[command(about = "Aggregate student reports into class statistics (instructor)")]
+    Aggregate {
+        #[arg(required = true)]
+        reports: Vec<String>,
+    },
+    #[command(about = "Find suspiciously similar submissions (instructor)")]
+    Similarity {
+        submissions: PathBuf,
+    },
+    #[command(about = "Generate a personalized copy of the exercises for a student (instructor)")]
+    Personalize {
+        #[arg(help = "Student name substituted for {{student}}")]
+        student: String,
+        #[arg(long, help = "Directory to write the personalized exercises to")]
+        output: PathBuf,
+        #[arg(long, value_name = "ID", help = "Course ID substituted for {{course}} [default: the --course name]")]
+        course_id: Option<String>,
+        #[arg(long, help = "Seed for {{seed}} and {{random MIN MAX}} [default: derived from the course and student]")]
+        seed: Option<u64>,
+    },
+    // 以 gRPC 服务的形式提供评测，供其他语言编写的平台提交评测任务；需要以 grpc 特性编译
+    #[cfg(feature = "grpc")]
+    #[command(about = "Serve the grading engine over gRPC (see proto/grader.proto)")]
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:50051", help = "Address to listen on")]
+        addr: std::net::SocketAddr,
+        // 同时执行的评测任务数，其余请求排队等待
+        #[arg(
+            long,
+            value_name = "N",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Grade up to N requests at a time and queue the rest [default: number of CPUs]"
+        )]
+        workers: Option<u32>,
+        // 请求的 submission 除服务器的习题目录外还可以在这些目录中（如存放学生仓库的目录）
+        #[arg(
+            long = "allow-dir",
+            value_name = "DIR",
+            help = "Also accept requests for exercise directories under DIR (repeatable); by default only the server's exercise directory"
+        )]
+        allow_dirs: Vec<PathBuf>,
+    },
+    // 把每名学生的提交分发给多台运行 cargotest serve 的评测节点，汇总各节点返回的报告
+    #[cfg(feature = "grpc")]
+    #[command(about = "Grade every submission on remote `serve` workers and aggregate the reports (instructor)")]
+    Coordinate {
+        #[arg(help = "Directory with one subdirectory of exercises per student")]
+        submissions: PathBuf,
+        #[arg(
+            long,
+            required = true,
+            value_name = "ADDR",
+            value_delimiter = ',',
+            help = "Addresses of the workers, e.g. grader1:50051,grader2:50051"
+        )]
+        workers: Vec<String>,
+        // 每个节点同时评测的提交数；节点自身按 serve --workers 限制并发，多出的请求在节点上排队
+        #[arg(
+            long,
+            default_value = "1",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Submissions sent to each worker at a time"
+        )]
+        slots: u32,
+        #[arg(long, help = "Directory for the students' reports [default: reports/submissions in the state directory]")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Manage installed courses")]
+    Courses {
+        #[command(subcommand)]
+        command: Option<CoursesCommand>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ReportCommand {
+    #[command(about = "Merge partial reports from CI shards")]
+    Merge {
+        #[arg(required = true)]
+        reports: Vec<String>,
+        #[arg(long, help = "Where to write the merged report (default: the state directory)")]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "Compare two reports (default: the latest archived report and the current one)")]
+    Diff {
+        #[arg(help = "Older report [default: the latest archived report]")]
+        old: Option<PathBuf>,
+        #[arg(help = "Newer report [default: the current report]")]
+        new: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CoursesCommand {
+    #[command(about = "List installed courses")]
+    List,
+}
+
+impl Mode {
+    // 子命令名称，用于错误信息
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Watch { .. } => "watch",
+            Mode::All { .. } => "all",
+            Mode::Run { .. } => "run",
+            Mode::List { .. } => "list",
+            Mode::Clean => "clean",
+            Mode::Report { .. } => "report",
+            Mode::Rerun { .. } => "rerun",
+            Mode::Replay { .. } => "replay",
+            Mode::Login => "login",
+            Mode::Submit { .. } => "submit",
+            Mode::Hint { .. } => "hint",
+            Mode::Show { .. } => "show",
+            Mode::Share { .. } => "share",
+            Mode::Validate => "validate",
+            Mode::Bundle => "bundle",
+            Mode::Diff { .. } => "diff",
+            Mode::Aggregate { .. } => "aggregate",
+            Mode::Similarity { .. } => "similarity",
+            Mode::Personalize { .. } => "personalize",
+            #[cfg(feature = "grpc")]
+            Mode::Serve { .. } => "serve",
+            #[cfg(feature = "grpc")]
+            Mode::Coordinate { .. } => "coordinate",
+            Mode::Courses { .. } => "courses",
+        }
+    }
+
+    // 评测模式的公共选项；其他子命令返回 None
+    pub fn grade_args(&self) -> Option<&GradeArgs> {
+        match self {
+            Mode::Watch { grade, .. } | Mode::All { grade, .. } | Mode::Run { grade, .. } => Some(grade),
+            _ => None,
+        }
+    }
+}
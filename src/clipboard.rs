@@ -0,0 +1,96 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// 各平台常见的剪贴板命令，按顺序尝试
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+    ("clip", &[]),
+];
+
+// 复制文本到系统剪贴板；找不到剪贴板命令时退回到终端的 OSC 52 转义序列
+pub fn copy(text: &str) -> Result<(), String> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(());
+        }
+    }
+    copy_osc52(text)
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} exited with {}", program, status))
+    }
+}
+
+// 多数现代终端（包括 SSH 会话）支持通过 OSC 52 写入剪贴板
+fn copy_osc52(text: &str) -> Result<(), String> {
+    let mut stdout = std::io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("Failed to write to the terminal clipboard: {}", e))
+}
+
+// 去掉 ANSI 颜色等控制序列
+pub fn strip_ansi(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            match chars.peek() {
+                Some('[') => {
+                    chars.next();
+                    // CSI 序列以 0x40..=0x7e 范围内的字符结束
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // 字符集切换（如 rustfmt 经 terminfo 输出的 ESC ( B），后面跟一个字符
+                Some('(' | ')') => {
+                    chars.next();
+                    chars.next();
+                }
+                _ => {}
+            }
+            continue;
+        }
+        result.push(ch);
+    }
+    result
+}
+
+fn base64(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,48 @@
+use serde::Deserialize;
+
+// clippy 的严格程度，可在 grader.toml 的 [clippy]（整个课程）、info.toml 的 [clippy]（一个章节）
+// 或习题元数据的 clippy 项中配置，越具体的配置整体覆盖越宽泛的配置；都未配置时按 clippy 的默认 lint 检查，
+// 只有默认即为 deny 的 lint 会判为失败
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ClippyConfig {
+    // 把所有警告视为错误（-D warnings），有任何警告即判为失败
+    pub deny_warnings: bool,
+    // 额外以警告级别启用的 lint 组，如 ["pedantic"]；可省略 clippy:: 前缀
+    pub groups: Vec<String>,
+    // 判为失败的 lint，如 ["clippy::unwrap_used"]
+    pub deny: Vec<String>,
+    // 不检查的 lint，如 ["clippy::needless_return", "dead_code"]，优先于 groups 和 deny_warnings
+    pub allow: Vec<String>,
+}
+
+impl ClippyConfig {
+    // 传给 clippy 的 lint 参数；后出现的参数优先，因此 allow 放在最后
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.deny_warnings {
+            args.extend(["-D".to_string(), "warnings".to_string()]);
+        }
+        for group in &self.groups {
+            let group = if group.contains("::") { group.clone() } else { format!("clippy::{}", group) };
+            args.extend(["-W".to_string(), group]);
+        }
+        for lint in &self.deny {
+            args.extend(["-D".to_string(), lint.clone()]);
+        }
+        for lint in &self.allow {
+            args.extend(["-A".to_string(), lint.clone()]);
+        }
+        args
+    }
+
+    // 失败时向学生说明检查标准，如 "-D warnings -W clippy::pedantic"
+    pub fn describe(&self) -> String {
+        let args = self.args();
+        if args.is_empty() {
+            "default lints; only lints that clippy denies by default fail the exercise".to_string()
+        } else {
+            args.join(" ")
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+// 构建脚本（build.rs）和过程宏会在编译期执行任意代码，该策略决定是否允许学生提交中出现它们
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompileTimePolicy {
+    #[default]
+    Allow,
+    // 发现构建脚本或过程宏时不编译，直接判为失败
+    Forbid,
+}
+
+// 找出 Cargo 项目中会在编译期执行的代码：包自身及其位于项目目录内的 path 依赖
+// 的构建脚本和过程宏 crate；返回每一处的说明
+pub fn find_compile_time_code(project_dir: &Path) -> Result<Vec<String>, String> {
+    let mut found = Vec::new();
+    let mut visited = Vec::new();
+    inspect(project_dir, project_dir, &mut visited, &mut found)?;
+    Ok(found)
+}
+
+fn inspect(root: &Path, crate_dir: &Path, visited: &mut Vec<PathBuf>, found: &mut Vec<String>) -> Result<(), String> {
+    let canonical = fs::canonicalize(crate_dir).map_err(|e| format!("Failed to read {}: {}", crate_dir.display(), e))?;
+    if visited.contains(&canonical) {
+        return Ok(());
+    }
+    visited.push(canonical);
+
+    let manifest_path = crate_dir.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+    let manifest: toml::Table = toml::from_str(&manifest)
+        .map_err(|e| format!("Failed to parse {}: {}", manifest_path.display(), e))?;
+
+    // 构建脚本：package.build 指定的文件，或未显式关闭时默认的 build.rs
+    let build = manifest.get("package").and_then(|package| package.get("build"));
+    let script = match build {
+        Some(toml::Value::String(script)) => Some(script.clone()),
+        Some(toml::Value::Boolean(false)) => None,
+        _ => crate_dir.join("build.rs").exists().then(|| "build.rs".to_string()),
+    };
+    if let Some(script) = script {
+        found.push(format!("build script {}", crate_dir.join(script).display()));
+    }
+
+    let proc_macro = manifest
+        .get("lib")
+        .and_then(|lib| lib.get("proc-macro").or_else(|| lib.get("proc_macro")))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    if proc_macro {
+        found.push(format!("proc-macro crate {}", crate_dir.display()));
+    }
+
+    // 学生可以修改的 path 依赖（位于项目目录内）也要检查
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(dependencies) = manifest.get(section).and_then(|deps| deps.as_table()) else {
+            continue;
+        };
+        for dependency in dependencies.values() {
+            let Some(path) = dependency.get("path").and_then(|path| path.as_str()) else {
+                continue;
+            };
+            let dependency_dir = crate_dir.join(path);
+            let inside = match (fs::canonicalize(&dependency_dir), fs::canonicalize(root)) {
+                (Ok(dependency_dir), Ok(root)) => dependency_dir.starts_with(root),
+                _ => false,
+            };
+            if inside {
+                inspect(root, &dependency_dir, visited, found)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use crate::hashing::content_hash;
+use crate::{ExerciseResult, ExerciseStatus};
+
+// 完成状态文件，位于课程仓库根目录，随仓库保存，换一台机器或清理状态目录后仍然保留
+pub const COMPLETION_FILE: &str = ".progress.json";
+
+// 各习题最近一次评测的完成状态，键为习题名
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Completion {
+    pub exercises: BTreeMap<String, ExerciseState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExerciseState {
+    pub status: ExerciseStatus,
+    // 评测时习题内容的哈希，习题之后被修改过时不再视为已完成
+    pub hash: String,
+    // 最近一次评测的时间（RFC 3339）
+    pub updated: String,
+}
+
+impl Completion {
+    // 读取完成状态，文件不存在时所有习题都未完成
+    pub fn load() -> Result<Completion, String> {
+        let path = Path::new(COMPLETION_FILE);
+        match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Completion::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(COMPLETION_FILE, content + "\n").map_err(|e| format!("Failed to write {}: {}", COMPLETION_FILE, e))
+    }
+
+    // 记录评测结果；结果的名称即习题路径
+    pub fn record<'a>(&mut self, results: impl IntoIterator<Item = &'a ExerciseResult>) {
+        let updated = OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default();
+        for result in results {
+            let Ok(hash) = content_hash(Path::new(&result.name)) else {
+                continue;
+            };
+            let state = ExerciseState { status: result.status, hash, updated: updated.clone() };
+            self.exercises.insert(result.name.clone(), state);
+        }
+    }
+
+    // 习题上次评测时通过，且之后没有被修改过
+    pub fn is_done(&self, exercise: &Path) -> bool {
+        self.exercises.get(&exercise.display().to_string()).is_some_and(|state| {
+            state.status == ExerciseStatus::Passed
+                && content_hash(exercise).is_ok_and(|hash| hash == state.hash)
+        })
+    }
+
+    // 已完成的习题数
+    pub fn done_count(&self, exercises: &[PathBuf]) -> usize {
+        exercises.iter().filter(|exercise| self.is_done(exercise)).count()
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use glob::Pattern;
+use serde::Deserialize;
+use crate::artifacts::ArtifactsConfig;
+use crate::classroom::ClassroomConfig;
+use crate::clippy::ClippyConfig;
+use crate::compile_time::CompileTimePolicy;
+use crate::deadline::DeadlineConfig;
+use crate::display::Theme;
+use crate::github::GithubConfig;
+use crate::hermetic::HermeticConfig;
+use crate::info;
+use crate::lti::LtiConfig;
+use crate::metadata::ExerciseMeta;
+use crate::paths;
+use crate::placeholders::PlaceholderConfig;
+use crate::roots::ExerciseRoot;
+use crate::share::ShareConfig;
+use crate::submit::ServerConfig;
+use crate::toolchain::ToolchainConfig;
+use crate::webhook::WebhookConfig;
+
+// 评测器配置文件，位于仓库根目录
+pub const CONFIG_FILE: &str = "grader.toml";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    // 不参与评测和清理的目录/文件，glob 语法，相对于 exercises 目录
+    // 以 `/` 结尾的模式只匹配目录；不含 `/` 的模式匹配任意层级的同名项
+    pub exclude: Vec<String>,
+    // 课程公共辅助 crate 的路径，相对于 exercises 目录
+    // 评测前统一编译一次，本身不参与评测和清理
+    pub shared_crates: Vec<String>,
+    // 逐题推送评测结果的 webhook，未配置时不推送
+    pub webhook: Option<WebhookConfig>,
+    // 课程服务器，用于登录和提交报告
+    pub server: Option<ServerConfig>,
+    // 参考答案目录（仅教师模式可用），结构与 exercises 目录一致
+    pub solutions_dir: String,
+    // 隐藏测试目录（仅教师模式注入），结构与 exercises 目录一致
+    pub hidden_tests_dir: String,
+    // 加密的参考答案与隐藏测试包，教师模式下用教师密钥解密
+    pub solutions_bundle: String,
+    // 未修改的模板占位符检查
+    pub placeholders: PlaceholderConfig,
+    // 可选的"未完成"标记（如 `I AM NOT DONE`），习题中保留该标记时即使能编译也视为进行中
+    pub not_done_marker: Option<String>,
+    // 分享失败日志用的粘贴服务，未配置时使用课程服务器
+    pub share: Option<ShareConfig>,
+    // 各习题的元数据，键为习题路径（如 exercises/panics/panic1.rs）
+    pub exercises: BTreeMap<String, ExerciseMeta>,
+    // 截止日期与迟交扣分策略，未配置时不扣分
+    pub deadline: Option<DeadlineConfig>,
+    // 课堂模式，配置后评测前校验提交目录的 git 状态
+    pub classroom: Option<ClassroomConfig>,
+    // 是否允许提交中包含构建脚本和过程宏（allow / forbid），习题元数据可单独覆盖
+    pub compile_time_code: CompileTimePolicy,
+    // 运行习题程序时隔离网络（Linux 上使用独立的网络命名空间），保证安全且结果可复现
+    pub isolate_network: bool,
+    // 在一次性的临时目录中运行习题程序（默认开启），程序的写入不会影响课程仓库和评测器状态；
+    // 需要在课程目录中运行的习题可在元数据中设为 false
+    pub isolate_fs: bool,
+    // 隔离文件系统时，把习题目录的只读副本放进临时目录，供程序读取数据文件
+    pub expose_exercise: bool,
+    // clippy 的严格程度（-D warnings、额外的 lint 组、允许的 lint），章节清单和习题元数据可单独覆盖
+    pub clippy: ClippyConfig,
+    // 评测后检查习题是否在课程目录中留下了允许列表之外的新文件，未配置时不检查
+    pub artifacts: Option<ArtifactsConfig>,
+    // 课程要求的编译器版本/通道，评测前检查，未配置时不检查
+    pub toolchain: Option<ToolchainConfig>,
+    // 报告和看板中数字、日期的语言环境（如 zh-CN、de），未配置时取 LANG 等环境变量
+    pub locale: Option<String>,
+    // 始终使用纯 ASCII 输出（同 --ascii），适合屏幕阅读器用户写在用户级配置中
+    pub ascii: bool,
+    // 状态符号主题：default、plain、emoji 或 nerd_font
+    pub theme: Theme,
+    // 学生姓名，记录在评测报告中
+    pub user_name: Option<String>,
+    // 多个习题根目录（如 exercises 和 challenges），未配置时只使用 exercises；
+    // 排除规则相对于各自的根目录；公共 crate、参考答案和隐藏测试只对应第一个根目录
+    pub roots: Vec<ExerciseRoot>,
+    // 每道习题的默认评测时限（秒），--timeout 和习题元数据可覆盖，未配置时不限时
+    pub timeout_secs: Option<u64>,
+    // 按进度解锁：学生模式下后面的习题在前置习题通过之前被锁定，不评测也不列出；解锁状态跨运行保存
+    pub progression: bool,
+    // 汇总班级成绩后通过 LTI 成绩服务回传到 LMS 成绩册，未配置时不回传
+    pub lti: Option<LtiConfig>,
+    // 在 GitHub Actions 中发布评测摘要：作业摘要和 PR 置顶评论
+    pub github: GithubConfig,
+    // Cargo 项目习题共用课程目录下 .grader-cache 中的构建缓存，公共依赖只编译一次，评测后保留供下次使用；
+    // 共用缓存的构建由 cargo 加锁依次进行
+    pub shared_target: bool,
+    // 批量评测前先把各 Cargo 项目习题依赖的并集编译到构建缓存中，习题的计时不包括首次编译依赖；需要 shared_target
+    pub warm_up: bool,
+    // cargo 通过 sccache 调用 rustc（需要已安装 sccache），批量评测许多学生的仓库时复用编译结果
+    pub sccache: bool,
+    // 每次评测后删除各习题目录中的 target 目录（不使用构建缓存时习题构建到这里）
+    pub clean_targets: bool,
+    // 每道习题评测后把编译器和 clippy 的诊断写入 .grader-cache/annotations 中的注解文件，供编辑器插件标出位置
+    pub annotations: bool,
+    // --hermetic 评测使用的容器引擎和基础镜像
+    pub hermetic: HermeticConfig,
+    // 保留的历史报告份数：每次完整评测前把上一份 report.json 移到 reports/archive，0 表示不归档
+    pub report_retention: usize,
+    // 保留运行记录（评测时终端上的全部输出，供 replay 重新显示）的运行次数，0 表示不记录
+    pub transcript_retention: usize,
+    // 按运行 ID 保留的运行清单份数（供 rerun <运行 ID> 重放较早的运行），0 表示只保留最近一次运行的清单
+    pub manifest_retention: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            exclude: vec![".git/".to_string(), "target/".to_string()],
+            shared_crates: Vec::new(),
+            webhook: None,
+            server: None,
+            solutions_dir: "solutions".to_string(),
+            hidden_tests_dir: "hidden_tests".to_string(),
+            solutions_bundle: "solutions.bundle".to_string(),
+            placeholders: PlaceholderConfig::default(),
+            not_done_marker: None,
+            share: None,
+            exercises: BTreeMap::new(),
+            deadline: None,
+            classroom: None,
+            compile_time_code: CompileTimePolicy::default(),
+            isolate_network: false,
+            isolate_fs: true,
+            expose_exercise: false,
+            clippy: ClippyConfig::default(),
+            artifacts: None,
+            toolchain: None,
+            locale: None,
+            ascii: false,
+            theme: Theme::default(),
+            user_name: None,
+            roots: Vec::new(),
+            timeout_secs: None,
+            progression: false,
+            lti: None,
+            github: GithubConfig::default(),
+            shared_target: true,
+            warm_up: true,
+            sccache: false,
+            clean_targets: false,
+            annotations: true,
+            hermetic: HermeticConfig::default(),
+            report_retention: 20,
+            transcript_retention: 20,
+            manifest_retention: 100,
+        }
+    }
+}
+
+impl Config {
+    // 读取配置文件；文件不存在时依次尝试用户配置目录中的默认配置和内置默认配置
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, String> {
+        let path = path.as_ref();
+        let fallback = paths::user_config_file();
+        for path in std::iter::once(path).chain(fallback.as_deref()) {
+            match fs::read_to_string(path) {
+                Ok(content) => {
+                    return toml::from_str(&content)
+                        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e));
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+            }
+        }
+        Ok(Config::default())
+    }
+
+    // 查找习题的元数据：grader.toml 中配置的项优先，未配置的项取习题的 exercise.toml 或所在目录的 info.toml，
+    // 都没有时返回默认值
+    pub fn exercise_meta(&self, exercise: &Path) -> ExerciseMeta {
+        let key = exercise.to_string_lossy().replace('\\', "/");
+        let meta = self.exercises.get(key.trim_end_matches('/')).cloned().unwrap_or_default();
+        meta.or(info::find_meta(exercise).unwrap_or_default())
+    }
+
+    // 是否有习题关闭了文件系统隔离（全局关闭或在习题元数据中关闭），在课程目录中运行
+    pub fn runs_outside_sandbox(&self) -> bool {
+        !self.isolate_fs || self.exercises.values().any(|meta| meta.isolate_fs == Some(false))
+    }
+
+    // 编译排除模式
+    pub fn exclusions(&self) -> Result<Exclusions, String> {
+        let mut patterns = Vec::new();
+        for raw in &self.exclude {
+            let dir_only = raw.ends_with('/');
+            let trimmed = raw.trim_end_matches('/');
+            let pattern = Pattern::new(trimmed)
+                .map_err(|e| format!("Invalid exclude pattern '{}': {}", raw, e))?;
+            patterns.push(ExcludePattern {
+                pattern,
+                dir_only,
+                anchored: trimmed.contains('/'),
+            });
+        }
+        // 公共 crate 同样不参与评测和清理
+        for shared in &self.shared_crates {
+            let trimmed = shared.trim_end_matches('/');
+            patterns.push(ExcludePattern {
+                pattern: Pattern::new(&Pattern::escape(trimmed))
+                    .map_err(|e| format!("Invalid shared crate path '{}': {}", shared, e))?,
+                dir_only: true,
+                anchored: true,
+            });
+        }
+        Ok(Exclusions { patterns })
+    }
+}
+
+struct ExcludePattern {
+    pattern: Pattern,
+    dir_only: bool,
+    anchored: bool,
+}
+
+// 已编译的排除模式集合
+#[derive(Default)]
+pub struct Exclusions {
+    patterns: Vec<ExcludePattern>,
+}
+
+impl Exclusions {
+    // 判断 root 下的 path 是否被排除
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let is_dir = path.is_dir();
+
+        self.patterns.iter().any(|p| {
+            if p.dir_only && !is_dir {
+                return false;
+            }
+            if p.anchored {
+                p.pattern.matches(&relative)
+            } else {
+                p.pattern.matches(&file_name)
+            }
+        })
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tonic::transport::Channel;
+use tonic::Code;
+use crate::grpc::proto::grader_client::GraderClient;
+use crate::grpc::proto::progress::Event;
+use crate::grpc::proto::{EvaluateSubmissionRequest, File};
+use crate::grpc::MAX_REQUEST_SIZE;
+use crate::{save_report_to_json, Report};
+
+// 同一份提交最多评测的次数：评测节点中途失联时提交回到队列，由其他节点重新评测
+const MAX_ATTEMPTS: usize = 3;
+// 上传提交时跳过的目录：构建产物和版本库不参与评测
+const SKIPPED_DIRS: [&str; 3] = ["target", ".git", ".grader-cache"];
+
+// 队列中的一份提交：submissions 目录下的一个子目录，目录名即学生名
+struct Job {
+    student: String,
+    dir: PathBuf,
+    attempts: usize,
+}
+
+// 各评测节点共享的任务队列和结果
+struct Queue {
+    jobs: VecDeque<Job>,
+    // 已保存的报告文件
+    reports: Vec<String>,
+    // 未能评测的学生及原因
+    failed: Vec<(String, String)>,
+}
+
+// 把 submissions_dir 下每名学生的提交分发给 workers 中的评测节点（cargotest serve），
+// 每个节点同时评测 slots 份；各学生的报告保存到 output_dir，返回报告文件列表
+pub fn coordinate(submissions_dir: &Path, workers: &[String], slots: usize, output_dir: &Path) -> Result<Vec<String>, String> {
+    let mut jobs: Vec<Job> = fs::read_dir(submissions_dir)
+        .map_err(|e| format!("Failed to read {}: {}", submissions_dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|dir| Job { student: dir.file_name().unwrap_or_default().to_string_lossy().to_string(), dir, attempts: 0 })
+        .collect();
+    if jobs.is_empty() {
+        return Err(format!("No submissions in {}", submissions_dir.display()));
+    }
+    jobs.sort_by(|a, b| a.student.cmp(&b.student));
+    fs::create_dir_all(output_dir).map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+
+    println!("Grading {} submission(s) on {} worker(s)", jobs.len(), workers.len());
+    let queue = Arc::new(Mutex::new(Queue { jobs: jobs.into(), reports: Vec::new(), failed: Vec::new() }));
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start the gRPC client: {}", e))?;
+    runtime.block_on(async {
+        let mut tasks = Vec::new();
+        for worker in workers {
+            let url = if worker.contains("://") { worker.clone() } else { format!("http://{}", worker) };
+            for _ in 0..slots {
+                tasks.push(tokio::spawn(run_worker(url.clone(), queue.clone(), output_dir.to_path_buf())));
+            }
+        }
+        for task in tasks {
+            let _ = task.await;
+        }
+    });
+
+    let mut queue = lock(&queue);
+    // 所有节点都失联后队列中剩下的提交
+    while let Some(job) = queue.jobs.pop_front() {
+        queue.failed.push((job.student, "no worker available".to_string()));
+    }
+    let mut reports = std::mem::take(&mut queue.reports);
+    reports.sort();
+    println!("\nGraded {} of {} submission(s)", reports.len(), reports.len() + queue.failed.len());
+    if queue.failed.is_empty() {
+        return Ok(reports);
+    }
+    for (student, reason) in &queue.failed {
+        eprintln!("  {}: {}", student, reason);
+    }
+    if reports.is_empty() {
+        return Err("No submission was graded".to_string());
+    }
+    eprintln!("Warning: the aggregate only includes the graded submissions");
+    Ok(reports)
+}
+
+// 一个评测名额：不断从队列中取提交交给 url 上的节点评测；节点不可用时停止，未评测完的提交回到队列
+async fn run_worker(url: String, queue: Arc<Mutex<Queue>>, output_dir: PathBuf) {
+    let mut client = match GraderClient::connect(url.clone()).await {
+        Ok(client) => client.max_decoding_message_size(MAX_REQUEST_SIZE),
+        Err(e) => {
+            eprintln!("Worker {} is unavailable: {}", url, e);
+            return;
+        }
+    };
+    loop {
+        let Some(mut job) = lock(&queue).jobs.pop_front() else {
+            return;
+        };
+        job.attempts += 1;
+        match grade(&mut client, &job).await {
+            Ok(report) => {
+                let file = output_dir.join(format!("{}.json", job.student));
+                let mut queue = lock(&queue);
+                match save_report_to_json(&file, &report) {
+                    Ok(()) => {
+                        println!(
+                            "  {}: {}/{} passed ({})",
+                            job.student, report.statistics.total_succeeds, report.statistics.total_exercations, url
+                        );
+                        queue.reports.push(file.display().to_string());
+                    }
+                    Err(e) => queue.failed.push((job.student, format!("Failed to write {}: {}", file.display(), e))),
+                }
+            }
+            // 节点失联：提交交给其他节点，本名额停止
+            Err(Failure::Worker(e)) => {
+                eprintln!("Worker {} failed while grading {}: {}", url, job.student, e);
+                let mut queue = lock(&queue);
+                if job.attempts < MAX_ATTEMPTS {
+                    queue.jobs.push_front(job);
+                } else {
+                    queue.failed.push((job.student, e));
+                }
+                return;
+            }
+            Err(Failure::Submission(e)) => lock(&queue).failed.push((job.student, e)),
+        }
+    }
+}
+
+// 评测失败的原因：节点失联，或提交本身无法评测
+enum Failure {
+    Worker(String),
+    Submission(String),
+}
+
+// 上传一份提交并等待评测完成，返回其报告
+async fn grade(client: &mut GraderClient<Channel>, job: &Job) -> Result<Report, Failure> {
+    let mut files = Vec::new();
+    collect_files(&job.dir, &job.dir, &mut files)
+        .map_err(|e| Failure::Submission(format!("Failed to read {}: {}", job.dir.display(), e)))?;
+    let request = EvaluateSubmissionRequest { submission: String::new(), student: job.student.clone(), files };
+    let status = |status: tonic::Status| match status.code() {
+        Code::Unavailable | Code::Unknown | Code::Cancelled | Code::Aborted => Failure::Worker(status.message().to_string()),
+        _ => Failure::Submission(status.message().to_string()),
+    };
+    let mut stream = client.evaluate_submission(request).await.map_err(status)?.into_inner();
+    while let Some(progress) = stream.message().await.map_err(status)? {
+        if let Some(Event::Finished(finished)) = progress.event {
+            return serde_json::from_str(&finished.report_json)
+                .map_err(|e| Failure::Submission(format!("Failed to parse the report: {}", e)));
+        }
+    }
+    Err(Failure::Worker("the worker closed the stream before grading finished".to_string()))
+}
+
+// 收集提交中的文件，路径相对于提交目录并以 / 分隔
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<File>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if !path.file_name().is_some_and(|name| SKIPPED_DIRS.iter().any(|skipped| name == *skipped)) {
+                collect_files(root, &path, files)?;
+            }
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            files.push(File { path: relative, content: fs::read(&path)? });
+        }
+    }
+    Ok(())
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use directories::BaseDirs;
+use crate::config::CONFIG_FILE;
+
+// 已安装课程的存放位置（相对于用户主目录），每门课程一个子目录，
+// 其中包含各自的 exercises 目录、grader.toml、运行清单和 .grader 状态目录
+const COURSES_DIR: &str = ".grader/courses";
+
+// 课程根目录 ~/.grader/courses
+pub fn courses_root() -> Result<PathBuf, String> {
+    BaseDirs::new()
+        .map(|dirs| dirs.home_dir().join(COURSES_DIR))
+        .ok_or_else(|| "Could not determine the home directory".to_string())
+}
+
+// 切换到指定课程的目录，之后的评测、状态和报告都相对于该目录
+pub fn enter(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("Invalid course name '{}'", name));
+    }
+    let dir = courses_root()?.join(name);
+    if !dir.is_dir() {
+        return Err(format!(
+            "Course '{}' is not installed (expected it at {}). Run 'courses list' to see installed courses",
+            name,
+            dir.display()
+        ));
+    }
+    env::set_current_dir(&dir).map_err(|e| format!("Failed to enter {}: {}", dir.display(), e))
+}
+
+// 列出已安装的课程
+pub fn list(exercises_dir: &str) -> Result<(), String> {
+    let root = courses_root()?;
+    let mut courses: Vec<PathBuf> = match fs::read_dir(&root) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect(),
+        Err(_) => Vec::new(),
+    };
+    courses.sort();
+    if courses.is_empty() {
+        println!("No courses installed in {}", root.display());
+        return Ok(());
+    }
+
+    let current = env::current_dir().ok().and_then(|dir| fs::canonicalize(dir).ok());
+    println!("Installed courses ({}):", root.display());
+    for course in courses {
+        let name = course.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut notes = Vec::new();
+        if !course.join(exercises_dir).is_dir() {
+            notes.push("no exercises directory");
+        }
+        if !course.join(CONFIG_FILE).exists() {
+            notes.push("no config");
+        }
+        if current.as_deref() == fs::canonicalize(&course).ok().as_deref() {
+            notes.push("current");
+        }
+        if notes.is_empty() {
+            println!("  {}", name);
+        } else {
+            println!("  {} ({})", name, notes.join(", "));
+        }
+    }
+    Ok(())
+}
+
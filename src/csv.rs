@@ -0,0 +1,67 @@
+use std::fmt::Write;
+use crate::report::ReportWriter;
+use crate::{ExerciseStatus, Report};
+
+// 每道习题一行的 CSV，便于在电子表格中汇总
+pub struct CsvWriter;
+
+impl ReportWriter for CsvWriter {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn extension(&self) -> &str {
+        "csv"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        render(report)
+    }
+}
+
+// 将评测报告导出为 CSV：每道习题一行，最后是汇总行；带上学生姓名一列，
+// 助教可以把多名学生的文件直接拼接后在电子表格中汇总
+pub fn render(report: &Report) -> String {
+    let student = report.user_name.as_deref().unwrap_or_default();
+    let mut csv = String::from("student,exercise,result,duration_seconds,error_category,error_code\n");
+    for exercise in &report.exercises {
+        let result = match exercise.status {
+            ExerciseStatus::Passed => "passed",
+            ExerciseStatus::Failed => "failed",
+            ExerciseStatus::InProgress => "in_progress",
+        };
+        let duration = exercise.duration_ms.map(|ms| format!("{:.3}", ms as f64 / 1000.0)).unwrap_or_default();
+        let category = exercise.reason.map(|reason| reason.label()).unwrap_or_default();
+        let code = exercise.code.map(|code| code.as_str()).unwrap_or_default();
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{}",
+            field(student),
+            field(&exercise.name),
+            result,
+            duration,
+            field(category),
+            code
+        );
+    }
+    // 汇总行：result 列为通过情况（不写成 6/12，避免被电子表格识别为日期），耗时为整次运行的耗时
+    let stats = &report.statistics;
+    let _ = writeln!(
+        csv,
+        "{},TOTAL,{} of {} passed,{},,",
+        field(student),
+        stats.total_succeeds,
+        stats.total_exercations,
+        stats.total_time
+    );
+    csv
+}
+
+// 含有逗号、引号或换行的字段用引号括起来，其中的引号写两遍
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
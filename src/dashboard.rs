@@ -0,0 +1,93 @@
+use std::fmt::Write;
+use crate::display::{self, Status};
+use crate::instructor::AggregateReport;
+use crate::locale::Locale;
+
+// 将班级汇总报告渲染为单个自包含的 HTML 页面（不依赖外部脚本和样式），数字和日期按语言环境格式化
+pub fn render(report: &AggregateReport, locale: Locale) -> String {
+    let stats = &report.statistics;
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"{}\">", locale.tag());
+    html.push_str(concat!(
+        "<head>\n<meta charset=\"utf-8\">\n",
+        "<title>Class dashboard</title>\n<style>\n",
+        "body { font-family: sans-serif; margin: 2em; color: #222; }\n",
+        "table { border-collapse: collapse; margin-bottom: 2em; }\n",
+        "th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: left; }\n",
+        ".bar { background: #4a90d9; height: 14px; }\n",
+        ".hard { color: #c0392b; }\n",
+        "</style>\n</head>\n<body>\n<h1>Class dashboard</h1>\n",
+    ));
+    let _ = writeln!(
+        html,
+        "<p>Generated <time datetime=\"{}\">{}</time></p>",
+        escape(&report.generated_at.value),
+        escape(&report.generated_at.display)
+    );
+
+    let _ = writeln!(html, "<h2>Statistics</h2>\n<table>");
+    let _ = writeln!(html, "<tr><th>Students</th><td>{}</td></tr>", stats.students);
+    let _ = writeln!(html, "<tr><th>Mean</th><td>{}</td></tr>", locale.percent(stats.mean, 1));
+    let _ = writeln!(html, "<tr><th>Median</th><td>{}</td></tr>", locale.percent(stats.median, 1));
+    let _ = writeln!(html, "<tr><th>Std. deviation</th><td>{}</td></tr>", locale.number(stats.stddev, 1));
+    html.push_str("</table>\n");
+
+    // 分数分布直方图，条形长度按人数最多的区间归一化
+    let max_count = stats.histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    html.push_str("<h2>Score distribution</h2>\n<table>\n<tr><th>Score</th><th>Students</th><th></th></tr>\n");
+    for bucket in &stats.histogram {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}-{}</td><td>{}</td><td><div class=\"bar\" style=\"width: {}px\"></div></td></tr>",
+            bucket.low,
+            bucket.high,
+            bucket.count,
+            bucket.count * 300 / max_count
+        );
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Exercises</h2>\n<table>\n<tr><th></th><th>Exercise</th><th>Passed</th><th>Attempted</th><th>Pass rate</th></tr>\n");
+    for exercise in &report.exercises {
+        let class = if stats.hardest_exercises.contains(&exercise.name) { " class=\"hard\"" } else { "" };
+        // 状态符号与终端使用同一主题：全部通过、无人通过、部分通过
+        let status = match exercise.passed {
+            passed if passed == exercise.attempted => Status::Pass,
+            0 => Status::Fail,
+            _ => Status::Partial,
+        };
+        let _ = writeln!(
+            html,
+            "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            display::symbol(status),
+            escape(&exercise.name),
+            exercise.passed,
+            exercise.attempted,
+            locale.percent(exercise.pass_rate * 100.0, 0)
+        );
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Students</h2>\n<table>\n<tr><th>Student</th><th>Passed</th><th>Total</th><th>Score</th></tr>\n");
+    for student in &report.students {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&student.student),
+            student.score,
+            student.total,
+            locale.percent(student.percent(), 1)
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+// 转义 HTML 特殊字符
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,142 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use crate::ExerciseResult;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+// 提交时间的来源
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSource {
+    // 最近一次修改 exercises 目录的 git 提交时间，不在 git 仓库中时退回文件修改时间
+    #[default]
+    Git,
+    // exercises 目录中最新的文件修改时间
+    Mtime,
+}
+
+// 截止日期与迟交扣分策略，在 grader.toml 的 [deadline] 中配置
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeadlineConfig {
+    // 截止时间，RFC 3339 格式，如 "2026-10-01T23:59:00+08:00"
+    pub due: String,
+    // 每迟交一天（不足一天按一天计）扣除的百分比
+    #[serde(default = "default_penalty_per_day")]
+    pub penalty_per_day: f64,
+    // 扣分上限（百分比）
+    #[serde(default = "default_max_penalty")]
+    pub max_penalty: f64,
+    #[serde(default)]
+    pub timestamp: TimestampSource,
+}
+
+fn default_penalty_per_day() -> f64 {
+    10.0
+}
+
+fn default_max_penalty() -> f64 {
+    100.0
+}
+
+// 报告中记录的迟交信息，同时保留原始分和扣分后的分数（均为百分制）
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LatePenalty {
+    pub due: String,
+    pub submitted_at: String,
+    pub days_late: u64,
+    pub penalty_percent: f64,
+    pub raw_score: f64,
+    pub adjusted_score: f64,
+}
+
+impl DeadlineConfig {
+    // 按提交时间计算本次评测的原始分和扣分后的分数
+    pub fn apply(&self, exercises_dir: &Path, exercises: &[ExerciseResult]) -> Result<LatePenalty, String> {
+        let due = OffsetDateTime::parse(&self.due, &Rfc3339)
+            .map_err(|e| format!("Invalid deadline '{}': {}", self.due, e))?;
+        let submitted = submission_time(exercises_dir, self.timestamp)?;
+
+        let late_seconds = submitted.unix_timestamp() - due.unix_timestamp();
+        let days_late = if late_seconds > 0 {
+            ((late_seconds + SECONDS_PER_DAY - 1) / SECONDS_PER_DAY) as u64
+        } else {
+            0
+        };
+        let penalty_percent = (days_late as f64 * self.penalty_per_day).min(self.max_penalty).max(0.0);
+        let raw_score = raw_score(exercises);
+        Ok(LatePenalty {
+            due: self.due.clone(),
+            submitted_at: submitted.format(&Rfc3339).unwrap_or_default(),
+            days_late,
+            penalty_percent,
+            raw_score,
+            adjusted_score: (raw_score * (100.0 - penalty_percent) / 100.0 * 10.0).round() / 10.0,
+        })
+    }
+}
+
+// 原始分：各习题得分的平均值；配置了评分细则的习题取细则得分，其余按通过与否记 100 或 0 分
+fn raw_score(exercises: &[ExerciseResult]) -> f64 {
+    if exercises.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = exercises
+        .iter()
+        .map(|e| e.score.unwrap_or(if e.result { 100.0 } else { 0.0 }))
+        .sum();
+    (total / exercises.len() as f64 * 10.0).round() / 10.0
+}
+
+// 确定提交时间
+fn submission_time(exercises_dir: &Path, source: TimestampSource) -> Result<OffsetDateTime, String> {
+    let seconds = match source {
+        TimestampSource::Git => match commit_time(exercises_dir) {
+            Some(seconds) => seconds,
+            None => latest_mtime(exercises_dir)?,
+        },
+        TimestampSource::Mtime => latest_mtime(exercises_dir)?,
+    };
+    OffsetDateTime::from_unix_timestamp(seconds).map_err(|e| format!("Invalid submission time: {}", e))
+}
+
+// 最近一次修改该目录的提交时间
+fn commit_time(dir: &Path) -> Option<i64> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%ct", "--"])
+        .arg(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+// 目录中最新的文件修改时间（跳过 target 目录）
+fn latest_mtime(dir: &Path) -> Result<i64, String> {
+    fn visit(dir: &Path, latest: &mut i64) -> std::io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                if path.file_name().map(|name| name != "target").unwrap_or(true) {
+                    visit(&path, latest)?;
+                }
+            } else if let Ok(modified) = entry.metadata()?.modified() {
+                if let Ok(since) = modified.duration_since(UNIX_EPOCH) {
+                    *latest = (*latest).max(since.as_secs() as i64);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let mut latest = 0;
+    visit(dir, &mut latest).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    Ok(latest)
+}
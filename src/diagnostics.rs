@@ -0,0 +1,124 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+// 编译器或 clippy 报告的一条诊断（错误或警告），位置为其主要代码片段
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    // error 或 warning
+    pub level: String,
+    // 错误码或 lint 名称，如 E0308、clippy::needless_return
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    // 相对于当前目录的源文件路径
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    // 编译器给出的完整文本（含代码片段、标注和帮助信息），与不使用 JSON 格式时输出的相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<String>,
+}
+
+// JSON 格式输出的解析结果
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub diagnostics: Vec<Diagnostic>,
+    // 各诊断的文本形式，与不带 --message-format=json 时编译器输出的内容相同
+    pub rendered: String,
+    // 不是 JSON 消息的输出，如 cargo test 中测试程序的输出
+    pub other: String,
+}
+
+// cargo --message-format=json 输出的一行消息
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RawDiagnostic>,
+}
+
+// rustc --error-format=json 输出的一条诊断
+#[derive(Deserialize)]
+struct RawDiagnostic {
+    message: String,
+    code: Option<RawCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RawSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RawSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+}
+
+// 解析 cargo --message-format=json 或 rustc --error-format=json 的输出；
+// 诊断中的文件路径相对于 base（cargo 为项目目录，rustc 为当前目录）
+pub fn parse(output: &str, base: &Path) -> Parsed {
+    let mut parsed = Parsed::default();
+    for line in output.lines() {
+        if !line.starts_with('{') {
+            parsed.other.push_str(line);
+            parsed.other.push('\n');
+            continue;
+        }
+        let raw = match serde_json::from_str::<CargoMessage>(line) {
+            Ok(message) if message.reason == "compiler-message" => message.message,
+            // 构建产物、构建脚本等其他 cargo 消息
+            Ok(_) => None,
+            Err(_) => match serde_json::from_str::<RawDiagnostic>(line) {
+                Ok(diagnostic) => Some(diagnostic),
+                Err(_) => {
+                    parsed.other.push_str(line);
+                    parsed.other.push('\n');
+                    None
+                }
+            },
+        };
+        let Some(raw) = raw else {
+            continue;
+        };
+        if let Some(rendered) = &raw.rendered {
+            parsed.rendered.push_str(rendered);
+        }
+        if let Some(diagnostic) = convert(raw, base) {
+            parsed.diagnostics.push(diagnostic);
+        }
+    }
+    parsed
+}
+
+// 只保留有源码位置的错误和警告；"aborting due to …" 之类的汇总信息没有位置
+fn convert(raw: RawDiagnostic, base: &Path) -> Option<Diagnostic> {
+    let level = match raw.level.as_str() {
+        "warning" => "warning",
+        level if level.starts_with("error") => "error",
+        _ => return None,
+    };
+    let span = raw.spans.iter().find(|span| span.is_primary)?;
+    let file = base.join(&span.file_name);
+    let file = file.strip_prefix(".").unwrap_or(&file);
+    Some(Diagnostic {
+        level: level.to_string(),
+        code: raw.code.map(|code| code.code),
+        message: raw.message,
+        file: file.display().to_string(),
+        line: span.line_start,
+        column: span.column_start,
+        end_line: span.line_end,
+        end_column: span.column_end,
+        rendered: raw.rendered,
+    })
+}
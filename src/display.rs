@@ -0,0 +1,127 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use serde::Deserialize;
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+// 状态符号主题，在 grader.toml 中用 theme = "emoji" 等选择，
+// 终端摘要、矩阵表格和 HTML 看板使用同一套符号
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    // 彩色文字加 ✓ / ✗
+    #[default]
+    Default,
+    // 只用文字
+    Plain,
+    // ✅ / ❌ / 🚧
+    Emoji,
+    // Nerd Font 图标，需要终端使用打过补丁的字体
+    NerdFont,
+}
+
+// 需要用符号表示的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Fail,
+    // 进行中或部分通过
+    Partial,
+    // 因前面的步骤失败而未执行
+    Skip,
+}
+
+// 状态的语义，决定终端中的颜色
+#[derive(Debug, Clone, Copy)]
+pub enum Tone {
+    Good,
+    Bad,
+    Warn,
+}
+
+// 开启纯 ASCII 模式（适合屏幕阅读器和不支持颜色的终端）：不输出颜色和 Unicode 符号，
+// 状态只用文字表示，表格使用 ASCII 边框
+pub fn set_ascii(enabled: bool) {
+    ASCII.store(enabled, Ordering::Relaxed);
+    if enabled {
+        colored::control::set_override(false);
+    }
+}
+
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+// 实际生效的主题；纯 ASCII 模式下总是只用文字
+pub fn theme() -> Theme {
+    if ascii() {
+        Theme::Plain
+    } else {
+        THEME.get().copied().unwrap_or_default()
+    }
+}
+
+// 状态符号
+pub fn symbol(status: Status) -> &'static str {
+    match (theme(), status) {
+        (Theme::Default, Status::Pass) => "✓",
+        (Theme::Default, Status::Fail) => "✗",
+        (Theme::Default, Status::Partial) => "~",
+        (Theme::Default, Status::Skip) => "-",
+        (Theme::Plain, Status::Pass) => "pass",
+        (Theme::Plain, Status::Fail) => "fail",
+        (Theme::Plain, Status::Partial) => "partial",
+        (Theme::Plain, Status::Skip) => "skipped",
+        (Theme::Emoji, Status::Pass) => "✅",
+        (Theme::Emoji, Status::Fail) => "❌",
+        (Theme::Emoji, Status::Partial) => "🚧",
+        (Theme::Emoji, Status::Skip) => "⏭️",
+        (Theme::NerdFont, Status::Pass) => "\u{f00c}",
+        (Theme::NerdFont, Status::Fail) => "\u{f00d}",
+        (Theme::NerdFont, Status::Partial) => "\u{f252}",
+        (Theme::NerdFont, Status::Skip) => "\u{f051}",
+    }
+}
+
+// 图标主题下放在状态行开头的图标（带空格）；文字主题下状态行本身已有文字，不加前缀
+pub fn prefix(status: Status) -> String {
+    match theme() {
+        Theme::Emoji | Theme::NerdFont => format!("{} ", symbol(status)),
+        Theme::Default | Theme::Plain => String::new(),
+    }
+}
+
+// 符号在终端中占的列数（emoji 占两列）
+pub fn symbol_width(status: Status) -> usize {
+    match theme() {
+        Theme::Emoji => 2,
+        _ => symbol(status).chars().count(),
+    }
+}
+
+// 按语义给文本上色；纯 ASCII 模式下原样返回，因此文本本身必须能说明状态
+pub fn paint(text: &str, tone: Tone) -> String {
+    if ascii() {
+        return text.to_string();
+    }
+    let code = match tone {
+        Tone::Good => 32,
+        Tone::Bad => 31,
+        Tone::Warn => 33,
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+// Unicode 符号在纯 ASCII 模式下的替代文本
+pub fn glyph(unicode: &'static str, plain: &'static str) -> &'static str {
+    if ascii() {
+        plain
+    } else {
+        unicode
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+use std::time::Instant;
+use crate::config::{Config, Exclusions, CONFIG_FILE};
+use crate::instructor::Role;
+use crate::output;
+use crate::precompile::Precompiled;
+use crate::roots::{self, ExerciseRoot};
+use crate::shared::SharedCrate;
+use crate::{clean_roots, collect_exercises, grade_exercise, record_result, scan_roots, ExerciseResult, Report, RunContext};
+
+// 一道习题的评测结果，与报告中的条目相同
+pub type EvaluationOutcome = ExerciseResult;
+
+// 一道习题：Cargo 项目目录或单个 .rs 文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exercise {
+    pub path: PathBuf,
+}
+
+impl Exercise {
+    pub fn new(path: impl Into<PathBuf>) -> Exercise {
+        Exercise { path: path.into() }
+    }
+
+    pub fn is_cargo_project(&self) -> bool {
+        self.path.is_dir()
+    }
+}
+
+// 供课程平台在程序中嵌入评测，不必调用命令行再解析输出：
+// 与 cargotest all 使用相同的配置和评测流程，结果以结构化的 EvaluationOutcome / Report 返回
+pub struct Evaluator {
+    ctx: RunContext,
+    exclusions: Exclusions,
+    roots: Vec<PathBuf>,
+}
+
+impl Evaluator {
+    // 读取当前目录的 grader.toml（不存在时使用默认配置），评测 exercises_dir 中的习题
+    pub fn new(exercises_dir: impl Into<PathBuf>) -> Result<Evaluator, String> {
+        Evaluator::with_config(Config::load(CONFIG_FILE)?, exercises_dir)
+    }
+
+    // 使用给定的配置评测 exercises_dir 中的习题；公共 crate 在这里预先编译
+    pub fn with_config(config: Config, exercises_dir: impl Into<PathBuf>) -> Result<Evaluator, String> {
+        let exercises_dir = exercises_dir.into();
+        let exclusions = config.exclusions()?;
+        let mut shared_crates = Vec::new();
+        for path in &config.shared_crates {
+            shared_crates.push(SharedCrate::prepare(exercises_dir.join(path))?);
+        }
+        let roots: Vec<ExerciseRoot> = roots::resolve(&[exercises_dir.display().to_string()], &[]);
+        let ctx = RunContext {
+            exercises_dir: exercises_dir.clone(),
+            roots,
+            metadata_dir: exercises_dir.clone(),
+            hidden_tests_dir: PathBuf::from(&config.hidden_tests_dir),
+            shared_crates,
+            webhook: None,
+            annotations: None,
+            shard: None,
+            filter: None,
+            replay_set: None,
+            timeout_secs: config.timeout_secs,
+            precompiled: Precompiled::default(),
+            config,
+            role: Role::Student,
+            verbose: false,
+        };
+        Ok(Evaluator { ctx, exclusions, roots: vec![exercises_dir] })
+    }
+
+    // 以教师身份评测，评测时注入隐藏测试
+    pub fn role(mut self, role: Role) -> Evaluator {
+        self.ctx.role = role;
+        self
+    }
+
+    // 从 dir 读取习题元数据（评测用例、生成器等），而不是从被评测的目录读取；
+    // 评测学生提交时指向课程自己的习题目录，提交中被改动的元数据不影响评测
+    pub fn metadata_dir(mut self, dir: impl Into<PathBuf>) -> Evaluator {
+        self.ctx.metadata_dir = dir.into();
+        self
+    }
+
+    // 是否打印评测过程的输出（默认打印）；关闭后错误输出仍记录在结果的 log 中
+    pub fn print_output(self, enabled: bool) -> Evaluator {
+        output::set_quiet(!enabled);
+        self
+    }
+
+    // 按评测顺序列出全部习题
+    pub fn exercises(&self) -> Result<Vec<Exercise>, String> {
+        let exercise_dirs = scan_roots(&self.roots, &self.exclusions)?;
+        Ok(collect_exercises(&self.ctx, exercise_dirs, &self.exclusions).into_iter().map(Exercise::new).collect())
+    }
+
+    // 评测一道习题
+    pub fn evaluate(&self, exercise: &Exercise) -> EvaluationOutcome {
+        grade_exercise(&self.ctx, &exercise.path, false)
+    }
+
+    // 依次评测全部习题，评测后清理各习题的 target 目录
+    pub fn evaluate_all(&self) -> Result<Report, String> {
+        let report = self.evaluate_with(&self.exercises()?, |_| true);
+        self.clean()?;
+        Ok(report)
+    }
+
+    // 依次评测给定的习题并汇总为报告；每道习题评测前后调用 on_event，
+    // 返回 false 时不再评测后续习题（如调用方已断开连接）
+    pub fn evaluate_with(&self, exercises: &[Exercise], mut on_event: impl FnMut(EvaluationEvent) -> bool) -> Report {
+        let started = Instant::now();
+        let mut report = Report::new(self.ctx.config.user_name.clone());
+        for (index, exercise) in exercises.iter().enumerate() {
+            if !on_event(EvaluationEvent::Started(index, exercise)) {
+                break;
+            }
+            let outcome = self.evaluate(exercise);
+            let keep_going = on_event(EvaluationEvent::Finished(index, &outcome));
+            record_result(&mut report, outcome);
+            if !keep_going {
+                break;
+            }
+        }
+        report.statistics.total_exercations = report.exercises.len();
+        report.statistics.total_time = started.elapsed().as_secs();
+        report
+    }
+
+    // 清理各习题的 target 目录
+    pub fn clean(&self) -> Result<(), String> {
+        clean_roots(&self.roots, &self.exclusions).map_err(|e| format!("Error cleaning target directories: {}", e))
+    }
+}
+
+// 评测过程中的事件，附带习题在本次评测中的序号
+pub enum EvaluationEvent<'a> {
+    Started(usize, &'a Exercise),
+    Finished(usize, &'a EvaluationOutcome),
+}
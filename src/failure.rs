@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::cases::{CaseResult, CaseStatus};
+use crate::diagnostics::Diagnostic;
+
+// 习题失败的主要原因，按评测流程中最先出错的一步确定
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    CompileError,
+    TestFailure,
+    Clippy,
+    Timeout,
+    RuntimeError,
+    // 残留占位符或"未完成"标记
+    Incomplete,
+    // 其他检查未通过，如禁止的构建脚本、沙箱外写文件、修改了公共 crate
+    Other,
+}
+
+// 稳定的失败代码，写入报告和控制台输出，脚本和教学平台可以据此区分失败类型而不必解析文字说明。
+// 按类别分段编号：01 编译、02 运行、03 测试与输出、04 lint、05 未完成、09 其他检查；
+// 段内留有空位供以后细分，已发布的代码不再改变含义
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FailureCode {
+    #[serde(rename = "GR0101")]
+    CompileError,
+    // 单文件习题中的 #[test] 函数无法编译
+    #[serde(rename = "GR0102")]
+    TestCompileError,
+    // 策略禁止的构建脚本或过程宏
+    #[serde(rename = "GR0103")]
+    CompileTimeCode,
+    #[serde(rename = "GR0201")]
+    RuntimeError,
+    #[serde(rename = "GR0202")]
+    MemoryLimit,
+    #[serde(rename = "GR0203")]
+    Timeout,
+    #[serde(rename = "GR0301")]
+    TestFailure,
+    // 没有按 expect_panic 的要求 panic
+    #[serde(rename = "GR0302")]
+    PanicMismatch,
+    // 输出比对类习题的答案错误
+    #[serde(rename = "GR0305")]
+    OutputMismatch,
+    #[serde(rename = "GR0401")]
+    Clippy,
+    // 残留未修改的模板占位符
+    #[serde(rename = "GR0501")]
+    Placeholder,
+    // 仍保留"未完成"标记
+    #[serde(rename = "GR0502")]
+    NotDone,
+    // 在沙箱外留下了允许列表之外的文件
+    #[serde(rename = "GR0901")]
+    Artifacts,
+    // 修改了公共 crate
+    #[serde(rename = "GR0902")]
+    SharedCrateModified,
+    #[serde(rename = "GR0999")]
+    Other,
+}
+
+impl FailureCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailureCode::CompileError => "GR0101",
+            FailureCode::TestCompileError => "GR0102",
+            FailureCode::CompileTimeCode => "GR0103",
+            FailureCode::RuntimeError => "GR0201",
+            FailureCode::MemoryLimit => "GR0202",
+            FailureCode::Timeout => "GR0203",
+            FailureCode::TestFailure => "GR0301",
+            FailureCode::PanicMismatch => "GR0302",
+            FailureCode::OutputMismatch => "GR0305",
+            FailureCode::Clippy => "GR0401",
+            FailureCode::Placeholder => "GR0501",
+            FailureCode::NotDone => "GR0502",
+            FailureCode::Artifacts => "GR0901",
+            FailureCode::SharedCrateModified => "GR0902",
+            FailureCode::Other => "GR0999",
+        }
+    }
+
+    // 代码所属的失败原因，用于汇总和下一步建议
+    pub fn reason(self) -> FailureReason {
+        match self {
+            FailureCode::CompileError | FailureCode::TestCompileError => FailureReason::CompileError,
+            FailureCode::RuntimeError | FailureCode::MemoryLimit => FailureReason::RuntimeError,
+            FailureCode::Timeout => FailureReason::Timeout,
+            FailureCode::TestFailure | FailureCode::PanicMismatch | FailureCode::OutputMismatch => FailureReason::TestFailure,
+            FailureCode::Clippy => FailureReason::Clippy,
+            FailureCode::Placeholder | FailureCode::NotDone => FailureReason::Incomplete,
+            FailureCode::CompileTimeCode | FailureCode::Artifacts | FailureCode::SharedCrateModified | FailureCode::Other => {
+                FailureReason::Other
+            }
+        }
+    }
+
+    // 输出比对类习题：取第一个失败用例的原因
+    pub fn from_cases(cases: &[CaseResult]) -> Option<FailureCode> {
+        cases.iter().find_map(|case| match case.status {
+            CaseStatus::Passed => None,
+            CaseStatus::WrongAnswer => Some(FailureCode::OutputMismatch),
+            CaseStatus::RuntimeError => Some(FailureCode::RuntimeError),
+            CaseStatus::TimedOut => Some(FailureCode::Timeout),
+        })
+    }
+}
+
+impl FailureReason {
+    // 下一步建议，{n} 为习题数量，{exercise} 为第一道此类失败的习题
+    fn next_step(self) -> &'static str {
+        match self {
+            FailureReason::CompileError => "{n} exercise(s) fail to compile — run `cargotest run {exercise}` to see the compiler errors",
+            FailureReason::TestFailure => "{n} exercise(s) fail their tests — run `cargotest run {exercise}` to see which assertions fail",
+            FailureReason::Clippy => "{n} exercise(s) have clippy warnings — run `cargotest run {exercise}` to see the lints",
+            FailureReason::Timeout => "{n} exercise(s) time out — look for infinite loops or blocking reads in {exercise}",
+            FailureReason::RuntimeError => "{n} exercise(s) crash at runtime — run `cargotest run {exercise}` to see the panic message",
+            FailureReason::Incomplete => "{n} exercise(s) are not finished yet — replace the placeholders in {exercise}",
+            FailureReason::Other => "{n} exercise(s) fail other checks — run `cargotest run {exercise}` for details",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureReason::CompileError => "compile error",
+            FailureReason::TestFailure => "test failure",
+            FailureReason::Clippy => "clippy",
+            FailureReason::Timeout => "timeout",
+            FailureReason::RuntimeError => "runtime error",
+            FailureReason::Incomplete => "incomplete",
+            FailureReason::Other => "other checks",
+        }
+    }
+}
+
+// 运行结束时根据失败原因给出下一步建议；多道失败习题集中在同一章节时建议监视该章节
+pub fn print_next_steps<'a>(failures: impl Iterator<Item = (&'a str, FailureReason)>, can_submit: bool) {
+    let mut groups: BTreeMap<FailureReason, Vec<&str>> = BTreeMap::new();
+    for (name, reason) in failures {
+        groups.entry(reason).or_default().push(name);
+    }
+    println!("\nNext steps:");
+    if groups.is_empty() {
+        if can_submit {
+            println!("  - All exercises pass — run `cargotest submit` to hand in your work");
+        } else {
+            println!("  - All exercises pass — nothing left to do");
+        }
+        return;
+    }
+    for (reason, names) in &groups {
+        let step = reason.next_step().replace("{n}", &names.len().to_string()).replace("{exercise}", names[0]);
+        println!("  - {}", step);
+    }
+    // 单文件习题失败最多的章节（Cargo 项目习题本身就是一个目录）
+    let mut chapters: BTreeMap<&Path, usize> = BTreeMap::new();
+    for name in groups.values().flatten().filter(|name| name.ends_with(".rs")) {
+        if let Some(chapter) = Path::new(name).parent() {
+            *chapters.entry(chapter).or_default() += 1;
+        }
+    }
+    if let Some((chapter, count)) = chapters.into_iter().max_by_key(|(_, count)| *count).filter(|(_, count)| *count > 1) {
+        println!(
+            "  - {} failing exercises are in {} — run `cargotest watch --chapter {}` to re-grade them as you edit",
+            count,
+            chapter.display(),
+            chapter.display()
+        );
+    }
+}
+
+// 一道失败的习题：习题名、失败代码和第一条编译错误
+pub type Failure<'a> = (&'a str, FailureCode, Option<&'a Diagnostic>);
+
+// 在总结中按失败原因分组列出失败的习题及其失败代码；有编译错误时附上第一条错误及其位置
+pub fn print_summary<'a>(failures: impl Iterator<Item = Failure<'a>>) {
+    let mut groups: BTreeMap<FailureReason, Vec<Failure>> = BTreeMap::new();
+    for (name, code, error) in failures {
+        groups.entry(code.reason()).or_default().push((name, code, error));
+    }
+    if groups.is_empty() {
+        return;
+    }
+    println!("Failures by reason:");
+    for (reason, names) in groups {
+        println!("  {} ({}):", reason.label(), names.len());
+        for (name, code, error) in names {
+            match error {
+                Some(error) => println!(
+                    "    - {} [{}]: {}{} at {}:{}:{}",
+                    name,
+                    code.as_str(),
+                    error.code.as_ref().map(|code| format!("[{}] ", code)).unwrap_or_default(),
+                    error.message,
+                    error.file,
+                    error.line,
+                    error.column
+                ),
+                None => println!("    - {} [{}]", name, code.as_str()),
+            }
+        }
+    }
+}
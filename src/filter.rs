@@ -0,0 +1,35 @@
+use std::path::Path;
+use glob::Pattern;
+use regex::Regex;
+
+// --filter：只评测路径匹配的习题；默认按 glob 匹配，以 `re:` 开头时按正则表达式搜索
+#[derive(Debug, Clone)]
+pub enum ExerciseFilter {
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl ExerciseFilter {
+    pub fn parse(value: &str) -> Result<ExerciseFilter, String> {
+        match value.strip_prefix("re:") {
+            Some(regex) => Regex::new(regex)
+                .map(ExerciseFilter::Regex)
+                .map_err(|e| format!("Invalid filter regex '{}': {}", regex, e)),
+            None => Pattern::new(value)
+                .map(ExerciseFilter::Glob)
+                .map_err(|e| format!("Invalid filter pattern '{}': {}", value, e)),
+        }
+    }
+
+    // 同时尝试完整路径和相对于习题目录的路径，`exercises/a/*` 和 `a/*` 都能使用
+    pub fn matches(&self, exercise: &Path, exercises_dir: &Path) -> bool {
+        let relative = exercise.strip_prefix(exercises_dir).unwrap_or(exercise);
+        [exercise, relative].iter().any(|path| {
+            let path = path.to_string_lossy().replace('\\', "/");
+            match self {
+                ExerciseFilter::Glob(pattern) => pattern.matches(&path),
+                ExerciseFilter::Regex(regex) => regex.is_match(&path),
+            }
+        })
+    }
+}
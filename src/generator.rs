@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::Deserialize;
+use crate::cases::TestCase;
+use crate::paths;
+use crate::process::{self, RunOptions};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GeneratorConfig {
+    // 输入生成程序的源码（单个 .rs 文件），相对于习题目录；运行时第一个参数为种子
+    pub source: String,
+    // 参考答案源码，相对于习题目录；未配置时使用 solutions 目录中对应的文件
+    pub reference: Option<String>,
+    // 生成的用例数量
+    #[serde(default = "default_count")]
+    pub count: u64,
+}
+
+fn default_count() -> u64 {
+    10
+}
+
+// 编译生成器和参考答案，按种子生成输入，并用参考答案的输出作为期望输出
+pub fn generate_cases(generator: &Path, reference: &Path, count: u64, seed: u64) -> Result<Vec<TestCase>, String> {
+    let build_dir = paths::scratch_dir("gen");
+    fs::create_dir_all(&build_dir).map_err(|e| format!("Failed to create {}: {}", build_dir.display(), e))?;
+    let generator_bin = compile(generator, &build_dir.join("generator"))?;
+    let reference_bin = compile(reference, &build_dir.join("reference"))?;
+
+    let mut cases = Vec::new();
+    for i in 0..count {
+        let case_seed = seed.wrapping_add(i);
+        let output = process::output(Command::new(&generator_bin).arg(case_seed.to_string()))
+            .map_err(|e| format!("Failed to run the input generator: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Input generator failed for seed {}:\n{}",
+                case_seed,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+        let input = String::from_utf8_lossy(&output.stdout).to_string();
+        let expected = run_with_input(&reference_bin, &input)
+            .map_err(|e| format!("Reference solution failed for seed {}: {}", case_seed, e))?;
+        cases.push(TestCase {
+            name: format!("generated #{} (seed {})", i + 1, case_seed),
+            input: Some(input),
+            expected: Some(expected),
+            ..TestCase::default()
+        });
+    }
+
+    let _ = fs::remove_dir_all(&build_dir);
+    Ok(cases)
+}
+
+fn compile(source: &Path, output: &Path) -> Result<PathBuf, String> {
+    let output = output.with_extension(std::env::consts::EXE_EXTENSION);
+    let result = process::output(Command::new("rustc").arg("-O").arg(source).arg("-o").arg(&output))
+        .map_err(|e| format!("Failed to execute rustc: {}", e))?;
+    if !result.status.success() {
+        return Err(format!(
+            "Failed to compile {}:\n{}",
+            source.display(),
+            String::from_utf8_lossy(&result.stderr)
+        ));
+    }
+    Ok(output)
+}
+
+fn run_with_input(binary: &Path, input: &str) -> Result<String, String> {
+    let measured = process::run_measured(
+        &mut Command::new(binary),
+        RunOptions {
+            stdin: Some(input.as_bytes()),
+            ..Default::default()
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    if !measured.output.status.success() {
+        return Err(String::from_utf8_lossy(&measured.output.stderr).to_string());
+    }
+    Ok(String::from_utf8_lossy(&measured.output.stdout).to_string())
+}
@@ -0,0 +1,125 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::json;
+use crate::markdown_report;
+use crate::Report;
+
+// GitHub Actions 中作业摘要文件的环境变量，写入其中的 Markdown 显示在运行的摘要页上
+pub const STEP_SUMMARY_ENV: &str = "GITHUB_STEP_SUMMARY";
+// 置顶评论中的标记，据此找到并更新上一次发布的评论，而不是每次运行都新增一条
+const COMMENT_MARKER: &str = "<!-- rust-basic-grader -->";
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct GithubConfig {
+    // 在 GitHub Actions 中运行时把 Markdown 摘要写入作业摘要
+    pub step_summary: bool,
+    // 在触发运行的 PR 上发布（或更新）一条置顶评论，需要具有 pull-requests: write 权限的令牌
+    pub pr_comment: bool,
+    // 保存 API 令牌的环境变量，工作流中需要显式传入，如 env: { GITHUB_TOKEN: ${{ secrets.GITHUB_TOKEN }} }
+    pub token_env: String,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        GithubConfig {
+            step_summary: true,
+            pr_comment: false,
+            token_env: "GITHUB_TOKEN".to_string(),
+        }
+    }
+}
+
+// 在 GitHub Actions 中运行时发布评测摘要；不在 Actions 中时什么也不做
+pub fn publish(report: &Report, config: &GithubConfig) {
+    if env::var_os("GITHUB_ACTIONS").is_none() {
+        return;
+    }
+    let summary = markdown_report::render(report);
+    if config.step_summary {
+        if let Err(e) = write_step_summary(&summary) {
+            eprintln!("Error writing the job summary: {}", e);
+        }
+    }
+    if config.pr_comment {
+        match post_pr_comment(&summary, config) {
+            Ok(Some(url)) => println!("Posted the grading summary to {}", url),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error posting the PR comment: {}", e),
+        }
+    }
+}
+
+// 追加到作业摘要文件；同一作业中的各步骤共用这个文件
+fn write_step_summary(summary: &str) -> Result<(), String> {
+    let Some(path) = env::var_os(STEP_SUMMARY_ENV).filter(|path| !path.is_empty()) else {
+        return Ok(());
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.to_string_lossy(), e))?;
+    writeln!(file, "{}", summary).map_err(|e| format!("Failed to write {}: {}", path.to_string_lossy(), e))
+}
+
+// 发布或更新置顶评论，返回评论地址；不是由 PR 触发的运行没有可评论的 PR，返回 None
+fn post_pr_comment(summary: &str, config: &GithubConfig) -> Result<Option<String>, String> {
+    let Some(number) = pull_request_number()? else {
+        return Ok(None);
+    };
+    let token = env::var(&config.token_env)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| format!("{} is not set; pass the workflow token to the grading step", config.token_env))?;
+    let repository = env::var("GITHUB_REPOSITORY").map_err(|_| "GITHUB_REPOSITORY is not set".to_string())?;
+    let api = env::var("GITHUB_API_URL").unwrap_or_else(|_| DEFAULT_API_URL.to_string());
+    let body = json!({ "body": format!("{}\n{}", COMMENT_MARKER, summary) });
+
+    let comments = format!("{}/repos/{}/issues/{}/comments", api, repository, number);
+    let existing: Vec<Comment> = request(ureq::get(&format!("{}?per_page=100", comments)), &token)
+        .call()
+        .map_err(|e| format!("Failed to list comments on #{}: {}", number, e))?
+        .into_json()
+        .map_err(|e| format!("Failed to parse comments on #{}: {}", number, e))?;
+    let response = match existing.iter().find(|comment| comment.body.contains(COMMENT_MARKER)) {
+        Some(comment) => request(ureq::patch(&format!("{}/repos/{}/issues/comments/{}", api, repository, comment.id)), &token)
+            .send_json(body),
+        None => request(ureq::post(&comments), &token).send_json(body),
+    }
+    .map_err(|e| format!("Failed to comment on #{}: {}", number, e))?;
+    let comment: Comment = response.into_json().map_err(|e| format!("Failed to parse the comment: {}", e))?;
+    Ok(Some(comment.html_url))
+}
+
+#[derive(Deserialize)]
+struct Comment {
+    id: u64,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    html_url: String,
+}
+
+fn request(request: ureq::Request, token: &str) -> ureq::Request {
+    request
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Accept", "application/vnd.github+json")
+        .set("X-GitHub-Api-Version", "2022-11-28")
+        .timeout(Duration::from_secs(10))
+}
+
+// 从触发运行的事件中读取 PR 编号
+fn pull_request_number() -> Result<Option<u64>, String> {
+    let Some(path) = env::var_os("GITHUB_EVENT_PATH") else {
+        return Ok(None);
+    };
+    let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.to_string_lossy(), e))?;
+    let event: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.to_string_lossy(), e))?;
+    Ok(event.pointer("/pull_request/number").and_then(|number| number.as_u64()))
+}
@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use crate::config::Config;
+use crate::diagnostics::Diagnostic;
+use crate::evaluator::{EvaluationEvent, Evaluator};
+use crate::instructor::Role;
+use crate::watcher::Watcher;
+use crate::{build_cache, new_run_id, paths, ExerciseResult, ExerciseStatus};
+
+// 由 proto/grader.proto 生成的消息和服务
+pub mod proto {
+    tonic::include_proto!("grader.v1");
+}
+
+use proto::grader_server::{Grader, GraderServer};
+use proto::progress::Event;
+use proto::{DiagnosticsUpdate, EvaluateExerciseRequest, EvaluateSubmissionRequest, Progress, WatchDiagnosticsRequest};
+
+// 评测进度流中缓冲的消息数，客户端读取得慢时评测线程在此等待
+const PROGRESS_BUFFER: usize = 16;
+// 上传的提交在状态目录中的临时位置，评测结束后删除
+const UPLOAD_DIR: &str = "uploads";
+// 请求的大小上限，上传的提交包含习题目录中的全部文件，比 gRPC 默认的 4 MB 大
+pub const MAX_REQUEST_SIZE: usize = 64 * 1024 * 1024;
+
+// 本进程中上传的提交数，与运行 ID 一起构成临时目录名
+static UPLOADS: AtomicUsize = AtomicUsize::new(0);
+
+// gRPC 评测服务：最多同时执行 workers 个评测任务，其余请求排队等待
+pub struct GraderService {
+    config: Config,
+    role: Role,
+    // 请求未指定习题目录时使用的目录
+    exercises_dir: PathBuf,
+    // 请求可以指定的习题目录所在的目录（已规范化）：服务器的习题目录和 --allow-dir 指定的目录
+    allowed_dirs: Vec<PathBuf>,
+    workers: Arc<Semaphore>,
+}
+
+// 在 addr 上提供评测服务，直到进程被结束；请求中的 submission 只能是习题目录或 allow_dirs 中的目录及其子目录，
+// 评测会执行其中的构建脚本和测试，不能让客户端指定服务器上的任意目录
+pub fn serve(
+    addr: SocketAddr,
+    workers: usize,
+    config: Config,
+    role: Role,
+    exercises_dir: PathBuf,
+    allow_dirs: &[PathBuf],
+) -> Result<(), String> {
+    let mut allowed_dirs = Vec::new();
+    for dir in std::iter::once(&exercises_dir).chain(allow_dirs) {
+        match fs::canonicalize(dir) {
+            Ok(dir) => allowed_dirs.push(dir),
+            // 服务器的习题目录可以不存在（只评测上传的提交），--allow-dir 指定的目录必须存在
+            Err(_) if dir == &exercises_dir => {}
+            Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+        }
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start the gRPC server: {}", e))?;
+    let service = GraderService { config, role, exercises_dir, allowed_dirs, workers: Arc::new(Semaphore::new(workers)) };
+    println!("Serving the grader on {} with {} worker(s)", addr, workers);
+    let server = GraderServer::new(service).max_decoding_message_size(MAX_REQUEST_SIZE);
+    runtime
+        .block_on(tonic::transport::Server::builder().add_service(server).serve(addr))
+        .map_err(|e| format!("gRPC server error on {}: {}", addr, e))
+}
+
+impl GraderService {
+    // 请求中的习题目录，为空时使用服务器的习题目录；不在允许的目录中的路径（包括经符号链接指到外面的）被拒绝
+    fn submission_dir(&self, submission: &str) -> Result<PathBuf, Status> {
+        let dir = match submission {
+            "" => self.exercises_dir.clone(),
+            submission => PathBuf::from(submission),
+        };
+        let canonical = fs::canonicalize(&dir)
+            .ok()
+            .filter(|canonical| canonical.is_dir())
+            .ok_or_else(|| Status::not_found(format!("{} is not a directory", dir.display())))?;
+        if !self.allowed_dirs.iter().any(|allowed| canonical.starts_with(allowed)) {
+            return Err(Status::permission_denied(format!(
+                "{} is outside the exercise directory; start the server with --allow-dir to grade it",
+                dir.display()
+            )));
+        }
+        Ok(dir)
+    }
+}
+
+#[tonic::async_trait]
+impl Grader for GraderService {
+    async fn evaluate_exercise(
+        &self,
+        request: Request<EvaluateExerciseRequest>,
+    ) -> Result<Response<proto::ExerciseResult>, Status> {
+        let request = request.into_inner();
+        let dir = self.submission_dir(&request.submission)?;
+        let permit = self.workers.clone().acquire_owned().await.map_err(|e| Status::unavailable(e.to_string()))?;
+        let (config, role, exercises_dir) = (self.config.clone(), self.role, self.exercises_dir.clone());
+        // 评测是阻塞的，在单独的线程中执行；客户端断开后评测仍会完成，占用的名额随之释放
+        let result = tokio::task::spawn_blocking(move || -> Result<_, Status> {
+            let _permit = permit;
+            let evaluator = evaluator(config, role, &dir, &exercises_dir)?;
+            let path = dir.join(&request.exercise);
+            let exercise = evaluator
+                .exercises()
+                .map_err(Status::internal)?
+                .into_iter()
+                .find(|exercise| exercise.path == path)
+                .ok_or_else(|| Status::not_found(format!("No exercise {} in {}", request.exercise, dir.display())))?;
+            let outcome = evaluator.evaluate(&exercise);
+            println!("{}: {}", outcome.name, status_name(outcome.status));
+            Ok(exercise_result(&outcome, outcome.name.clone()))
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))??;
+        Ok(Response::new(result))
+    }
+
+    type EvaluateSubmissionStream = ReceiverStream<Result<Progress, Status>>;
+
+    async fn evaluate_submission(
+        &self,
+        request: Request<EvaluateSubmissionRequest>,
+    ) -> Result<Response<Self::EvaluateSubmissionStream>, Status> {
+        let request = request.into_inner();
+        // 上传的提交在取得评测名额后才写入临时目录
+        let dir = match request.files.is_empty() {
+            true => Some(self.submission_dir(&request.submission)?),
+            false => None,
+        };
+        let (tx, rx) = mpsc::channel(PROGRESS_BUFFER);
+        let workers = self.workers.clone();
+        let (config, role, exercises_dir) = (self.config.clone(), self.role, self.exercises_dir.clone());
+        tokio::spawn(async move {
+            if workers.available_permits() == 0 {
+                let _ = tx.send(Ok(progress(Event::Queued(proto::Queued {})))).await;
+            }
+            let Ok(permit) = workers.acquire_owned().await else {
+                return;
+            };
+            let _ = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                let submission = match dir {
+                    Some(dir) => Ok(Submission { dir, upload: None }),
+                    None => Submission::upload(&request.files, &exercises_dir),
+                };
+                let result = submission
+                    .and_then(|submission| grade_submission(config, role, &submission, &exercises_dir, &request.student, &tx));
+                if let Err(status) = result {
+                    let _ = tx.blocking_send(Err(status));
+                }
+            })
+            .await;
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    type WatchDiagnosticsStream = ReceiverStream<Result<DiagnosticsUpdate, Status>>;
+
+    async fn watch_diagnostics(
+        &self,
+        request: Request<WatchDiagnosticsRequest>,
+    ) -> Result<Response<Self::WatchDiagnosticsStream>, Status> {
+        let request = request.into_inner();
+        let dir = self.submission_dir(&request.submission)?;
+        let (tx, rx) = mpsc::channel(PROGRESS_BUFFER);
+        let workers = self.workers.clone();
+        let (config, role, exercises_dir) = (self.config.clone(), self.role, self.exercises_dir.clone());
+        // 监视在单独的线程中进行，只在每次评测时占用评测名额
+        tokio::task::spawn_blocking(move || {
+            let result = evaluator(config, role, &dir, &exercises_dir)
+                .and_then(|evaluator| watch_exercise(&evaluator, &dir, &request.path, &workers, &tx));
+            if let Err(status) = result {
+                let _ = tx.blocking_send(Err(status));
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+// 评测的习题目录：服务器上的目录，或上传到服务器的提交
+struct Submission {
+    dir: PathBuf,
+    upload: Option<Upload>,
+}
+
+// 上传的提交所在的临时目录 uploads/<id>/<习题目录名>，评测结束后删除
+struct Upload {
+    root: PathBuf,
+    // 服务器的习题目录，报告中的习题名以它为准
+    exercises_dir: PathBuf,
+}
+
+impl Submission {
+    // 把上传的文件写入临时目录；习题目录名与服务器的习题目录相同，报告中习题所属的根目录也就相同
+    fn upload(files: &[proto::File], exercises_dir: &Path) -> Result<Submission, Status> {
+        let id = format!("{}-{}", new_run_id(), UPLOADS.fetch_add(1, Ordering::Relaxed));
+        let root = paths::state_file(UPLOAD_DIR).join(id);
+        let name = exercises_dir.file_name().unwrap_or("exercises".as_ref());
+        let upload = Upload { root: root.clone(), exercises_dir: exercises_dir.to_path_buf() };
+        let dir = root.join(name);
+        for file in files {
+            let relative = Path::new(&file.path);
+            if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+                return Err(Status::invalid_argument(format!("Invalid file path in the submission: {}", file.path)));
+            }
+            let path = dir.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| Status::internal(format!("Failed to create {}: {}", parent.display(), e)))?;
+            }
+            fs::write(&path, &file.content).map_err(|e| Status::internal(format!("Failed to write {}: {}", path.display(), e)))?;
+        }
+        Ok(Submission { dir, upload: Some(upload) })
+    }
+
+    // 报告中的习题名：上传的提交换回服务器习题目录中的路径，与学生在本地评测时的报告一致
+    fn name(&self, path: &Path) -> String {
+        match &self.upload {
+            Some(upload) => match path.strip_prefix(&self.dir) {
+                Ok(relative) => upload.exercises_dir.join(relative).display().to_string(),
+                Err(_) => path.display().to_string(),
+            },
+            None => path.display().to_string(),
+        }
+    }
+}
+
+impl Drop for Submission {
+    fn drop(&mut self) {
+        let Some(upload) = &self.upload else {
+            return;
+        };
+        // 构建缓存中按习题路径存放的最终产物也一并删除，否则每次上传都会留下一份
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                let _ = fs::remove_dir_all(build_cache::target_dir(&entry.path()));
+            }
+        }
+        let _ = fs::remove_dir_all(&upload.root);
+    }
+}
+
+// 评测一份提交中的全部习题，通过 tx 发送进度；客户端断开后不再评测后续习题
+fn grade_submission(
+    config: Config,
+    role: Role,
+    submission: &Submission,
+    exercises_dir: &Path,
+    student: &str,
+    tx: &mpsc::Sender<Result<Progress, Status>>,
+) -> Result<(), Status> {
+    let clean_targets = config.clean_targets;
+    let evaluator = evaluator(config, role, &submission.dir, exercises_dir)?;
+    let exercises = evaluator.exercises().map_err(Status::internal)?;
+    let send = |event| tx.blocking_send(Ok(progress(event))).is_ok();
+    send(Event::Started(proto::Started { total: exercises.len() as u32 }));
+    let mut report = evaluator.evaluate_with(&exercises, |event| match event {
+        EvaluationEvent::Started(index, exercise) => send(Event::ExerciseStarted(proto::ExerciseStarted {
+            name: submission.name(&exercise.path),
+            index: index as u32,
+        })),
+        EvaluationEvent::Finished(_, outcome) => {
+            send(Event::ExerciseFinished(exercise_result(outcome, submission.name(Path::new(&outcome.name)))))
+        }
+    });
+    if clean_targets {
+        evaluator.clean().map_err(Status::internal)?;
+    }
+    for exercise in &mut report.exercises {
+        exercise.name = submission.name(Path::new(&exercise.name));
+    }
+    if !student.is_empty() {
+        report.user_name = Some(student.to_string());
+    }
+    println!(
+        "{}: {} passed, {} failed",
+        if student.is_empty() { submission.dir.display().to_string() } else { student.to_string() },
+        report.statistics.total_succeeds,
+        report.statistics.total_failures
+    );
+    let report_json = serde_json::to_string(&report).map_err(|e| Status::internal(e.to_string()))?;
+    send(Event::Finished(proto::Finished {
+        passed: report.statistics.total_succeeds as u32,
+        failed: report.statistics.total_failures as u32,
+        report_json,
+    }));
+    Ok(())
+}
+
+// 监视 dir 中包含 path 的习题，每次评测后通过 tx 推送诊断，直到客户端断开
+fn watch_exercise(
+    evaluator: &Evaluator,
+    dir: &Path,
+    path: &str,
+    workers: &Semaphore,
+    tx: &mpsc::Sender<Result<DiagnosticsUpdate, Status>>,
+) -> Result<(), Status> {
+    let target = dir.join(path);
+    let exercise = evaluator
+        .exercises()
+        .map_err(Status::internal)?
+        .into_iter()
+        .find(|exercise| target.starts_with(&exercise.path))
+        .ok_or_else(|| Status::not_found(format!("{} is not in any exercise in {}", path, dir.display())))?;
+    // 单文件习题监视所在的目录：编辑器保存时常常写入临时文件再改名，只监视文件本身会丢失之后的变化
+    let watched = match exercise.is_cargo_project() {
+        true => exercise.path.clone(),
+        false => exercise.path.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let mut watcher = Watcher::new(&[watched]).map_err(Status::internal)?;
+    let runtime = tokio::runtime::Handle::current();
+    // 上次推送时有诊断的文件
+    let mut published = HashSet::new();
+    loop {
+        let outcome = {
+            let _permit = runtime.block_on(workers.acquire()).map_err(|e| Status::unavailable(e.to_string()))?;
+            evaluator.evaluate(&exercise)
+        };
+        println!("{}: {} (watching)", outcome.name, status_name(outcome.status));
+        if tx.blocking_send(Ok(diagnostics_update(&outcome, &mut published))).is_err() {
+            return Ok(());
+        }
+        // 忽略评测过程本身造成的变化，只在习题的文件被保存后重新评测
+        watcher.refresh();
+        loop {
+            let Some(changed) = watcher.wait_for_changes_until(|| tx.is_closed()) else {
+                return Ok(());
+            };
+            if changed.iter().any(|changed| changed.starts_with(&exercise.path)) {
+                break;
+            }
+        }
+    }
+}
+
+// 按文件分组的 LSP 诊断；上次有诊断而这次没有的文件给出空列表
+fn diagnostics_update(outcome: &ExerciseResult, published: &mut HashSet<String>) -> DiagnosticsUpdate {
+    let mut files: BTreeMap<String, Vec<proto::Diagnostic>> = BTreeMap::new();
+    for diagnostic in &outcome.diagnostics {
+        files.entry(file_uri(Path::new(&diagnostic.file))).or_default().push(lsp_diagnostic(diagnostic));
+    }
+    for uri in published.drain() {
+        files.entry(uri).or_default();
+    }
+    published.extend(files.iter().filter(|(_, diagnostics)| !diagnostics.is_empty()).map(|(uri, _)| uri.clone()));
+    DiagnosticsUpdate {
+        result: Some(exercise_result(outcome, outcome.name.clone())),
+        files: files
+            .into_iter()
+            .map(|(uri, diagnostics)| proto::PublishDiagnosticsParams { uri, diagnostics })
+            .collect(),
+    }
+}
+
+// LSP 的行列号从 0 开始，诊断中的从 1 开始
+fn lsp_diagnostic(diagnostic: &Diagnostic) -> proto::Diagnostic {
+    let position = |line: usize, column: usize| proto::Position {
+        line: line.saturating_sub(1) as u32,
+        character: column.saturating_sub(1) as u32,
+    };
+    let code = diagnostic.code.clone().unwrap_or_default();
+    proto::Diagnostic {
+        range: Some(proto::Range {
+            start: Some(position(diagnostic.line, diagnostic.column)),
+            end: Some(position(diagnostic.end_line, diagnostic.end_column)),
+        }),
+        severity: match diagnostic.level.as_str() {
+            "error" => 1,
+            "warning" => 2,
+            _ => 3,
+        },
+        source: if code.starts_with("clippy::") { "clippy" } else { "rustc" }.to_string(),
+        code,
+        message: diagnostic.message.clone(),
+    }
+}
+
+// 诊断中的路径（相对于当前目录）对应的 file:// URI
+fn file_uri(path: &Path) -> String {
+    let path = std::env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    // Windows 的路径以盘符开头
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => uri.push(byte as char),
+            _ => {
+                let _ = write!(uri, "%{:02X}", byte);
+            }
+        }
+    }
+    uri
+}
+
+// 评测 dir 中习题的评测器，习题元数据取自服务器的习题目录；多个请求同时评测，不打印评测过程的输出
+fn evaluator(config: Config, role: Role, dir: &Path, exercises_dir: &Path) -> Result<Evaluator, Status> {
+    Evaluator::with_config(config, dir)
+        .map(|evaluator| evaluator.role(role).metadata_dir(exercises_dir).print_output(false))
+        .map_err(Status::failed_precondition)
+}
+
+fn progress(event: Event) -> Progress {
+    Progress { event: Some(event) }
+}
+
+// 转换为 gRPC 消息，name 为报告中的习题名
+fn exercise_result(result: &ExerciseResult, name: String) -> proto::ExerciseResult {
+    let status = match result.status {
+        ExerciseStatus::Passed => proto::Status::Passed,
+        ExerciseStatus::Failed => proto::Status::Failed,
+        ExerciseStatus::InProgress => proto::Status::InProgress,
+    };
+    let mut json = serde_json::to_value(result).unwrap_or_default();
+    json["name"] = name.clone().into();
+    proto::ExerciseResult {
+        name,
+        status: status as i32,
+        code: result.code.map(|code| code.as_str().to_string()).unwrap_or_default(),
+        points: result.points,
+        max_points: result.max_points,
+        json: json.to_string(),
+    }
+}
+
+fn status_name(status: ExerciseStatus) -> &'static str {
+    match status {
+        ExerciseStatus::Passed => "passed",
+        ExerciseStatus::Failed => "failed",
+        ExerciseStatus::InProgress => "in progress",
+    }
+}
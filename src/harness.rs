@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::diagnostics::{self, Diagnostic};
+use crate::paths;
+use crate::process;
+
+// 习题中一个测试（#[test] 函数或 Cargo 项目的文档测试）的结果
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestStatus,
+}
+
+// 一次测试运行的结果
+pub struct TestRun {
+    pub tests: Vec<TestResult>,
+    // 测试程序的完整输出，包含失败测试的 panic 信息
+    pub output: String,
+    pub passed: bool,
+}
+
+// 源码中是否有 #[test] 函数
+pub fn has_tests(exercise_file: &Path) -> bool {
+    fs::read_to_string(exercise_file).map(|source| source.contains("#[test]")).unwrap_or(false)
+}
+
+// 用 rustc --test 把单文件习题编译为测试程序并运行其中的 #[test] 函数；
+// 编译器的诊断（编译成功时为警告）追加到 diagnostics，编译失败时返回诊断的文本形式
+pub fn run_tests(exercise_file: &Path, diagnostics: &mut Vec<Diagnostic>) -> Result<TestRun, String> {
+    let build_dir = paths::scratch_dir("test");
+    fs::create_dir_all(&build_dir).map_err(|e| format!("Failed to create {}: {}", build_dir.display(), e))?;
+    let result = compile_and_run(exercise_file, &build_dir, diagnostics);
+    let _ = fs::remove_dir_all(&build_dir);
+    result
+}
+
+fn compile_and_run(exercise_file: &Path, build_dir: &Path, diagnostics: &mut Vec<Diagnostic>) -> Result<TestRun, String> {
+    let stem = exercise_file.file_stem().unwrap_or_default();
+    let binary = build_dir.join(stem).with_extension(std::env::consts::EXE_EXTENSION);
+    let output = process::output(
+        Command::new("rustc").args(["--test", "--error-format=json"]).arg(exercise_file).arg("-o").arg(&binary),
+    )
+    .map_err(|e| format!("Failed to execute rustc: {}", e))?;
+    let parsed = diagnostics::parse(&String::from_utf8_lossy(&output.stderr), Path::new(""));
+    diagnostics.extend(parsed.diagnostics);
+    if !output.status.success() {
+        return Err(format!("rustc --test compilation failed: {}{}", parsed.rendered, parsed.other));
+    }
+
+    let exercise_dir = exercise_file.parent().unwrap_or(Path::new("."));
+    let output = process::output(Command::new(&binary).current_dir(exercise_dir))
+        .map_err(|e| format!("Failed to run the tests: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    Ok(TestRun {
+        tests: parse(&stdout),
+        output: stdout,
+        passed: output.status.success(),
+    })
+}
+
+// 解析单个测试程序的输出，如 "test tests::adds ... ok"；
+// libtest 的 --format json 只能在 nightly 上使用，稳定版工具链上解析文本输出
+pub fn parse(stdout: &str) -> Vec<TestResult> {
+    stdout.lines().filter_map(parse_line).collect()
+}
+
+// 解析 cargo test 的输出，测试名前加上所属的测试目标，如 "src/lib.rs: tests::adds"、"tests/it.rs: tests::adds"，
+// 不同目标中的同名测试不会混淆。各测试程序的结果在标准输出中以 "running N tests" 开头依次排列，
+// cargo 在标准错误中按同样的顺序列出测试目标；两边数目对不上时（如测试程序自己输出了类似的行）不加前缀
+pub fn parse_cargo(stdout: &str, stderr: &str) -> Vec<TestResult> {
+    let targets: Vec<&str> = stderr.lines().filter_map(target_label).collect();
+    let paired = stdout.lines().filter(|line| is_run_header(line)).count() == targets.len();
+    let mut target = None;
+    let mut next = 0;
+    stdout
+        .lines()
+        .filter_map(|line| {
+            if is_run_header(line) {
+                target = targets.get(next).filter(|_| paired);
+                next += 1;
+                return None;
+            }
+            let mut result = parse_line(line)?;
+            if let Some(target) = target {
+                result.name = format!("{}: {}", target, result.name);
+            }
+            Some(result)
+        })
+        .collect()
+}
+
+fn parse_line(line: &str) -> Option<TestResult> {
+    let (name, result) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+    let status = match result.trim() {
+        "ok" => TestStatus::Passed,
+        "FAILED" => TestStatus::Failed,
+        result if result.starts_with("ignored") => TestStatus::Ignored,
+        _ => return None,
+    };
+    Some(TestResult { name: name.to_string(), status })
+}
+
+// 测试程序开始运行时的第一行，如 "running 3 tests"
+fn is_run_header(line: &str) -> bool {
+    line.starts_with("running ") && (line.ends_with(" tests") || line.ends_with(" test"))
+}
+
+// cargo 运行测试程序前输出的目标："Running unittests src/lib.rs (target/debug/deps/…)" 取 src/lib.rs，
+// "Running tests/it.rs (…)" 取 tests/it.rs，文档测试 "Doc-tests <crate>" 记为 doc-tests
+fn target_label(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.starts_with("Doc-tests ") {
+        return Some("doc-tests");
+    }
+    let target = line.strip_prefix("Running ")?;
+    let target = target.rsplit_once(" (").map(|(target, _)| target).unwrap_or(target);
+    Some(target.strip_prefix("unittests ").unwrap_or(target))
+}
@@ -0,0 +1,42 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+
+// 计算习题内容的 SHA-256：单文件即文件内容，目录为其下所有文件（忽略 target）的相对路径与内容
+pub fn content_hash(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    if path.is_dir() {
+        let mut files = Vec::new();
+        collect_files(path, &mut files)?;
+        files.sort();
+        for file in files {
+            let relative = file.strip_prefix(path).unwrap_or(&file);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(fs::read(&file)?);
+            hasher.update([0]);
+        }
+    } else {
+        hasher.update(fs::read(path)?);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().map(|n| n != "target").unwrap_or(true) {
+                collect_files(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use serde::Deserialize;
+use crate::config::{Config, CONFIG_FILE};
+use crate::manifest::{RunManifest, Toolchain};
+use crate::paths::{self, STATE_DIR_ENV};
+use crate::new_run_id;
+
+// 在容器中运行时为所用镜像的 ID，记录在运行清单中；同时表示已经在容器中，不再嵌套启动容器
+pub const IMAGE_ENV: &str = "GRADER_HERMETIC_IMAGE";
+// 构建镜像用的临时目录（状态目录中的子目录），镜像构建完成后删除
+const CONTEXT_DIR: &str = "hermetic";
+// 重放容器中的运行时，清单复制到状态目录中的这个位置，容器内通过挂载的状态目录读取
+const RERUN_MANIFEST: &str = "hermetic-rerun.json";
+// 镜像名，标签为构建时生成的 ID
+const IMAGE_NAME: &str = "rust-basic-grader";
+// 容器中课程目录和状态目录的挂载点
+const COURSE_MOUNT: &str = "/course";
+const STATE_MOUNT: &str = "/state";
+// 传入容器的环境变量
+const PASSED_ENV: [&str; 2] = ["GRADER_ROLE", "EXERCISES_DIR"];
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct HermeticConfig {
+    // 容器引擎：docker 或 podman
+    pub engine: String,
+    // 基础镜像，默认按 [toolchain] 中固定的版本（或当前 rustc 的版本）使用官方镜像 rust:<版本>
+    pub base_image: Option<String>,
+}
+
+impl Default for HermeticConfig {
+    fn default() -> Self {
+        HermeticConfig { engine: "docker".to_string(), base_image: None }
+    }
+}
+
+// 在容器中运行时返回所用镜像的 ID
+pub fn image() -> Option<String> {
+    env::var(IMAGE_ENV).ok().filter(|image| !image.is_empty())
+}
+
+// --hermetic：构建固定工具链、依赖已 vendor 的镜像，在其中断网运行评测器（参数为去掉 --hermetic 的原参数），
+// 返回容器中评测器的退出状态。镜像保留在本机，日后可以用 rerun 在同一镜像中原样重新评测
+pub fn run(args: &[String], config: &Config, roots: &[PathBuf]) -> Result<i32, String> {
+    let course = env::current_dir().map_err(|e| format!("Failed to read the current directory: {}", e))?;
+    for root in roots {
+        let inside = fs::canonicalize(root).map(|root| root.starts_with(&course)).unwrap_or(false);
+        if !inside {
+            return Err(format!("--hermetic only mounts the course directory, but {} is outside it", root.display()));
+        }
+    }
+    let (tag, image) = build_image(config, roots)?;
+    let args: Vec<String> = args.iter().filter(|arg| *arg != "--hermetic").cloned().collect();
+    let code = run_in_image(&config.hermetic, &image, &args)?;
+    println!("Graded in image {} ({})", tag, image);
+    println!("Keep this image (e.g. `{} save {}`) to re-grade the run identically with `cargotest rerun`", config.hermetic.engine, tag);
+    Ok(code)
+}
+
+// 重放运行：清单记录了镜像时在同一镜像中重放，返回容器中评测器的退出状态；不是在容器中评测的运行返回 None
+pub fn rerun(path: &Path) -> Result<Option<i32>, String> {
+    let Some(image) = RunManifest::load(path)?.image else {
+        return Ok(None);
+    };
+    let config = Config::load(CONFIG_FILE)?;
+    let engine = &config.hermetic.engine;
+    let exists = Command::new(engine)
+        .args(["image", "inspect", &image])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if !exists {
+        return Err(format!(
+            "The run was graded in image {}, which is not available; restore it with `{} load` first",
+            image, engine
+        ));
+    }
+    fs::copy(path, paths::state_file(RERUN_MANIFEST)).map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+    let manifest = format!("{}/{}", STATE_MOUNT, RERUN_MANIFEST);
+    println!("Replaying the run in image {}", image);
+    run_in_image(&config.hermetic, &image, &["rerun".to_string(), "--manifest".to_string(), manifest]).map(Some)
+}
+
+// 构建本次运行的镜像，返回标签和镜像 ID
+fn build_image(config: &Config, roots: &[PathBuf]) -> Result<(String, String), String> {
+    let base = match &config.hermetic.base_image {
+        Some(image) => image.clone(),
+        None => format!("rust:{}", pinned_version(config)?),
+    };
+    let context = paths::state_file(CONTEXT_DIR).join(new_run_id());
+    let result = write_context(&context, &base, roots).and_then(|()| {
+        let tag = format!("{}:{}", IMAGE_NAME, context.file_name().unwrap_or_default().to_string_lossy());
+        println!("Building image {} from {}", tag, base);
+        let status = Command::new(&config.hermetic.engine)
+            .args(["build", "--tag", &tag])
+            .arg(&context)
+            .status()
+            .map_err(|e| format!("Failed to run {}: {}", config.hermetic.engine, e))?;
+        if !status.success() {
+            return Err(format!("Failed to build image {}", tag));
+        }
+        let output = Command::new(&config.hermetic.engine)
+            .args(["image", "inspect", "--format", "{{.Id}}", &tag])
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", config.hermetic.engine, e))?;
+        let image = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() || image.is_empty() {
+            return Err(format!("Failed to inspect image {}", tag));
+        }
+        Ok((tag, image))
+    });
+    let _ = fs::remove_dir_all(&context);
+    result
+}
+
+// 镜像中的 Rust 版本：[toolchain] 中固定的完整版本号，否则为当前 rustc 的版本
+fn pinned_version(config: &Config) -> Result<String, String> {
+    if let Some(version) = config.toolchain.as_ref().and_then(|toolchain| toolchain.version.as_ref()) {
+        if version.split('.').count() == 3 {
+            return Ok(version.clone());
+        }
+    }
+    let rustc = Toolchain::detect().rustc;
+    match rustc.split_whitespace().nth(1) {
+        Some(version) if !version.contains('-') => Ok(version.to_string()),
+        _ => Err(format!(
+            "Cannot pin the toolchain '{}' to an official image; set [hermetic] base_image in {}",
+            rustc, CONFIG_FILE
+        )),
+    }
+}
+
+// 镜像的构建上下文：评测器、vendor 的依赖、离线使用 vendor 依赖的 cargo 配置和 Dockerfile
+fn write_context(context: &Path, base: &str, roots: &[PathBuf]) -> Result<(), String> {
+    let vendor = context.join("vendor");
+    fs::create_dir_all(&vendor).map_err(|e| format!("Failed to create {}: {}", vendor.display(), e))?;
+    let grader = env::current_exe().map_err(|e| format!("Failed to locate the grader: {}", e))?;
+    fs::copy(&grader, context.join("cargotest")).map_err(|e| format!("Failed to copy {}: {}", grader.display(), e))?;
+    vendor_dependencies(roots, &vendor);
+    let cargo_config = "[source.crates-io]\n\
+        replace-with = \"vendored-sources\"\n\n\
+        [source.vendored-sources]\n\
+        directory = \"/grader/vendor\"\n\n\
+        [net]\n\
+        offline = true\n";
+    fs::write(context.join("config.toml"), cargo_config).map_err(|e| format!("Failed to write the cargo config: {}", e))?;
+    let dockerfile = format!(
+        "FROM {base}\n\
+        RUN rustup component add clippy rustfmt\n\
+        COPY vendor /grader/vendor\n\
+        COPY config.toml /grader/cargo-home/config.toml\n\
+        COPY cargotest /usr/local/bin/cargotest\n\
+        RUN chmod -R a+w /grader/cargo-home\n\
+        ENV CARGO_HOME=/grader/cargo-home\n\
+        WORKDIR {COURSE_MOUNT}\n"
+    );
+    fs::write(context.join("Dockerfile"), dockerfile).map_err(|e| format!("Failed to write the Dockerfile: {}", e))
+}
+
+// 把各 Cargo 项目（习题和公共 crate）的依赖 vendor 到同一目录；
+// 逐个项目执行，某个项目无法解析依赖时只影响它自己
+fn vendor_dependencies(roots: &[PathBuf], vendor: &Path) {
+    let mut manifests = Vec::new();
+    for root in roots {
+        find_manifests(root, &mut manifests);
+    }
+    manifests.sort();
+    for manifest in &manifests {
+        let output = Command::new("cargo")
+            .args(["vendor", "--versioned-dirs", "--no-delete", "--quiet", "--manifest-path"])
+            .arg(manifest)
+            .arg(vendor)
+            .stdout(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => eprintln!(
+                "Warning: failed to vendor the dependencies of {}: {}",
+                manifest.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            Err(e) => eprintln!("Warning: failed to run cargo vendor: {}", e),
+        }
+    }
+    println!("Vendored the dependencies of {} Cargo project(s)", manifests.len());
+}
+
+fn find_manifests(dir: &Path, manifests: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != "target" && name != ".git") {
+                find_manifests(&path, manifests);
+            }
+        } else if path.file_name().is_some_and(|name| name == "Cargo.toml") {
+            manifests.push(path);
+        }
+    }
+}
+
+// 在镜像中断网运行评测器：课程目录和状态目录挂载进容器，报告等照常写到本机的状态目录
+fn run_in_image(config: &HermeticConfig, image: &str, args: &[String]) -> Result<i32, String> {
+    let course = env::current_dir().map_err(|e| format!("Failed to read the current directory: {}", e))?;
+    let state = fs::canonicalize(paths::state_dir()).map_err(|e| format!("Failed to read the state directory: {}", e))?;
+    let mut command = Command::new(&config.engine);
+    command
+        .args(["run", "--rm", "--network", "none"])
+        .arg("--volume")
+        .arg(format!("{}:{}", course.display(), COURSE_MOUNT))
+        .arg("--volume")
+        .arg(format!("{}:{}", state.display(), STATE_MOUNT))
+        .args(["--env", &format!("{}={}", STATE_DIR_ENV, STATE_MOUNT)])
+        .args(["--env", &format!("{}={}", IMAGE_ENV, image)]);
+    for name in PASSED_ENV {
+        if let Ok(value) = env::var(name) {
+            command.args(["--env", &format!("{}={}", name, value)]);
+        }
+    }
+    // 以当前用户运行，容器中写出的报告和构建产物归当前用户所有
+    #[cfg(unix)]
+    {
+        // SAFETY: getuid/getgid 总是成功，没有副作用
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        command.args(["--user", &format!("{}:{}", uid, gid)]);
+    }
+    let status = command
+        .arg(image)
+        .arg("cargotest")
+        .args(args)
+        .status()
+        .map_err(|e| format!("Failed to run {}: {}", config.engine, e))?;
+    Ok(status.code().unwrap_or(1))
+}
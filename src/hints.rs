@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::markdown;
+use crate::metadata::ExerciseMeta;
+use crate::paths;
+
+// 提示使用记录，保存在当前课程的状态目录中
+pub const HINTS_FILE: &str = "hints.json";
+// hint.md 中分隔各条提示的行
+const SEPARATOR: &str = "---";
+
+// 各习题已经查看过的提示条数，键为习题名
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct HintLog {
+    revealed: BTreeMap<String, usize>,
+}
+
+impl HintLog {
+    pub fn load() -> Result<HintLog, String> {
+        let path = paths::state_file(HINTS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HintLog::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = paths::state_file(HINTS_FILE);
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // 已经查看过的提示条数
+    pub fn used(&self, name: &str) -> usize {
+        self.revealed.get(name).copied().unwrap_or(0)
+    }
+
+    // 再揭示一条提示（已全部揭示时不变），返回揭示后的条数
+    pub fn reveal(&mut self, name: &str, available: usize) -> usize {
+        let revealed = self.revealed.entry(name.to_string()).or_default();
+        *revealed = (*revealed + 1).min(available);
+        *revealed
+    }
+}
+
+// 习题的提示：元数据中的 hints，之后是提示文件中用 --- 分隔的各条提示
+pub fn hints_for(exercise: &Path, meta: &ExerciseMeta) -> Result<Vec<String>, String> {
+    let mut hints = meta.hints.clone();
+    let file = hint_file(exercise);
+    match fs::read_to_string(&file) {
+        Ok(content) => {
+            let mut current = String::new();
+            for line in content.lines() {
+                if line.trim() == SEPARATOR {
+                    hints.push(std::mem::take(&mut current));
+                } else {
+                    current.push_str(line);
+                    current.push('\n');
+                }
+            }
+            hints.push(current);
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to read {}: {}", file.display(), e)),
+    }
+    hints.retain(|hint| !hint.trim().is_empty());
+    Ok(hints)
+}
+
+// 提示文件：Cargo 项目习题为目录中的 hint.md，单文件习题为同目录的 <文件名>.hint.md
+fn hint_file(exercise: &Path) -> PathBuf {
+    if exercise.is_dir() {
+        exercise.join("hint.md")
+    } else {
+        exercise.with_extension("hint.md")
+    }
+}
+
+// 揭示习题的下一条提示并记录，返回到目前为止揭示的全部提示
+pub fn reveal_next(name: &str, hints: &[String]) -> Result<Vec<String>, String> {
+    if hints.is_empty() {
+        return Err(format!("{} has no hints", name));
+    }
+    let mut log = HintLog::load()?;
+    let revealed = log.reveal(name, hints.len());
+    log.save()?;
+    Ok(hints[..revealed].to_vec())
+}
+
+// 显示揭示的提示，最后一条之后说明是否还有更多提示
+pub fn render(revealed: &[String], total: usize) -> String {
+    let mut text = String::new();
+    for (i, hint) in revealed.iter().enumerate() {
+        text.push_str(&format!("Hint {}/{}:\n{}\n", i + 1, total, markdown::render(hint.trim())));
+    }
+    if revealed.len() < total {
+        text.push_str("Ask again for the next hint.\n");
+    } else {
+        text.push_str("That was the last hint.\n");
+    }
+    text
+}
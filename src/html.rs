@@ -0,0 +1,121 @@
+use std::fmt::Write;
+use crate::clipboard::strip_ansi;
+use crate::dashboard::escape;
+use crate::display::{self, Status};
+use crate::locale::Locale;
+use crate::steps;
+use crate::report::ReportWriter;
+use crate::{ExerciseStatus, Report};
+
+// 单个自包含的 HTML 页面，可以直接在浏览器中打开
+pub struct HtmlWriter;
+
+impl ReportWriter for HtmlWriter {
+    fn name(&self) -> &str {
+        "html"
+    }
+
+    fn extension(&self) -> &str {
+        "html"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        render(report)
+    }
+}
+
+// 将评测报告渲染为单个自包含的 HTML 页面：汇总统计、按通过与否着色的习题列表，
+// 每道习题的错误输出放在可折叠的区域中；数字和日期按报告记录的语言环境格式化
+pub fn render(report: &Report) -> String {
+    let locale = Locale::resolve(report.summary.as_ref().map(|summary| summary.locale.as_str()));
+    let stats = &report.statistics;
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"{}\">", locale.tag());
+    html.push_str(concat!(
+        "<head>\n<meta charset=\"utf-8\">\n",
+        "<title>Grading report</title>\n<style>\n",
+        "body { font-family: sans-serif; margin: 2em; color: #222; }\n",
+        "table { border-collapse: collapse; margin-bottom: 2em; }\n",
+        "th, td { border: 1px solid #ccc; padding: 4px 10px; text-align: left; vertical-align: top; }\n",
+        ".passed { background: #e6f4ea; }\n",
+        ".failed { background: #fce8e6; }\n",
+        ".in_progress { background: #fef7e0; }\n",
+        "pre { margin: 0.5em 0; white-space: pre-wrap; font-size: 0.9em; }\n",
+        "</style>\n</head>\n<body>\n<h1>Grading report</h1>\n",
+    ));
+    if let Some(summary) = &report.summary {
+        let _ = writeln!(
+            html,
+            "<p>Finished <time datetime=\"{}\">{}</time></p>",
+            escape(&summary.finished_at.value),
+            escape(&summary.finished_at.display)
+        );
+    }
+
+    let _ = writeln!(html, "<h2>Summary</h2>\n<table>");
+    if let Some(user_name) = &report.user_name {
+        let _ = writeln!(html, "<tr><th>Student</th><td>{}</td></tr>", escape(user_name));
+    }
+    let _ = writeln!(html, "<tr><th>Exercises</th><td>{}</td></tr>", stats.total_exercations);
+    let _ = writeln!(html, "<tr><th>Passed</th><td>{}</td></tr>", stats.total_succeeds);
+    let _ = writeln!(html, "<tr><th>Failed</th><td>{}</td></tr>", stats.total_failures);
+    if let Some(summary) = &report.summary {
+        let _ = writeln!(html, "<tr><th>Pass rate</th><td>{}</td></tr>", escape(&summary.pass_rate.display));
+        let _ = writeln!(html, "<tr><th>Duration</th><td>{}</td></tr>", escape(&summary.duration.display));
+    }
+    if let Some(penalty) = &report.late_penalty {
+        let _ = writeln!(
+            html,
+            "<tr><th>Score</th><td>{} ({} day(s) late, -{})</td></tr>",
+            locale.number(penalty.adjusted_score, 1),
+            penalty.days_late,
+            locale.percent(penalty.penalty_percent, 0)
+        );
+    }
+    html.push_str("</table>\n");
+
+    html.push_str("<h2>Exercises</h2>\n<table>\n<tr><th></th><th>Exercise</th><th>Result</th><th>Steps</th><th>Time</th><th>Details</th></tr>\n");
+    for exercise in &report.exercises {
+        let (status, class, result) = match exercise.status {
+            ExerciseStatus::Passed => (Status::Pass, "passed", "passed".to_string()),
+            ExerciseStatus::InProgress => (Status::Partial, "in_progress", "in progress".to_string()),
+            ExerciseStatus::Failed => (
+                Status::Fail,
+                "failed",
+                match (exercise.reason, exercise.code) {
+                    (Some(reason), Some(code)) => format!("{} ({})", reason.label(), code.as_str()),
+                    (Some(reason), None) => reason.label().to_string(),
+                    (None, _) => "failed".to_string(),
+                },
+            ),
+        };
+        let steps = steps::summary(&exercise.steps);
+        let time = exercise
+            .duration_ms
+            .map(|ms| format!("{} s", locale.number(ms as f64 / 1000.0, 2)))
+            .unwrap_or_default();
+        // 去掉颜色序列后剩余的控制字符在 HTML 中无法显示
+        let log: String = strip_ansi(&exercise.log)
+            .chars()
+            .filter(|ch| !ch.is_control() || matches!(ch, '\t' | '\n'))
+            .collect();
+        let details = if log.trim().is_empty() {
+            String::new()
+        } else {
+            format!("<details><summary>Log</summary><pre>{}</pre></details>", escape(log.trim()))
+        };
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            display::symbol(status),
+            escape(&exercise.name),
+            escape(&result),
+            escape(&steps),
+            time,
+            details
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use crate::clippy::ClippyConfig;
+use crate::metadata::ExerciseMeta;
+
+// 习题清单，位于习题根目录或章节目录中，按顺序列出其中的习题及其元数据；
+// 存在时代替按目录结构推断习题
+pub const INFO_FILE: &str = "info.toml";
+// 单道 Cargo 项目习题的元数据，位于习题目录中
+pub const EXERCISE_FILE: &str = "exercise.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Info {
+    pub exercises: Vec<InfoEntry>,
+    // 章节中习题的 clippy 设置，覆盖 grader.toml 中的 [clippy]；习题自己的 clippy 项优先
+    pub clippy: Option<ClippyConfig>,
+}
+
+// 清单中的一道习题，如 { name = "intro1", path = "intro/intro1.rs", mode = "run", points = 10 }
+#[derive(Deserialize, Debug, Clone)]
+pub struct InfoEntry {
+    // 相对于清单所在目录的路径，未配置时依次尝试 <name>.rs 和 <name> 目录
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(flatten)]
+    pub meta: ExerciseMeta,
+}
+
+impl Info {
+    // 读取目录中的清单，没有清单时返回 None
+    pub fn load(dir: &Path) -> Result<Option<Info>, String> {
+        let Some(content) = read_optional(&dir.join(INFO_FILE))? else {
+            return Ok(None);
+        };
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse {}: {}", dir.join(INFO_FILE).display(), e))
+    }
+
+    // 按清单顺序列出习题路径；找不到的习题作为错误返回
+    pub fn exercises(&self, dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let mut exercises = Vec::new();
+        let mut missing = Vec::new();
+        for (i, entry) in self.exercises.iter().enumerate() {
+            match entry.resolve(dir) {
+                Some(path) => exercises.push(path),
+                None => missing.push(entry.label(i)),
+            }
+        }
+        if missing.is_empty() {
+            Ok(exercises)
+        } else {
+            Err(format!("{} lists exercises that do not exist: {}", dir.join(INFO_FILE).display(), missing.join(", ")))
+        }
+    }
+
+    // 清单中某道习题的元数据
+    fn meta(&self, dir: &Path, exercise: &Path) -> Option<ExerciseMeta> {
+        self.exercises
+            .iter()
+            .find(|entry| entry.resolve(dir).as_deref() == Some(exercise))
+            .map(|entry| entry.meta.clone())
+    }
+}
+
+impl InfoEntry {
+    fn resolve(&self, dir: &Path) -> Option<PathBuf> {
+        let candidates = match (&self.path, &self.meta.name) {
+            (Some(path), _) => vec![dir.join(path.trim_end_matches('/'))],
+            (None, Some(name)) => vec![dir.join(format!("{}.rs", name)), dir.join(name)],
+            (None, None) => Vec::new(),
+        };
+        candidates.into_iter().find(|path| path.exists())
+    }
+
+    fn label(&self, index: usize) -> String {
+        match (&self.path, &self.meta.name) {
+            (Some(path), _) => path.clone(),
+            (None, Some(name)) => name.clone(),
+            (None, None) => format!("entry #{} (no name or path)", index + 1),
+        }
+    }
+}
+
+// 读取 Cargo 项目习题目录中的 exercise.toml，没有时返回 None
+pub fn load_exercise_file(dir: &Path) -> Result<Option<ExerciseMeta>, String> {
+    let path = dir.join(EXERCISE_FILE);
+    let Some(content) = read_optional(&path)? else {
+        return Ok(None);
+    };
+    toml::from_str(&content).map(Some).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+// 查找习题在 exercise.toml 或上级目录清单中的元数据；清单有误时在扫描习题时已报告，这里忽略
+pub fn find_meta(exercise: &Path) -> Option<ExerciseMeta> {
+    let meta = exercise
+        .is_dir()
+        .then(|| load_exercise_file(exercise).ok().flatten())
+        .flatten()
+        .or_else(|| {
+            exercise
+                .ancestors()
+                .skip(1)
+                .find_map(|dir| Info::load(dir).ok().flatten().and_then(|info| info.meta(dir, exercise)))
+        });
+    // 习题未配置 clippy 时取最近一级章节清单中的设置
+    let clippy = exercise.ancestors().skip(1).find_map(|dir| Info::load(dir).ok().flatten().and_then(|info| info.clippy));
+    match clippy {
+        Some(clippy) => {
+            let mut meta = meta.unwrap_or_default();
+            meta.clippy = meta.clippy.or(Some(clippy));
+            Some(meta)
+        }
+        None => meta,
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use similar::TextDiff;
+use crate::dashboard;
+use crate::locale::{Formatted, Locale};
+use crate::Report;
+
+// 运行身份：学生模式下隐藏参考答案、隐藏测试和班级统计等教师功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Student,
+    Instructor,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Result<Role, String> {
+        match value {
+            "student" => Ok(Role::Student),
+            "instructor" => Ok(Role::Instructor),
+            other => Err(format!("Unknown role '{}'. Use 'student' or 'instructor'", other)),
+        }
+    }
+
+    // 教师功能的统一检查
+    pub fn require_instructor(self, feature: &str) -> Result<(), String> {
+        match self {
+            Role::Instructor => Ok(()),
+            Role::Student => Err(format!("'{}' is only available with --role instructor", feature)),
+        }
+    }
+}
+
+// 显示学生代码与参考答案之间的差异
+pub fn solution_diff(exercise: &Path, exercises_dir: &Path, solutions_dir: &Path) -> Result<String, String> {
+    let relative = exercise.strip_prefix(exercises_dir).unwrap_or(exercise);
+    let solution = solutions_dir.join(relative);
+    let student_code = fs::read_to_string(exercise)
+        .map_err(|e| format!("Failed to read {}: {}", exercise.display(), e))?;
+    let solution_code = fs::read_to_string(&solution)
+        .map_err(|e| format!("Failed to read solution {}: {}", solution.display(), e))?;
+
+    Ok(TextDiff::from_lines(&solution_code, &student_code)
+        .unified_diff()
+        .header(&solution.display().to_string(), &exercise.display().to_string())
+        .to_string())
+}
+
+// 评测前将隐藏测试复制到 Cargo 习题的 tests 目录，返回复制的文件以便评测后删除
+pub fn inject_hidden_tests(exercise_dir: &Path, exercises_dir: &Path, hidden_tests_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let relative = exercise_dir.strip_prefix(exercises_dir).unwrap_or(exercise_dir);
+    let source_dir = hidden_tests_dir.join(relative);
+    let entries = match fs::read_dir(&source_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let tests_dir = exercise_dir.join("tests");
+    fs::create_dir_all(&tests_dir)
+        .map_err(|e| format!("Failed to create {}: {}", tests_dir.display(), e))?;
+    let mut injected = Vec::new();
+    for entry in entries.flatten() {
+        let source = entry.path();
+        if source.extension().map(|ext| ext == "rs").unwrap_or(false) {
+            let file_name = source.file_name().unwrap_or_default();
+            let target = tests_dir.join(format!("hidden_{}", file_name.to_string_lossy()));
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+            injected.push(target);
+        }
+    }
+    Ok(injected)
+}
+
+// 删除注入的隐藏测试；tests 目录若因注入而创建且已为空，一并删除
+pub fn remove_hidden_tests(injected: &[PathBuf]) {
+    for file in injected {
+        let _ = fs::remove_file(file);
+    }
+    if let Some(tests_dir) = injected.first().and_then(|f| f.parent()) {
+        let _ = fs::remove_dir(tests_dir);
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct StudentSummary {
+    pub student: String,
+    pub score: usize,
+    pub total: usize,
+    // 按截止日期策略扣分后的分数（百分制），报告中没有迟交记录时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjusted_score: Option<f64>,
+}
+
+impl StudentSummary {
+    // 通过率折算的百分制分数
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.score as f64 / self.total as f64 * 100.0
+        }
+    }
+
+    // 最终成绩（百分制）：有迟交扣分时取扣分后的分数
+    pub fn final_score(&self) -> f64 {
+        self.adjusted_score.unwrap_or_else(|| self.percent())
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ExerciseSummary {
+    pub name: String,
+    pub passed: usize,
+    pub attempted: usize,
+    pub pass_rate: f64,
+}
+
+// 分数直方图中的一个区间 [low, high)，最后一个区间包含 100 分
+#[derive(Serialize, Debug)]
+pub struct HistogramBucket {
+    pub low: u32,
+    pub high: u32,
+    pub count: usize,
+}
+
+// 班级层面的统计（分数均为百分制）
+#[derive(Serialize, Debug)]
+pub struct ClassStatistics {
+    pub students: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub stddev: f64,
+    pub histogram: Vec<HistogramBucket>,
+    // 通过率最低的若干道习题
+    pub hardest_exercises: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AggregateReport {
+    pub locale: String,
+    pub generated_at: Formatted<String>,
+    pub students: Vec<StudentSummary>,
+    pub exercises: Vec<ExerciseSummary>,
+    pub statistics: ClassStatistics,
+}
+
+// 直方图区间宽度（分）
+const BUCKET_WIDTH: u32 = 10;
+// 统计中列出的最难习题数量
+const HARDEST_COUNT: usize = 5;
+
+// 汇总多个学生的评测报告，同时生成 JSON 汇总报告和 HTML 看板
+pub fn aggregate_reports(report_files: &[String], output_file: &str, dashboard_file: &str, locale: Locale) -> Result<AggregateReport, String> {
+    if report_files.is_empty() {
+        return Err("Please provide at least one report file to aggregate".to_string());
+    }
+
+    let mut students = Vec::new();
+    let mut exercises: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for file in report_files {
+        let reader = File::open(file).map_err(|e| format!("Failed to open {}: {}", file, e))?;
+        let report: Report = serde_json::from_reader(reader)
+            .map_err(|e| format!("Failed to parse {}: {}", file, e))?;
+        let student = report.user_name.clone().unwrap_or_else(|| {
+            Path::new(file)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| file.clone())
+        });
+        for exercise in &report.exercises {
+            let entry = exercises.entry(exercise.name.clone()).or_default();
+            entry.1 += 1;
+            if exercise.result {
+                entry.0 += 1;
+            }
+        }
+        students.push(StudentSummary {
+            student,
+            score: report.statistics.total_succeeds,
+            total: report.statistics.total_exercations,
+            adjusted_score: report.late_penalty.as_ref().map(|penalty| penalty.adjusted_score),
+        });
+    }
+
+    let exercises: Vec<ExerciseSummary> = exercises
+        .into_iter()
+        .map(|(name, (passed, attempted))| ExerciseSummary {
+            name,
+            passed,
+            attempted,
+            pass_rate: passed as f64 / attempted as f64,
+        })
+        .collect();
+    let statistics = class_statistics(&students, &exercises);
+    let aggregate = AggregateReport {
+        locale: locale.tag().to_string(),
+        generated_at: locale.now(),
+        students,
+        exercises,
+        statistics,
+    };
+
+    println!("\nStudents:");
+    for student in &aggregate.students {
+        println!("  {}: {}/{}", student.student, student.score, student.total);
+    }
+    println!("\nExercises:");
+    for exercise in &aggregate.exercises {
+        println!("  {}: {}/{} passed ({:.0}%)", exercise.name, exercise.passed, exercise.attempted, exercise.pass_rate * 100.0);
+    }
+    let stats = &aggregate.statistics;
+    println!("\nClass statistics:");
+    println!("  mean {:.1}, median {:.1}, stddev {:.1}", stats.mean, stats.median, stats.stddev);
+    for bucket in &stats.histogram {
+        println!("  {:>3}-{:<3} {}", bucket.low, bucket.high, "#".repeat(bucket.count));
+    }
+    if !stats.hardest_exercises.is_empty() {
+        println!("  hardest: {}", stats.hardest_exercises.join(", "));
+    }
+
+    let file = File::create(output_file).map_err(|e| format!("Failed to create {}: {}", output_file, e))?;
+    serde_json::to_writer_pretty(file, &aggregate).map_err(|e| format!("Failed to write {}: {}", output_file, e))?;
+    println!("\nAggregate report saved to {}", output_file);
+    fs::write(dashboard_file, dashboard::render(&aggregate, locale))
+        .map_err(|e| format!("Failed to write {}: {}", dashboard_file, e))?;
+    println!("Dashboard saved to {}", dashboard_file);
+    Ok(aggregate)
+}
+
+// 计算班级分数的均值、中位数、标准差、直方图，以及通过率最低的习题
+fn class_statistics(students: &[StudentSummary], exercises: &[ExerciseSummary]) -> ClassStatistics {
+    let mut scores: Vec<f64> = students.iter().map(StudentSummary::percent).collect();
+    scores.sort_by(|a, b| a.total_cmp(b));
+    let count = scores.len();
+    let mean = if count == 0 { 0.0 } else { scores.iter().sum::<f64>() / count as f64 };
+    let median = match count {
+        0 => 0.0,
+        n if n % 2 == 1 => scores[n / 2],
+        n => (scores[n / 2 - 1] + scores[n / 2]) / 2.0,
+    };
+    let stddev = if count == 0 {
+        0.0
+    } else {
+        (scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / count as f64).sqrt()
+    };
+
+    let mut histogram: Vec<HistogramBucket> = (0..100 / BUCKET_WIDTH)
+        .map(|i| HistogramBucket { low: i * BUCKET_WIDTH, high: (i + 1) * BUCKET_WIDTH, count: 0 })
+        .collect();
+    for score in &scores {
+        let index = ((*score as u32) / BUCKET_WIDTH).min(histogram.len() as u32 - 1);
+        histogram[index as usize].count += 1;
+    }
+
+    let mut by_pass_rate: Vec<&ExerciseSummary> = exercises.iter().filter(|e| e.pass_rate < 1.0).collect();
+    by_pass_rate.sort_by(|a, b| a.pass_rate.total_cmp(&b.pass_rate).then_with(|| a.name.cmp(&b.name)));
+    let hardest_exercises = by_pass_rate.into_iter().take(HARDEST_COUNT).map(|e| e.name.clone()).collect();
+
+    ClassStatistics { students: count, mean, median, stddev, histogram, hardest_exercises }
+}
+
+// 代码相似度检查：submissions_dir 下每个子目录是一名学生的提交，
+// 对同一路径的 .rs 文件两两比较归一化后的 token 三元组集合
+pub fn similarity_check(submissions_dir: &Path, threshold: f64) -> Result<(), String> {
+    let mut students: Vec<PathBuf> = fs::read_dir(submissions_dir)
+        .map_err(|e| format!("Failed to read {}: {}", submissions_dir.display(), e))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    students.sort();
+
+    // 每名学生：相对路径 -> token 三元组集合
+    let mut fingerprints = Vec::new();
+    for student in &students {
+        let mut files = Vec::new();
+        collect_rs_files(student, &mut files);
+        let mut map = BTreeMap::new();
+        for file in files {
+            if let Ok(code) = fs::read_to_string(&file) {
+                let relative = file.strip_prefix(student).unwrap_or(&file).to_path_buf();
+                map.insert(relative, shingles(&code));
+            }
+        }
+        fingerprints.push(map);
+    }
+
+    let mut found = false;
+    for i in 0..students.len() {
+        for j in (i + 1)..students.len() {
+            for (path, a) in &fingerprints[i] {
+                let b = match fingerprints[j].get(path) {
+                    Some(b) => b,
+                    None => continue,
+                };
+                let union = a.union(b).count();
+                if union == 0 {
+                    continue;
+                }
+                let score = a.intersection(b).count() as f64 / union as f64;
+                if score >= threshold {
+                    found = true;
+                    println!(
+                        "{:.0}% similar: {} <-> {} ({})",
+                        score * 100.0,
+                        students[i].display(),
+                        students[j].display(),
+                        path.display()
+                    );
+                }
+            }
+        }
+    }
+    if !found {
+        println!("No submissions above {:.0}% similarity.", threshold * 100.0);
+    }
+    Ok(())
+}
+
+fn collect_rs_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if path.file_name().map(|n| n != "target" && n != ".git").unwrap_or(true) {
+                    collect_rs_files(&path, files);
+                }
+            } else if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
+                files.push(path);
+            }
+        }
+    }
+}
+
+// 去掉注释并把标识符统一替换，避免简单改名就绕过检查
+fn shingles(code: &str) -> HashSet<String> {
+    let tokens: Vec<String> = code
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .flat_map(|line| {
+            line.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|t| !t.is_empty())
+                .map(|t| {
+                    if t.chars().next().map(|c| c.is_alphabetic() || c == '_').unwrap_or(false)
+                        && !is_keyword(t)
+                    {
+                        "$id".to_string()
+                    } else {
+                        t.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    tokens.windows(3).map(|w| w.join(" ")).collect()
+}
+
+fn is_keyword(token: &str) -> bool {
+    matches!(
+        token,
+        "fn" | "let" | "mut" | "if" | "else" | "match" | "for" | "while" | "loop" | "return"
+            | "struct" | "enum" | "impl" | "trait" | "pub" | "use" | "mod" | "in" | "as" | "ref"
+            | "self" | "Self" | "where" | "break" | "continue" | "const" | "static" | "move"
+    )
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::clipboard::strip_ansi;
+use crate::roots::DEFAULT_ROOT;
+use crate::steps;
+use crate::report::ReportWriter;
+use crate::{ExerciseResult, Report};
+
+// JUnit XML，供 Jenkins、GitLab CI 等直接展示评测结果
+pub struct JunitWriter;
+
+impl ReportWriter for JunitWriter {
+    fn name(&self) -> &str {
+        "junit"
+    }
+
+    fn extension(&self) -> &str {
+        "xml"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        render(report)
+    }
+}
+
+// 生成 JUnit XML 报告：每个习题根目录是一个 testsuite，每道习题是一个 testcase，
+// classname 为所在章节，未通过的习题附带失败原因和错误输出
+pub fn render(report: &Report) -> String {
+    let mut suites: BTreeMap<&str, Vec<&ExerciseResult>> = BTreeMap::new();
+    for exercise in &report.exercises {
+        suites.entry(exercise.root.as_deref().unwrap_or(DEFAULT_ROOT)).or_default().push(exercise);
+    }
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"cargotest\" tests=\"{}\" failures=\"{}\" time=\"{}\">\n",
+        report.statistics.total_exercations, report.statistics.total_failures, report.statistics.total_time
+    ));
+    for (name, exercises) in suites {
+        let failures = exercises.iter().filter(|exercise| !exercise.result).count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{}\">\n",
+            escape(name),
+            exercises.len(),
+            failures,
+            seconds(exercises.iter().filter_map(|exercise| exercise.duration_ms).sum())
+        ));
+        for exercise in exercises {
+            render_case(&mut xml, exercise);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn render_case(xml: &mut String, exercise: &ExerciseResult) {
+    let path = Path::new(&exercise.name);
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let classname = path.parent().map(|parent| parent.display().to_string()).unwrap_or_default();
+    xml.push_str(&format!(
+        "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\"",
+        escape(&name),
+        escape(&classname),
+        seconds(exercise.duration_ms.unwrap_or(0))
+    ));
+    if exercise.result {
+        xml.push_str("/>\n");
+        return;
+    }
+    let message = exercise.reason.map(|reason| reason.label()).unwrap_or("failed");
+    // 失败信息的第一行列出各步骤的结果
+    let mut details = steps::summary(&exercise.steps);
+    if !details.is_empty() {
+        details.push_str("\n\n");
+    }
+    details.push_str(strip_ansi(&exercise.log).trim());
+    // type 为失败代码（如 GR0101），CI 可以据此分类
+    let code = exercise.code.map(|code| format!(" type=\"{}\"", code.as_str())).unwrap_or_default();
+    xml.push_str(&format!(
+        ">\n      <failure message=\"{}\"{}>{}</failure>\n    </testcase>\n",
+        escape(message),
+        code,
+        escape(&details)
+    ));
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+// 转义 XML 特殊字符，并去掉 XML 1.0 不允许出现的控制字符
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|ch| !ch.is_control() || matches!(ch, '\t' | '\n' | '\r'))
+        .collect::<String>()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,1329 @@
+pub mod annotations;
+pub mod archive;
+pub mod artifacts;
+pub mod auth;
+pub mod build_cache;
+pub mod bundle;
+pub mod cache;
+pub mod cases;
+pub mod checkpoint;
+pub mod classroom;
+pub mod cli;
+pub mod clipboard;
+pub mod clippy;
+pub mod compile_time;
+pub mod completion;
+pub mod config;
+#[cfg(feature = "grpc")]
+pub mod coordinator;
+pub mod courses;
+pub mod csv;
+pub mod dashboard;
+pub mod deadline;
+pub mod diagnostics;
+pub mod display;
+pub mod evaluator;
+pub mod failure;
+pub mod filter;
+pub mod generator;
+pub mod harness;
+pub mod hermetic;
+pub mod github;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod hashing;
+pub mod hints;
+pub mod html;
+pub mod info;
+pub mod instructor;
+pub mod junit;
+pub mod locale;
+pub mod lti;
+pub mod manifest;
+pub mod markdown;
+pub mod markdown_report;
+pub mod matrix;
+pub mod metadata;
+pub mod onboarding;
+pub mod output;
+pub mod paths;
+pub mod placeholders;
+pub mod precompile;
+pub mod process;
+pub mod progress;
+pub mod progression;
+pub mod share;
+pub mod report;
+pub mod roots;
+pub mod sandbox;
+pub mod sarif;
+pub mod rubric;
+pub mod shard;
+pub mod shared;
+pub mod steps;
+pub mod submit;
+pub mod tap;
+pub mod template;
+pub mod toolchain;
+pub mod transcript;
+pub mod validate;
+pub mod warmup;
+pub mod watcher;
+pub mod webhook;
+
+use std::process::Command;
+use std::fs::{self, File};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::io;
+use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use artifacts::Snapshot;
+use config::{Config, Exclusions};
+use deadline::LatePenalty;
+use diagnostics::Diagnostic;
+use display::{Status, Tone};
+use failure::{FailureCode, FailureReason};
+use filter::ExerciseFilter;
+use cases::CaseResult;
+use harness::TestResult;
+use hints::HintLog;
+use info::Info;
+use classroom::SubmissionInfo;
+use compile_time::CompileTimePolicy;
+use instructor::Role;
+use locale::LocalizedSummary;
+use metadata::{DirLayout, ExerciseMeta, ExerciseMode};
+use output::ExerciseOutput;
+use placeholders::PlaceholderAction;
+use precompile::{Compiled, Precompiled};
+use progress::Progress;
+use steps::StepStatus;
+use roots::ExerciseRoot;
+use rubric::ScoreComponent;
+use sandbox::Sandbox;
+use shard::Shard;
+use shared::SharedCrate;
+use webhook::Webhook;
+use regex::Regex;
+
+// 嵌入评测的入口
+pub use evaluator::{EvaluationOutcome, Evaluator, Exercise};
+
+// 传给习题程序的随机种子环境变量
+pub const SEED_ENV: &str = "GRADER_SEED";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseStatus {
+    Passed,
+    #[default]
+    Failed,
+    // 能编译通过但仍保留"未完成"标记
+    InProgress,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExerciseResult {
+    pub name: String,
+    pub result: bool,
+    #[serde(default)]
+    pub status: ExerciseStatus,
+    // 习题所属根目录的标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    // 未通过时的主要原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<FailureReason>,
+    // 未通过时的失败代码（如 GR0101），比 reason 更细
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<FailureCode>,
+    // 习题程序运行时的峰值内存（KB），未运行或平台不支持时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak_memory_kb: Option<u64>,
+    // 输出比对类习题各用例的结果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cases: Vec<CaseResult>,
+    // 各测试的结果：单文件习题中的 #[test] 函数，或 Cargo 项目中 cargo test 运行的全部测试
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TestResult>,
+    // 按评分细则计算的得分（0-100），未配置细则时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    // 各评分项的明细
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ScoreComponent>,
+    // 获得的分数和习题分值，习题元数据未配置分值时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_points: Option<f64>,
+    // 学生查看过的提示条数，未查看过时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hints_used: Option<usize>,
+    // 配置了 feature 组合时各组合的子结果
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<VariantResult>,
+    // 各评测步骤的结果：通过、失败或因前面的步骤失败而跳过
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub steps: BTreeMap<String, StepStatus>,
+    // 评测耗时（毫秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    // 各阶段（build、test、clippy、run）的耗时（毫秒）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub phases_ms: BTreeMap<String, u64>,
+    // 编译和 clippy 检查报告的错误和警告
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<Diagnostic>,
+    // 评测过程中的错误输出，不写入报告
+    #[serde(skip)]
+    pub log: String,
+}
+
+// 评测过程中采集的指标
+#[derive(Debug, Default)]
+struct ExerciseMetrics {
+    peak_memory_kb: Option<u64>,
+    cases: Vec<CaseResult>,
+    tests: Vec<TestResult>,
+    // 各评测步骤（compiles、tests、clippy、run）的结果，供报告和评分细则使用
+    steps: BTreeMap<&'static str, StepStatus>,
+    // 各阶段（build、test、clippy、run）的耗时（毫秒）
+    phases_ms: BTreeMap<&'static str, u64>,
+    variants: Vec<VariantResult>,
+    // 评测流程中最先出错的一步对应的失败原因
+    failure: Option<FailureCode>,
+    // 单文件习题编译时 rustc 输出的诊断信息（错误和警告），供逐题模式显示，不必再编译一遍
+    compiler_output: String,
+    // 编译和 clippy 检查报告的错误和警告，用于生成 SARIF 等带代码位置的报告
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ExerciseMetrics {
+    // 记录失败原因，只保留第一个
+    fn fail(&mut self, code: FailureCode) {
+        self.failure.get_or_insert(code);
+    }
+
+    // 记录步骤结果；多个 feature 组合时与此前组合的结果合并
+    fn record_step(&mut self, step: &'static str, status: StepStatus) {
+        let merged = self.steps.get(step).map(|previous| previous.merge(status)).unwrap_or(status);
+        self.steps.insert(step, merged);
+    }
+
+    // 累计阶段耗时；多个 feature 组合时各组合的耗时相加
+    fn record_time(&mut self, phase: &'static str, started: Instant) {
+        self.record_duration(phase, started.elapsed());
+    }
+
+    fn record_duration(&mut self, phase: &'static str, duration: Duration) {
+        *self.phases_ms.entry(phase).or_default() += duration.as_millis() as u64;
+    }
+
+    // 记录诊断；cargo 会重复输出已缓存的警告，多个 feature 组合也可能报告相同的问题，只保留一份
+    fn record_diagnostics(&mut self, diagnostics: Vec<Diagnostic>) {
+        for diagnostic in diagnostics {
+            if !self.diagnostics.contains(&diagnostic) {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+}
+
+// 在习题的评测时限内执行 evaluate：期间启动的进程到时连同其子进程一起被结束，失败原因记为超时
+// （进程被结束后各步骤会按编译失败、运行出错等记录原因，这里统一改为超时）
+fn with_time_limit<T>(
+    meta: &ExerciseMeta,
+    metrics: &mut ExerciseMetrics,
+    out: &mut ExerciseOutput,
+    evaluate: impl FnOnce(&mut ExerciseMetrics, &mut ExerciseOutput) -> T,
+) -> T {
+    process::set_deadline(meta.timeout_secs.map(|secs| Instant::now() + Duration::from_secs(secs)));
+    let result = evaluate(metrics, out);
+    if process::hit_deadline() {
+        metrics.failure = Some(FailureCode::Timeout);
+        out.err(&format!("Timed out after {}s; the exercise was stopped", meta.timeout_secs.unwrap_or_default()));
+    }
+    process::set_deadline(None);
+    result
+}
+
+// 按 feature 组合评测时每个组合的结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VariantResult {
+    pub name: String,
+    pub passed: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Statistics {
+    pub total_exercations: usize,
+    pub total_succeeds: usize,
+    pub total_failures: usize,
+    pub total_time: u64,
+}
+
+// 一次评测运行中各习题共享的上下文
+pub struct RunContext {
+    pub exercises_dir: PathBuf,
+    // 全部习题根目录，用于确定习题所属的根目录
+    pub roots: Vec<ExerciseRoot>,
+    // 习题元数据、生成器和用例文件所在的习题目录；
+    // 评测参考答案时 exercises_dir 指向 solutions 目录，这些内容仍从 exercises 目录读取
+    pub metadata_dir: PathBuf,
+    pub hidden_tests_dir: PathBuf,
+    pub shared_crates: Vec<SharedCrate>,
+    pub webhook: Option<Webhook>,
+    // 注解文件的目录，每道习题评测后在其中写出诊断注解；为空时不写
+    pub annotations: Option<PathBuf>,
+    pub shard: Option<Shard>,
+    pub filter: Option<ExerciseFilter>,
+    // 重放运行时只评测清单中的习题
+    pub replay_set: Option<HashSet<PathBuf>>,
+    // 每道习题的默认评测时限（秒），来自 --timeout 或全局配置
+    pub timeout_secs: Option<u64>,
+    // 批量评测前并行编译好的单文件习题
+    pub precompiled: Precompiled,
+    pub config: Config,
+    pub role: Role,
+    pub verbose: bool,
+}
+
+impl RunContext {
+    // 习题是否在本次运行的评测范围内
+    pub fn includes(&self, exercise: &Path) -> bool {
+        self.shard.map(|shard| shard.contains(exercise)).unwrap_or(true)
+            && self.filter.as_ref().map(|filter| filter.matches(exercise, self.root_dir(exercise))).unwrap_or(true)
+            && self.replay_set.as_ref().map(|set| set.contains(exercise)).unwrap_or(true)
+    }
+
+    // 习题程序是否在一次性沙箱中运行（未关闭文件系统隔离）
+    pub fn sandboxed(&self, exercise: &Path) -> bool {
+        self.exercise_meta(exercise).isolate_fs.unwrap_or(true)
+    }
+
+    // 习题所在的根目录；评测参考答案时为 solutions 目录
+    fn root_dir(&self, exercise: &Path) -> &Path {
+        roots::root_of(&self.roots, exercise).map(|root| root.path.as_path()).unwrap_or(&self.exercises_dir)
+    }
+
+    // 习题元数据，其中的相对路径解析为相对于当前目录的路径
+    fn exercise_meta(&self, exercise: &Path) -> ExerciseMeta {
+        let relative = exercise.strip_prefix(&self.exercises_dir).unwrap_or(exercise);
+        // 其他根目录中的习题路径不以 exercises_dir 开头，元数据直接按原路径查找
+        let original = if relative == exercise { exercise.to_path_buf() } else { self.metadata_dir.join(relative) };
+        let mut meta = self.config.exercise_meta(&original);
+        meta.isolate_network = Some(meta.isolate_network.unwrap_or(self.config.isolate_network));
+        meta.isolate_fs = Some(meta.isolate_fs.unwrap_or(self.config.isolate_fs));
+        meta.expose_exercise = Some(meta.expose_exercise.unwrap_or(self.config.expose_exercise));
+        meta.clippy = Some(meta.clippy.unwrap_or_else(|| self.config.clippy.clone()));
+        meta.timeout_secs = meta.timeout_secs.or(self.timeout_secs);
+        let dir = if exercise.is_dir() { original.as_path() } else { original.parent().unwrap_or(Path::new(".")) };
+        for case in &mut meta.cases {
+            for file in [&mut case.input_file, &mut case.expected_file].into_iter().flatten() {
+                *file = dir.join(&*file).display().to_string();
+            }
+        }
+        if let Some(generator) = meta.generator.as_mut() {
+            generator.source = dir.join(&generator.source).display().to_string();
+            // 默认参考答案：solutions 目录中的对应文件（Cargo 项目取 src/main.rs）
+            let reference = match &generator.reference {
+                Some(reference) => dir.join(reference),
+                None => {
+                    let mirror = Path::new(&self.config.solutions_dir).join(relative);
+                    if exercise.is_dir() { mirror.join("src").join("main.rs") } else { mirror }
+                }
+            };
+            generator.reference = Some(reference.display().to_string());
+        }
+        meta
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Report {
+    // 本次运行的唯一标识，服务器据此对重复提交去重
+    #[serde(default)]
+    pub run_id: String,
+    // 分片运行时记录本报告对应的分片，如 "1/4"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<String>,
+    pub exercises: Vec<ExerciseResult>,
+    pub user_name: Option<String>,
+    pub statistics: Statistics,
+    // 配置了截止日期时记录迟交扣分情况，包括原始分和扣分后的分数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub late_penalty: Option<LatePenalty>,
+    // 课堂模式下被评测代码的提交信息
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submission: Option<SubmissionInfo>,
+    // 按语言环境格式化的结束时间、耗时和通过率，同时保留 ISO 格式的原始值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<LocalizedSummary>,
+    // 运行被 Ctrl+C 中断，报告只包含中断前评测完的习题
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interrupted: bool,
+}
+
+impl Report {
+    // 新一次运行的空报告
+    pub fn new(user_name: Option<String>) -> Report {
+        Report {
+            run_id: new_run_id(),
+            shard: None,
+            exercises: Vec::new(),
+            user_name,
+            statistics: Statistics {
+                total_exercations: 0,
+                total_succeeds: 0,
+                total_failures: 0,
+                total_time: 0,
+            },
+            late_penalty: None,
+            submission: None,
+            summary: None,
+            interrupted: false,
+        }
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// 生成本次运行的唯一标识：时间戳 + 进程号
+pub fn new_run_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{:x}-{:x}", nanos, std::process::id())
+}
+
+// 扫描目录并返回其直接子目录和直接位于其中的 .rs 文件（不递归），跳过被排除的项；
+// 根目录下的 .rs 文件作为单文件习题评测
+fn scan_directory<P: AsRef<Path>>(dir: P, exclusions: &Exclusions) -> Result<Vec<PathBuf>, io::Error> {
+    let mut result = Vec::new();
+    let entries = fs::read_dir(&dir)?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let is_rs_file = path.is_file() && path.extension().map(|ext| ext == "rs").unwrap_or(false);
+        if (path.is_dir() || is_rs_file) && !exclusions.is_excluded(dir.as_ref(), &path) {
+            // 如果是目录或 .rs 文件，直接添加到结果列表
+            result.push(path);
+        }
+    }
+
+    // 排序保证各次运行（及各 CI 分片）的评测顺序一致
+    result.sort();
+    Ok(result)
+}
+
+// 获取目录下所有未被排除的 .rs 文件
+fn get_rs_files_in_directory<P: AsRef<Path>>(root: P, dir: &Path, exclusions: &Exclusions) -> Vec<PathBuf> {
+    let mut result = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().map(|ext| ext == "rs").unwrap_or(false)
+                && !exclusions.is_excluded(root.as_ref(), &path)
+            {
+                result.push(path);
+            }
+        }
+    }
+    result.sort();
+    result
+}
+
+// 章节目录中带有 exercise.toml 的子目录，检查其中的元数据能否解析
+fn exercise_file_dirs(root: &Path, dir: &Path, exclusions: &Exclusions) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut result: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join(info::EXERCISE_FILE).is_file() && !exclusions.is_excluded(root, path))
+        .collect();
+    result.sort();
+    result.retain(|path| match info::load_exercise_file(path) {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            false
+        }
+    });
+    result
+}
+
+// 评测一个 Cargo 项目习题，输出先写入缓冲区，评测结束后一次性打印
+pub fn grade_cargo_project(ctx: &RunContext, exercise_dir: &Path) -> ExerciseResult {
+    let started = Instant::now();
+    let name = exercise_dir.display().to_string();
+    let mut out = ExerciseOutput::new(&name, ctx.verbose);
+    out.out(&format!("\nEvaluating Cargo project: {}", name));
+    let meta = ctx.exercise_meta(exercise_dir);
+    let snapshot = take_snapshot(ctx, exercise_dir, &meta);
+    // 教师模式下注入隐藏测试，评测结束后删除
+    let mut hidden_tests = Vec::new();
+    if ctx.role == Role::Instructor {
+        match instructor::inject_hidden_tests(exercise_dir, &ctx.exercises_dir, &ctx.hidden_tests_dir) {
+            Ok(injected) => hidden_tests = injected,
+            Err(e) => out.err(&format!("Error injecting hidden tests: {}", e)),
+        }
+    }
+    let mut metrics = ExerciseMetrics::default();
+    let result = with_time_limit(&meta, &mut metrics, &mut out, |metrics, out| {
+        check_compile_time_code(ctx, exercise_dir, &meta, metrics, out)
+            && evaluate_cargo_project(exercise_dir, &meta, metrics, out)
+            && check_shared_crates(&ctx.shared_crates, metrics, out)
+    });
+    instructor::remove_hidden_tests(&hidden_tests);
+    let result = check_artifacts(ctx, snapshot.as_ref(), &meta, &mut metrics, &mut out) && result;
+    let placeholders_ok = check_placeholders(ctx, exercise_dir, &mut out);
+    let result = placeholders_ok && result;
+    let status = exercise_status(ctx, exercise_dir, result);
+    let code = failure_code(status, &metrics, placeholders_ok);
+    print_evaluation_result(&mut out, &name, status, code, &metrics.steps);
+    let (score, components) = score_exercise(exercise_dir, &meta, &metrics, &mut out);
+    let points = award_points(&meta, status, score, &mut out);
+    out.flush();
+    finish_exercise(ctx, ExerciseResult {
+        name,
+        result: status == ExerciseStatus::Passed,
+        status,
+        root: None,
+        reason: code.map(FailureCode::reason),
+        code,
+        peak_memory_kb: metrics.peak_memory_kb,
+        cases: metrics.cases,
+        tests: metrics.tests,
+        score,
+        components,
+        points,
+        max_points: meta.points,
+        hints_used: None,
+        variants: metrics.variants,
+        steps: metrics.steps.iter().map(|(step, status)| (step.to_string(), *status)).collect(),
+        duration_ms: Some(started.elapsed().as_millis() as u64),
+        phases_ms: metrics.phases_ms.iter().map(|(phase, ms)| (phase.to_string(), *ms)).collect(),
+        diagnostics: metrics.diagnostics,
+        log: out.errors().to_string(),
+    })
+}
+
+// 评测一道单文件习题；show_details 为真时附带打印编译警告等详细输出
+pub fn grade_single_file(ctx: &RunContext, rs_file: &Path, show_details: bool) -> ExerciseResult {
+    let started = Instant::now();
+    let name = rs_file.display().to_string();
+    let mut out = ExerciseOutput::new(&name, ctx.verbose);
+    out.out(&format!("\nEvaluating single file: {}", name));
+    let meta = ctx.exercise_meta(rs_file);
+    let chapter_dir = rs_file.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let snapshot = take_snapshot(ctx, chapter_dir, &meta);
+    let compiled = ctx.precompiled.take(rs_file);
+    let mut metrics = ExerciseMetrics::default();
+    let result = with_time_limit(&meta, &mut metrics, &mut out, |metrics, out| {
+        match evaluate_single_file(rs_file, &meta, compiled, metrics) {
+            Ok(()) => true,
+            Err(e) => {
+                out.err(&e);
+                false
+            }
+        }
+    });
+    let result = check_artifacts(ctx, snapshot.as_ref(), &meta, &mut metrics, &mut out) && result;
+    let placeholders_ok = check_placeholders(ctx, rs_file, &mut out);
+    let result = placeholders_ok && result;
+    let status = exercise_status(ctx, rs_file, result);
+    let code = failure_code(status, &metrics, placeholders_ok);
+    print_evaluation_result(&mut out, &name, status, code, &metrics.steps);
+    let (score, components) = score_exercise(rs_file, &meta, &metrics, &mut out);
+    let points = award_points(&meta, status, score, &mut out);
+    if show_details {
+        // 编译成功时的警告；编译失败时诊断信息已经作为错误输出
+        if metrics.steps.get("compiles") == Some(&StepStatus::Passed) && !metrics.compiler_output.is_empty() {
+            out.out(&format!("Compiler Output for {}: \n{}", name, metrics.compiler_output));
+        }
+        print_test_results(rs_file, &metrics.tests, &mut out);
+    }
+    out.flush();
+    finish_exercise(ctx, ExerciseResult {
+        name,
+        result: status == ExerciseStatus::Passed,
+        status,
+        root: None,
+        reason: code.map(FailureCode::reason),
+        code,
+        peak_memory_kb: metrics.peak_memory_kb,
+        cases: metrics.cases,
+        tests: metrics.tests,
+        score,
+        components,
+        points,
+        max_points: meta.points,
+        hints_used: None,
+        variants: metrics.variants,
+        steps: metrics.steps.iter().map(|(step, status)| (step.to_string(), *status)).collect(),
+        duration_ms: Some(started.elapsed().as_millis() as u64),
+        phases_ms: metrics.phases_ms.iter().map(|(phase, ms)| (phase.to_string(), *ms)).collect(),
+        diagnostics: metrics.diagnostics,
+        log: out.errors().to_string(),
+    })
+}
+
+// 未通过习题的失败代码：优先取评测流程中记录的代码，其次是残留占位符，其余归为其他检查
+fn failure_code(status: ExerciseStatus, metrics: &ExerciseMetrics, placeholders_ok: bool) -> Option<FailureCode> {
+    match status {
+        ExerciseStatus::Passed => None,
+        ExerciseStatus::InProgress => Some(FailureCode::NotDone),
+        ExerciseStatus::Failed => Some(metrics.failure.unwrap_or(if placeholders_ok {
+            FailureCode::Other
+        } else {
+            FailureCode::Placeholder
+        })),
+    }
+}
+
+// 配置了评分细则时计算得分并打印明细
+fn score_exercise(exercise: &Path, meta: &ExerciseMeta, metrics: &ExerciseMetrics, out: &mut ExerciseOutput) -> (Option<f64>, Vec<ScoreComponent>) {
+    if meta.rubric.is_empty() {
+        return (None, Vec::new());
+    }
+    let clippy = meta.clippy.clone().unwrap_or_default();
+    let (score, components) = rubric::score(&meta.rubric, &clippy, exercise, &metrics.steps, out);
+    out.out(&format!("Score: {}", rubric::describe(score, &components)));
+    (Some(score), components)
+}
+
+// 配置了分值时计算获得的分数：通过得满分，配置了评分细则时按得分折算
+fn award_points(meta: &ExerciseMeta, status: ExerciseStatus, score: Option<f64>, out: &mut ExerciseOutput) -> Option<f64> {
+    let max_points = meta.points?;
+    let points = match score {
+        Some(score) => (max_points * score / 10.0).round() / 10.0,
+        None if status == ExerciseStatus::Passed => max_points,
+        None => 0.0,
+    };
+    out.out(&format!("Points: {}/{}", points, max_points));
+    Some(points)
+}
+
+// 按扫描顺序列出本次运行范围内的全部习题：Cargo 项目目录和单文件习题
+pub fn collect_exercises(ctx: &RunContext, exercise_dirs: Vec<PathBuf>, exclusions: &Exclusions) -> Vec<PathBuf> {
+    let mut exercises = discover_exercises(exercise_dirs, exclusions, &ctx.config);
+    exercises.retain(|exercise| ctx.includes(exercise));
+    for (id, paths) in name_collisions(&exercises) {
+        let paths: Vec<String> = paths.iter().map(|path| path.display().to_string()).collect();
+        eprintln!(
+            "Warning: {} share the exercise name '{}'; their results overwrite each other in reports",
+            paths.join(" and "),
+            id
+        );
+    }
+    exercises
+}
+
+// 习题的标识：路径去掉 ./ 前缀和 .rs 扩展名，统一用 / 分隔
+fn exercise_id(exercise: &Path) -> String {
+    let path = if exercise.extension().map(|ext| ext == "rs").unwrap_or(false) {
+        exercise.with_extension("")
+    } else {
+        exercise.to_path_buf()
+    };
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 标识相同的习题（如同名的 x.rs 和 Cargo 项目 x，或重复指定的习题根目录），按标识分组返回；
+// 报告、进度和 webhook 都按名称索引习题，冲突时后评测的结果会覆盖先前的
+pub fn name_collisions(exercises: &[PathBuf]) -> Vec<(String, Vec<PathBuf>)> {
+    let mut by_id: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for exercise in exercises {
+        by_id.entry(exercise_id(exercise)).or_default().push(exercise.clone());
+    }
+    by_id.into_iter().filter(|(_, paths)| paths.len() > 1).collect()
+}
+
+// 按扫描顺序列出各章节目录中的习题；排除规则相对于章节所在的根目录
+pub fn discover_exercises(exercise_dirs: Vec<PathBuf>, exclusions: &Exclusions, config: &Config) -> Vec<PathBuf> {
+    let mut exercises = Vec::new();
+    for exercise_dir in exercise_dirs {
+        if exercise_dir.is_file() {
+            // 直接位于根目录下的单文件习题，扫描时已检查过排除规则
+            exercises.push(exercise_dir);
+        } else if exercise_dir.is_dir() {
+            let root = exercise_dir.parent().unwrap_or(Path::new("."));
+            // 章节目录中有清单时按清单列出习题；清单有误时报告后按目录结构推断
+            match Info::load(&exercise_dir).and_then(|info| info.map(|info| info.exercises(&exercise_dir)).transpose()) {
+                Ok(Some(listed)) => {
+                    exercises.extend(listed.into_iter().filter(|path| !exclusions.is_excluded(root, path)));
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Error: {}", e),
+            }
+            if exercise_dir.join("Cargo.toml").exists() {
+                // 如果目录下有 Cargo.toml 文件，按元数据声明的布局评测，默认认为这是一个完整的 Cargo 项目
+                let layout = config.exercise_meta(&exercise_dir).layout.unwrap_or_default();
+                if layout != DirLayout::Cargo {
+                    exercises.extend(get_rs_files_in_directory(root, &exercise_dir, exclusions));
+                }
+                if layout != DirLayout::Files {
+                    exercises.push(exercise_dir);
+                }
+            } else {
+                // 如果目录下没有 Cargo.toml 文件，则认为目录中的每个 .rs 文件都是单文件习题，
+                // 带有 exercise.toml 的子目录是 Cargo 项目习题
+                exercises.extend(get_rs_files_in_directory(root, &exercise_dir, exclusions));
+                exercises.extend(exercise_file_dirs(root, &exercise_dir, exclusions));
+            }
+        }
+    }
+    // 元数据中配置了顺序的习题按顺序排在前面，其余保持扫描顺序
+    exercises.sort_by_cached_key(|exercise| config.exercise_meta(exercise).order.unwrap_or(u32::MAX));
+    exercises
+}
+
+// 扫描各习题根目录，按根目录顺序返回所有章节目录和根目录下的单文件习题；
+// 根目录中有清单时改为返回清单中的习题
+pub fn scan_roots(roots: &[PathBuf], exclusions: &Exclusions) -> Result<Vec<PathBuf>, String> {
+    let mut exercise_dirs = Vec::new();
+    for root in roots {
+        if let Some(info) = Info::load(root)? {
+            let listed = info.exercises(root)?;
+            exercise_dirs.extend(listed.into_iter().filter(|path| !exclusions.is_excluded(root, path)));
+            continue;
+        }
+        let entries = scan_directory(root, exclusions)
+            .map_err(|e| format!("Error scanning exercises directory {}: {}", root.display(), e))?;
+        exercise_dirs.extend(entries);
+    }
+    Ok(exercise_dirs)
+}
+
+// 清理各习题根目录下的 target 目录
+pub fn clean_roots(roots: &[PathBuf], exclusions: &Exclusions) -> Result<(), io::Error> {
+    for root in roots {
+        clean_target_dirs(root, exclusions)?;
+    }
+    Ok(())
+}
+
+// run <name>：按路径或名称查找习题；名称可以是习题文件名（不含 .rs）、Cargo 项目目录名、
+// 元数据中的习题名称，或相对于习题根目录的路径
+pub fn resolve_exercise(roots: &[PathBuf], name: &str, exclusions: &Exclusions, config: &Config) -> Result<PathBuf, String> {
+    let path = Path::new(name);
+    if path.is_dir() || (path.is_file() && path.extension().map(|ext| ext == "rs").unwrap_or(false)) {
+        return Ok(path.to_path_buf());
+    }
+    let exercise_dirs = scan_roots(roots, exclusions)?;
+    let name = name.trim_end_matches(".rs").trim_end_matches('/');
+    let matches: Vec<PathBuf> = discover_exercises(exercise_dirs, exclusions, config)
+        .into_iter()
+        .filter(|exercise| {
+            let relative = roots
+                .iter()
+                .find_map(|root| exercise.strip_prefix(root).ok())
+                .unwrap_or(exercise)
+                .with_extension("");
+            relative == Path::new(name)
+                || exercise.file_stem().map(|stem| stem == name).unwrap_or(false)
+                || config.exercise_meta(exercise).name.as_deref() == Some(name)
+        })
+        .collect();
+    match matches.as_slice() {
+        [] => Err(format!("Exercise {} not found", name)),
+        [exercise] => Ok(exercise.clone()),
+        _ => Err(format!(
+            "Exercise name {} is ambiguous; use one of:\n{}",
+            name,
+            matches.iter().map(|m| format!("  {}", m.display())).collect::<Vec<_>>().join("\n")
+        )),
+    }
+}
+
+// 单题评测完成后的收尾工作，如标注所属根目录、记录提示使用情况、推送 webhook
+fn finish_exercise(ctx: &RunContext, mut result: ExerciseResult) -> ExerciseResult {
+    result.root = roots::root_of(&ctx.roots, Path::new(&result.name)).map(ExerciseRoot::label);
+    result.hints_used = HintLog::load().ok().map(|log| log.used(&result.name)).filter(|used| *used > 0);
+    if let Some(webhook) = &ctx.webhook {
+        webhook.notify(&result);
+    }
+    if let Some(dir) = &ctx.annotations {
+        if let Err(e) = annotations::write(dir, &result) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+    result
+}
+
+// 将单题结果计入报告
+pub fn record_result(report: &mut Report, result: ExerciseResult) {
+    if result.result {
+        report.statistics.total_succeeds += 1;
+    } else {
+        report.statistics.total_failures += 1;
+    }
+    report.exercises.push(result);
+}
+
+// 策略禁止编译期代码时，检查项目中没有构建脚本和过程宏，有则不编译直接判为失败
+fn check_compile_time_code(ctx: &RunContext, exercise_dir: &Path, meta: &ExerciseMeta, metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    let policy = meta.compile_time_code.unwrap_or(ctx.config.compile_time_code);
+    if policy == CompileTimePolicy::Allow {
+        return true;
+    }
+    match compile_time::find_compile_time_code(exercise_dir) {
+        Ok(found) if found.is_empty() => true,
+        Ok(found) => {
+            metrics.fail(FailureCode::CompileTimeCode);
+            out.err("Build scripts and proc-macros are not allowed in this exercise; it was not compiled:");
+            for item in found {
+                out.err(&format!("  - {}", item));
+            }
+            false
+        }
+        Err(e) => {
+            out.err(&e);
+            false
+        }
+    }
+}
+
+// 评测完整的 Cargo 项目；配置了 feature 组合时逐个组合评测，全部通过才算通过
+fn evaluate_cargo_project(exercise_dir: &Path, meta: &ExerciseMeta, metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    if meta.feature_sets.is_empty() {
+        return evaluate_cargo_variant(exercise_dir, &[], meta, metrics, out);
+    }
+    let mut passed = true;
+    for feature_set in &meta.feature_sets {
+        let label = feature_set.label();
+        out.out(&format!("Features: {}", label));
+        let tested = metrics.tests.len();
+        let variant_passed = evaluate_cargo_variant(exercise_dir, &feature_set.cargo_args(), meta, metrics, out);
+        // 各组合运行同一组测试，测试名后注明组合
+        for test in &mut metrics.tests[tested..] {
+            test.name = format!("{} [{}]", test.name, label);
+        }
+        if !variant_passed {
+            out.err(&format!("Failed with features: {}", label));
+        }
+        metrics.variants.push(VariantResult { name: label, passed: variant_passed });
+        passed &= variant_passed;
+    }
+    passed
+}
+
+// 以给定的 cargo 参数（feature 选项）构建、测试、检查并按需运行 Cargo 项目
+fn evaluate_cargo_variant(exercise_dir: &Path, cargo_args: &[String], meta: &ExerciseMeta, metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    // panic 断言和内存限制类习题还需要实际运行编译出的程序；
+    // 声明了评测方式时，compile 只构建，run 只运行程序不测试，test 按习题内容决定是否运行程序
+    let needs_tests = !matches!(meta.mode, Some(ExerciseMode::Compile | ExerciseMode::Run));
+    let needs_run = match meta.mode {
+        Some(ExerciseMode::Compile) => false,
+        Some(ExerciseMode::Run) => true,
+        _ => {
+            meta.expect_panic.is_some()
+                || meta.memory_limit_mb.is_some()
+                || !meta.cases.is_empty()
+                || meta.generator.is_some()
+        }
+    };
+    // cargo test --no-run 一次编译好依赖、crate 本身和测试，之后的测试步骤只运行不再编译；
+    // 需要运行程序时再构建可执行文件，此时依赖已经编译好，只需编译 crate 本身
+    let no_run_args: Vec<String> = std::iter::once("--no-run".to_string()).chain(cargo_args.iter().cloned()).collect();
+    let started = Instant::now();
+    let build_result = if needs_tests {
+        run_cargo_command(exercise_dir, "test", &no_run_args, metrics, out)
+            && (!needs_run || run_cargo_command(exercise_dir, "build", cargo_args, metrics, out))
+    } else {
+        run_cargo_command(exercise_dir, "build", cargo_args, metrics, out)
+    };
+    metrics.record_time("build", started);
+    metrics.record_step("compiles", StepStatus::from_result(build_result));
+    if !build_result {
+        // 编译失败时测试、clippy 和运行程序都没有意义，记为跳过
+        metrics.fail(FailureCode::CompileError);
+        if needs_tests {
+            metrics.record_step("tests", StepStatus::Skipped);
+            metrics.record_step("clippy", StepStatus::Skipped);
+        }
+        if needs_run {
+            metrics.record_step("run", StepStatus::Skipped);
+        }
+        return false;
+    }
+
+    let (test_result, clippy_result) = if needs_tests {
+        let started = Instant::now();
+        // 某个测试程序失败后仍运行其余的测试程序（集成测试、文档测试），报告中列出全部测试的结果
+        let test_args: Vec<String> = cargo_args.iter().cloned().chain(std::iter::once("--no-fail-fast".to_string())).collect();
+        let test_result = run_cargo_command(exercise_dir, "test", &test_args, metrics, out);
+        metrics.record_time("test", started);
+        let started = Instant::now();
+        let clippy = meta.clippy.clone().unwrap_or_default();
+        let clippy_args: Vec<String> = cargo_args.iter().cloned().chain(std::iter::once("--".to_string())).chain(clippy.args()).collect();
+        let clippy_result = run_cargo_command(exercise_dir, "clippy", &clippy_args, metrics, out);
+        metrics.record_time("clippy", started);
+        // 说明 clippy 按什么标准判定，学生可在本地用同样的参数复现
+        if !clippy_result {
+            out.err(&format!("Clippy settings: {}", clippy.describe()));
+        }
+        metrics.record_step("tests", StepStatus::from_result(test_result));
+        metrics.record_step("clippy", StepStatus::from_result(clippy_result));
+        if !test_result {
+            metrics.fail(FailureCode::TestFailure);
+        } else if !clippy_result {
+            metrics.fail(FailureCode::Clippy);
+        }
+        (test_result, clippy_result)
+    } else {
+        (true, true)
+    };
+    let run_result = if needs_run {
+        let started = Instant::now();
+        let result = cargo_binary(exercise_dir)
+            .and_then(|binary| execute_program(&binary, exercise_dir, meta, metrics));
+        metrics.record_time("run", started);
+        let run_result = result.map_err(|e| out.err(&e)).is_ok();
+        metrics.record_step("run", StepStatus::from_result(run_result));
+        run_result
+    } else {
+        true
+    };
+
+    test_result && clippy_result && run_result
+}
+
+// Cargo 项目编译出的可执行文件（<target 目录>/debug/<包名>）
+fn cargo_binary(exercise_dir: &Path) -> Result<PathBuf, String> {
+    let manifest = fs::read_to_string(exercise_dir.join("Cargo.toml"))
+        .map_err(|e| format!("Failed to read Cargo.toml: {}", e))?;
+    let manifest: toml::Table = toml::from_str(&manifest).map_err(|e| format!("Failed to parse Cargo.toml: {}", e))?;
+    let name = manifest
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .ok_or("Cargo.toml has no package name")?;
+    let binary = build_cache::target_dir(exercise_dir)
+        .join("debug")
+        .join(name)
+        .with_extension(std::env::consts::EXE_EXTENSION);
+    if binary.exists() {
+        Ok(binary)
+    } else {
+        Err(format!("Compiled binary {} not found", binary.display()))
+    }
+}
+
+// 检查公共 crate 未被习题修改
+fn check_shared_crates(shared_crates: &[SharedCrate], metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    let modified = shared::modified_crates(shared_crates);
+    for path in &modified {
+        out.err(&format!("Shared crate {} was modified; it must be used read-only", path.display()));
+    }
+    if !modified.is_empty() {
+        metrics.fail(FailureCode::SharedCrateModified);
+    }
+    modified.is_empty()
+}
+
+// 根据评测结果和"未完成"标记确定习题状态
+fn exercise_status(ctx: &RunContext, exercise: &Path, result: bool) -> ExerciseStatus {
+    if !result {
+        return ExerciseStatus::Failed;
+    }
+    match &ctx.config.not_done_marker {
+        Some(marker) if has_marker(exercise, marker) => ExerciseStatus::InProgress,
+        _ => ExerciseStatus::Passed,
+    }
+}
+
+// 习题源码中是否仍包含指定标记
+fn has_marker(exercise: &Path, marker: &str) -> bool {
+    placeholders::exercise_sources(exercise)
+        .iter()
+        .any(|file| fs::read_to_string(file).map(|c| c.contains(marker)).unwrap_or(false))
+}
+
+// 检查习题中是否残留未修改的模板占位符，按配置给出警告或判为失败
+fn check_placeholders(ctx: &RunContext, exercise: &Path, out: &mut ExerciseOutput) -> bool {
+    let found = placeholders::find_placeholders(exercise, &ctx.config.placeholders.markers);
+    for placeholder in &found {
+        out.err(&format!(
+            "Placeholder `{}` left at {}:{}",
+            placeholder.marker,
+            placeholder.file.display(),
+            placeholder.line
+        ));
+    }
+    found.is_empty() || ctx.config.placeholders.action == PlaceholderAction::Warn
+}
+
+// 配置了产物检查时，记录评测前习题目录（Cargo 测试的工作目录）中的文件；
+// 程序不在沙箱中而是在当前目录运行时，记录整个课程目录
+fn take_snapshot(ctx: &RunContext, exercise_dir: &Path, meta: &ExerciseMeta) -> Option<Snapshot> {
+    ctx.config.artifacts.as_ref()?;
+    let dir = if meta.isolate_fs.unwrap_or(true) { exercise_dir } else { Path::new(".") };
+    Some(Snapshot::take(dir))
+}
+
+// 检查评测过程中在课程目录（沙箱之外）新建的文件是否都在允许列表中
+fn check_artifacts(ctx: &RunContext, snapshot: Option<&Snapshot>, meta: &ExerciseMeta, metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    let (Some(config), Some(snapshot)) = (&ctx.config.artifacts, snapshot) else {
+        return true;
+    };
+    match config.unexpected(snapshot.new_files(), &meta.artifacts_allow) {
+        Ok(files) if files.is_empty() => true,
+        Ok(files) => {
+            out.err("Unexpected files were created outside the sandbox (remove them, or write to a temporary directory instead):");
+            for file in &files {
+                out.err(&format!("  - {}", file.display()));
+            }
+            if config.action == PlaceholderAction::Fail {
+                metrics.fail(FailureCode::Artifacts);
+            }
+            config.action == PlaceholderAction::Warn
+        }
+        Err(e) => {
+            out.err(&e);
+            false
+        }
+    }
+}
+
+// 评测单文件习题
+fn evaluate_single_file(exercise_file: &Path, meta: &ExerciseMeta, compiled: Option<Compiled>, metrics: &mut ExerciseMetrics) -> Result<(), String> {
+    if meta.mode == Some(ExerciseMode::Test) {
+        return evaluate_single_file_tests(exercise_file, metrics);
+    }
+    let result = run_rustc_command(exercise_file, meta, compiled, metrics);
+    let compiled = metrics.steps.get("compiles") == Some(&StepStatus::Passed);
+    let run_only = match meta.mode {
+        // 只检查能否编译，run_rustc_command 编译后不运行程序
+        Some(ExerciseMode::Compile) => return result,
+        Some(ExerciseMode::Run) => true,
+        _ => !harness::has_tests(exercise_file),
+    };
+    if run_only {
+        // 没有 #[test] 函数时，程序运行通过即视为测试通过；编译失败时不运行
+        metrics.record_step("tests", if compiled { StepStatus::from_result(result.is_ok()) } else { StepStatus::Skipped });
+        return result;
+    }
+
+    // 有 #[test] 函数时，程序运行记为 run，再以 rustc --test 编译运行测试，按测试结果评分
+    metrics.record_step("run", if compiled { StepStatus::from_result(result.is_ok()) } else { StepStatus::Skipped });
+    if !compiled {
+        metrics.record_step("tests", StepStatus::Skipped);
+        return result;
+    }
+    let started = Instant::now();
+    let tests = run_tests(exercise_file, metrics);
+    metrics.record_time("test", started);
+    metrics.record_step("tests", StepStatus::from_result(tests.is_ok()));
+    result.and(tests)
+}
+
+// 以测试方式评测单文件习题：只以 rustc --test 编译运行测试，不运行 main
+fn evaluate_single_file_tests(exercise_file: &Path, metrics: &mut ExerciseMetrics) -> Result<(), String> {
+    let started = Instant::now();
+    let tests = run_tests(exercise_file, metrics);
+    metrics.record_time("test", started);
+    let compiled = metrics.failure != Some(FailureCode::TestCompileError);
+    metrics.record_step("compiles", StepStatus::from_result(compiled));
+    metrics.record_step("tests", if compiled { StepStatus::from_result(tests.is_ok()) } else { StepStatus::Skipped });
+    tests
+}
+
+// 运行单文件习题中的 #[test] 函数，记录每个测试的结果
+fn run_tests(exercise_file: &Path, metrics: &mut ExerciseMetrics) -> Result<(), String> {
+    let mut diagnostics = Vec::new();
+    let run = harness::run_tests(exercise_file, &mut diagnostics);
+    metrics.record_diagnostics(diagnostics);
+    let run = run.inspect_err(|_| metrics.fail(FailureCode::TestCompileError))?;
+    metrics.tests = run.tests;
+    if run.passed {
+        return Ok(());
+    }
+    metrics.fail(FailureCode::TestFailure);
+    // 只保留失败测试的输出，逐个测试的结果记录在报告中
+    let failures = run.output.find("\nfailures:\n").map_or(run.output.as_str(), |start| &run.output[start + 1..]);
+    Err(format!("Tests failed:\n{}", failures))
+}
+
+// 运行 rustc 编译并执行单文件习题；已经预先编译好时直接使用编译结果
+fn run_rustc_command(exercise_file: &Path, meta: &ExerciseMeta, compiled: Option<Compiled>, metrics: &mut ExerciseMetrics) -> Result<(), String> {
+    let Compiled { output, binary: compiled_file, build_dir, duration } = match compiled {
+        Some(compiled) => compiled,
+        None => precompile::compile(exercise_file)?,
+    };
+    metrics.record_duration("build", duration);
+
+    metrics.record_step("compiles", StepStatus::from_result(output.status.success()));
+    let parsed = diagnostics::parse(&String::from_utf8_lossy(&output.stderr), Path::new(""));
+    metrics.compiler_output = parsed.rendered + &parsed.other;
+    metrics.record_diagnostics(parsed.diagnostics);
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(&build_dir);
+        metrics.fail(FailureCode::CompileError);
+        return Err(format!("rustc compilation failed: {}", metrics.compiler_output));
+    }
+    if meta.mode == Some(ExerciseMode::Compile) {
+        let _ = fs::remove_dir_all(&build_dir);
+        return Ok(());
+    }
+
+    // 执行编译后的文件
+    let exercise_dir = exercise_file.parent().unwrap_or(Path::new("."));
+    let started = Instant::now();
+    let result = execute_program(&compiled_file, exercise_dir, meta, metrics);
+    metrics.record_time("run", started);
+    let _ = fs::remove_dir_all(&build_dir);
+    result
+}
+
+// 运行习题程序，记录峰值内存，并按元数据检查退出状态、panic 信息和内存限制
+fn execute_program(binary: &Path, exercise_dir: &Path, meta: &ExerciseMeta, metrics: &mut ExerciseMetrics) -> Result<(), String> {
+    // 配置了输入生成器时，按本次运行的种子生成额外用例
+    let mut meta = meta.clone();
+    if let Some(generator) = meta.generator.take() {
+        let seed = std::env::var(SEED_ENV).ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+        let reference = generator.reference.unwrap_or_default();
+        let generated = generator::generate_cases(Path::new(&generator.source), Path::new(&reference), generator.count, seed)?;
+        meta.cases.extend(generated);
+    }
+    let meta = &meta;
+
+    // 输出比对类习题逐个运行用例
+    if !meta.cases.is_empty() {
+        let run = cases::run_cases(binary, exercise_dir, meta)?;
+        metrics.cases = run.results;
+        metrics.peak_memory_kb = run.peak_memory_kb;
+        if let Some(code) = FailureCode::from_cases(&metrics.cases) {
+            metrics.fail(code);
+        }
+        check_memory_limit(meta, run.peak_memory_kb).inspect_err(|_| metrics.fail(FailureCode::MemoryLimit))?;
+        return if run.failures.is_empty() { Ok(()) } else { Err(run.failures.join("\n")) };
+    }
+
+    let sandbox = Sandbox::for_exercise(meta, exercise_dir)?;
+    let options = process::RunOptions {
+        isolate_network: meta.isolate_network.unwrap_or(false),
+        working_dir: sandbox.as_ref().map(Sandbox::path),
+        ..Default::default()
+    };
+    let measured = process::run_measured(&mut Command::new(binary), options)
+        .map_err(|e| format!("Failed to execute compiled file: {}", e))?;
+    metrics.peak_memory_kb = measured.peak_memory_kb;
+    if measured.timed_out {
+        metrics.fail(FailureCode::Timeout);
+        return Err("Execution timed out".to_string());
+    }
+    let output = measured.output;
+
+    match &meta.expect_panic {
+        Some(pattern) => check_panic(&output, pattern).inspect_err(|_| metrics.fail(FailureCode::PanicMismatch))?,
+        None if !output.status.success() => {
+            metrics.fail(FailureCode::RuntimeError);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Execution failed: {}", stderr));
+        }
+        None => {}
+    }
+    check_memory_limit(meta, measured.peak_memory_kb).inspect_err(|_| metrics.fail(FailureCode::MemoryLimit))
+}
+
+// 检查峰值内存是否超出习题的内存限制
+fn check_memory_limit(meta: &ExerciseMeta, peak_memory_kb: Option<u64>) -> Result<(), String> {
+    if let (Some(limit_mb), Some(peak_kb)) = (meta.memory_limit_mb, peak_memory_kb) {
+        if peak_kb > limit_mb * 1024 {
+            return Err(format!(
+                "Memory limit exceeded: peak usage {:.1} MB, limit {} MB",
+                peak_kb as f64 / 1024.0,
+                limit_mb
+            ));
+        }
+    }
+    Ok(())
+}
+
+// 要求程序以匹配 pattern 的信息 panic
+fn check_panic(output: &std::process::Output, pattern: &str) -> Result<(), String> {
+    let regex = Regex::new(pattern).map_err(|e| format!("Invalid expect_panic pattern '{}': {}", pattern, e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if output.status.success() {
+        return Err(format!("Expected the program to panic with a message matching '{}', but it exited successfully", pattern));
+    }
+    // panic 信息位于 "panicked at" 之后（新版本 rustc 中在下一行）
+    let message = match stderr.find("panicked at") {
+        Some(start) => &stderr[start..],
+        None => return Err(format!("Expected a panic, but the program failed without panicking:\n{}", stderr)),
+    };
+    if regex.is_match(message) {
+        Ok(())
+    } else {
+        Err(format!("The program panicked, but its message does not match '{}':\n{}", pattern, stderr))
+    }
+}
+
+// 运行 cargo 命令；诊断以 JSON 格式输出，解析后记录下来，再按原来的文本形式打印
+fn run_cargo_command(exercise_dir: &Path, command: &str, args: &[String], metrics: &mut ExerciseMetrics, out: &mut ExerciseOutput) -> bool {
+    let mut cargo = Command::new("cargo");
+    cargo.arg(command).arg("--message-format=json").args(args).current_dir(exercise_dir);
+    build_cache::configure(&mut cargo, exercise_dir);
+    let output = process::output(&mut cargo).map_err(|e| format!("Failed to execute cargo {}: {}", command, e));
+
+    match output {
+        Ok(output) => {
+            let parsed = diagnostics::parse(&String::from_utf8_lossy(&output.stdout), exercise_dir);
+            if !parsed.other.is_empty() {
+                out.out(&parsed.other);
+            }
+            let errors = parsed.rendered + &String::from_utf8_lossy(&output.stderr);
+            if !errors.is_empty() {
+                out.err(&errors);
+            }
+            metrics.record_diagnostics(parsed.diagnostics);
+            // 运行测试时记录各测试的结果，--no-run 只编译
+            if command == "test" && !args.iter().any(|arg| arg == "--no-run") {
+                metrics.tests.extend(harness::parse_cargo(&parsed.other, &String::from_utf8_lossy(&output.stderr)));
+            }
+            output.status.success()
+        },
+        Err(e) => {
+            out.err(&e);
+            false
+        }
+    }
+}
+
+// 打印每道题目的评测结果，并使用颜色输出
+fn print_evaluation_result(
+    out: &mut ExerciseOutput,
+    name: &str,
+    status: ExerciseStatus,
+    code: Option<FailureCode>,
+    steps: &BTreeMap<&'static str, StepStatus>,
+) {
+    match status {
+        ExerciseStatus::Passed => out.out(&display::paint(&format!("{}{}: PASSED", display::prefix(Status::Pass), name), Tone::Good)), // 绿色表示成功
+        // 红色表示失败，附带失败代码
+        ExerciseStatus::Failed => out.out(&display::paint(
+            &format!("{}{}: FAILED [{}]", display::prefix(Status::Fail), name, code.unwrap_or(FailureCode::Other).as_str()),
+            Tone::Bad,
+        )),
+        // 黄色表示能编译但尚未完成
+        ExerciseStatus::InProgress => out.out(&format!(
+            "{} (remove the marker comment when you are done)",
+            display::paint(&format!("{}{}: IN PROGRESS", display::prefix(Status::Partial), name), Tone::Warn)
+        )),
+    }
+    // 失败时列出各步骤的结果，区分编译失败、测试失败和 clippy 警告
+    if status == ExerciseStatus::Failed && !steps.is_empty() {
+        out.out(&format!("Steps: {}", steps::describe(steps)));
+    }
+}
+
+// 用 jobs 个工作线程评测习题，结果按习题顺序返回；
+// 各习题的输出在评测完成后整体打印（verbose 模式下逐行带习题名前缀），不会相互穿插；
+// 每道习题评测完成时调用 on_result（如写入检查点）
+pub fn grade_parallel(
+    ctx: &RunContext,
+    exercises: &[PathBuf],
+    jobs: usize,
+    progress: &Mutex<Progress>,
+    on_result: impl Fn(&ExerciseResult) + Sync,
+) -> Vec<ExerciseResult> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<ExerciseResult>>> = Mutex::new(exercises.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, exercises.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                // 被中断后不再开始新的习题；中断时正在评测的习题结果不完整，丢弃
+                let Some(exercise) = exercises.get(index).filter(|_| !process::interrupted()) else {
+                    break;
+                };
+                lock(progress).start(exercise);
+                let result = grade_exercise(ctx, exercise, false);
+                lock(progress).finish(exercise);
+                if process::interrupted() {
+                    break;
+                }
+                on_result(&result);
+                lock(&results)[index] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap_or_else(|e| e.into_inner()).into_iter().flatten().collect()
+}
+
+// 工作线程 panic 后锁会中毒，其余线程仍可继续使用其中的数据
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn grade_exercise(ctx: &RunContext, exercise: &Path, show_details: bool) -> ExerciseResult {
+    if exercise.is_dir() {
+        grade_cargo_project(ctx, exercise)
+    } else {
+        grade_single_file(ctx, exercise, show_details)
+    }
+}
+
+// 逐个打印单文件习题中 #[test] 函数的结果，失败测试的输出已在评测时作为错误输出
+fn print_test_results(exercise_file: &Path, tests: &[TestResult], out: &mut ExerciseOutput) {
+    if tests.is_empty() {
+        return;
+    }
+    out.out(&format!("Tests in {}:", exercise_file.display()));
+    for test in tests {
+        let (status, tone) = match test.status {
+            harness::TestStatus::Passed => (Status::Pass, Tone::Good),
+            harness::TestStatus::Failed => (Status::Fail, Tone::Bad),
+            harness::TestStatus::Ignored => (Status::Skip, Tone::Warn),
+        };
+        out.out(&format!("  {} {}", display::paint(display::symbol(status), tone), test.name));
+    }
+}
+
+// 清理 exercises 目录下的所有 target 目录，被排除的目录不做处理
+fn clean_target_dirs<P: AsRef<Path>>(base_dir: P, exclusions: &Exclusions) -> Result<(), io::Error> {
+    let entries = fs::read_dir(&base_dir)?;
+
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() && !exclusions.is_excluded(base_dir.as_ref(), &path) {
+            // 如果是目录，检查是否包含 target 目录
+            let target_dir = path.join("target");
+            if target_dir.exists() {
+                fs::remove_dir_all(target_dir)?;
+                println!("Successfully cleaned target directory in: {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// 保存评测结果到 JSON 文件
+pub fn save_report_to_json(file_name: &Path, report: &Report) -> io::Result<()> {
+    let file = File::create(file_name)?;
+    serde_json::to_writer_pretty(file, report)?;
+    Ok(())
+}
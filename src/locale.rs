@@ -0,0 +1,197 @@
+use std::sync::OnceLock;
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, UtcOffset};
+
+// 启动时记录的本地时区偏移；进程有多个线程后 time 无法再安全地读取本地时区
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+// 记录本地时区偏移，须在启动任何线程之前调用
+pub fn capture_local_offset() {
+    if let Ok(offset) = UtcOffset::current_local_offset() {
+        let _ = LOCAL_OFFSET.set(offset);
+    }
+}
+
+// 报告和看板中数字、百分比、时长、日期的本地化格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Zh,
+    De,
+    Fr,
+    Es,
+}
+
+// 同时保存机器可读的值（ISO 8601 或数字）和按语言环境格式化后的文本
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Formatted<T> {
+    pub value: T,
+    pub display: String,
+}
+
+// 写入评测报告的本地化摘要
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LocalizedSummary {
+    pub locale: String,
+    pub finished_at: Formatted<String>,
+    pub duration: Formatted<String>,
+    // 通过率（百分数）
+    pub pass_rate: Formatted<f64>,
+}
+
+impl Locale {
+    // 优先使用配置中的语言，其次是 LC_ALL / LC_MESSAGES / LANG 环境变量，都无法识别时使用英语
+    pub fn resolve(configured: Option<&str>) -> Locale {
+        let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty());
+        configured
+            .map(str::to_string)
+            .or(from_env)
+            .and_then(|tag| Locale::parse(&tag))
+            .unwrap_or(Locale::En)
+    }
+
+    // 解析 "zh-CN"、"de_DE.UTF-8" 之类的语言标签，只看语言部分
+    fn parse(tag: &str) -> Option<Locale> {
+        let language = tag.split(['-', '_', '.']).next().unwrap_or_default().to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "zh" => Some(Locale::Zh),
+            "de" => Some(Locale::De),
+            "fr" => Some(Locale::Fr),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Zh => "zh",
+            Locale::De => "de",
+            Locale::Fr => "fr",
+            Locale::Es => "es",
+        }
+    }
+
+    // 小数点和千位分隔符
+    fn separators(self) -> (char, char) {
+        match self {
+            Locale::En | Locale::Zh => ('.', ','),
+            Locale::De | Locale::Es => (',', '.'),
+            Locale::Fr => (',', '\u{202f}'),
+        }
+    }
+
+    pub fn number(self, value: f64, decimals: usize) -> String {
+        let (decimal, thousands) = self.separators();
+        let text = format!("{:.*}", decimals, value);
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", text.as_str()),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push(thousands);
+            }
+            grouped.push(digit);
+        }
+        if fraction.is_empty() {
+            format!("{}{}", sign, grouped)
+        } else {
+            format!("{}{}{}{}", sign, grouped, decimal, fraction)
+        }
+    }
+
+    // 百分数，如 85.5 -> "85.5%" / "85,5 %"
+    pub fn percent(self, percent: f64, decimals: usize) -> String {
+        let number = self.number(percent, decimals);
+        match self {
+            Locale::En | Locale::Zh => format!("{}%", number),
+            Locale::De | Locale::Fr | Locale::Es => format!("{}\u{a0}%", number),
+        }
+    }
+
+    // 时长，如 3725 秒 -> "1 h 2 min 5 s" / "1小时2分5秒"
+    pub fn duration(self, seconds: u64) -> String {
+        let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+        let units = match self {
+            Locale::En | Locale::Fr | Locale::Es => [" h", " min", " s"],
+            Locale::Zh => ["小时", "分", "秒"],
+            Locale::De => [" Std.", " Min.", " Sek."],
+        };
+        let parts: Vec<String> = [hours, minutes, seconds]
+            .iter()
+            .zip(units)
+            .skip_while(|(value, _)| **value == 0)
+            .map(|(value, unit)| format!("{}{}", value, unit))
+            .collect();
+        let separator = if self == Locale::Zh { "" } else { " " };
+        if parts.is_empty() {
+            format!("0{}", units[2])
+        } else {
+            parts.join(separator)
+        }
+    }
+
+    // 日期和时间（分钟精度）
+    pub fn date_time(self, at: OffsetDateTime) -> String {
+        const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+        let (year, month, day) = (at.year(), u8::from(at.month()), at.day());
+        let time = format!("{:02}:{:02}", at.hour(), at.minute());
+        match self {
+            Locale::En => format!("{} {}, {}, {}", MONTHS[month as usize - 1], day, year, time),
+            Locale::Zh => format!("{}年{}月{}日 {}", year, month, day, time),
+            Locale::De => format!("{:02}.{:02}.{}, {}", day, month, year, time),
+            Locale::Fr => format!("{:02}/{:02}/{} {}", day, month, year, time),
+            Locale::Es => format!("{:02}/{:02}/{}, {}", day, month, year, time),
+        }
+    }
+
+    // 当前时间（启动时记录的本地时区，无法确定时区时使用 UTC）
+    pub fn now(self) -> Formatted<String> {
+        let now = OffsetDateTime::now_utc().to_offset(LOCAL_OFFSET.get().copied().unwrap_or(UtcOffset::UTC));
+        Formatted {
+            value: now.format(&Rfc3339).unwrap_or_default(),
+            display: self.date_time(now),
+        }
+    }
+
+    // 一次评测运行的摘要：结束时间、总耗时和通过率
+    pub fn summary(self, total_seconds: u64, passed: usize, total: usize) -> LocalizedSummary {
+        let pass_rate = if total == 0 { 0.0 } else { (passed as f64 * 1000.0 / total as f64).round() / 10.0 };
+        LocalizedSummary {
+            locale: self.tag().to_string(),
+            finished_at: self.now(),
+            duration: Formatted {
+                value: iso_duration(total_seconds),
+                display: self.duration(total_seconds),
+            },
+            pass_rate: Formatted {
+                value: pass_rate,
+                display: self.percent(pass_rate, 1),
+            },
+        }
+    }
+}
+
+// ISO 8601 时长，如 3725 秒 -> "PT1H2M5S"
+fn iso_duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    let mut text = "PT".to_string();
+    if hours > 0 {
+        text.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        text.push_str(&format!("{}M", minutes));
+    }
+    if seconds > 0 || text == "PT" {
+        text.push_str(&format!("{}S", seconds));
+    }
+    text
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+use serde::Deserialize;
+use serde_json::json;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use crate::instructor::{AggregateReport, StudentSummary};
+
+// 成绩回传的内容类型（LTI Assignment and Grade Services）
+const SCORE_CONTENT_TYPE: &str = "application/vnd.ims.lis.v1.score+json";
+
+// 通过 LTI 成绩服务（AGS）把汇总后的成绩写回 LMS（Canvas、Moodle 等）的成绩册
+#[derive(Deserialize, Debug, Clone)]
+pub struct LtiConfig {
+    // 作业对应的成绩项（line item）地址，在 LMS 中部署工具后获得
+    pub lineitem: String,
+    // 保存访问令牌的环境变量，令牌需要 https://purl.imsglobal.org/spec/lti-ags/scope/score 权限
+    #[serde(default = "default_token_env")]
+    pub token_env: String,
+    // 学生姓名（报告中的 user_name）到 LMS 用户 ID（LTI 启动时的 sub）的对应关系，
+    // 未列出的学生以姓名作为用户 ID
+    #[serde(default)]
+    pub users: BTreeMap<String, String>,
+}
+
+fn default_token_env() -> String {
+    "LTI_ACCESS_TOKEN".to_string()
+}
+
+impl LtiConfig {
+    // 成绩项的 scores 端点，成绩项地址可能带有查询参数
+    fn scores_url(&self) -> String {
+        match self.lineitem.split_once('?') {
+            Some((path, query)) => format!("{}/scores?{}", path.trim_end_matches('/'), query),
+            None => format!("{}/scores", self.lineitem.trim_end_matches('/')),
+        }
+    }
+}
+
+// 回传每个学生的最终成绩（百分制），返回成功回传的人数；有学生回传失败时返回错误，列出失败的学生
+pub fn pass_back(aggregate: &AggregateReport, config: &LtiConfig) -> Result<usize, String> {
+    let token = env::var(&config.token_env)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| format!("{} is not set; it must hold an LTI access token for posting scores", config.token_env))?;
+    let url = config.scores_url();
+    println!("\nPosting scores to {}", config.lineitem);
+    let mut failed = Vec::new();
+    for student in &aggregate.students {
+        match post_score(&url, &token, config, student) {
+            Ok(()) => println!("  {}: {:.1}", student.student, student.final_score()),
+            Err(e) => {
+                eprintln!("  {}: {}", student.student, e);
+                failed.push(student.student.as_str());
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(aggregate.students.len())
+    } else {
+        Err(format!("Failed to post the scores of {}", failed.join(", ")))
+    }
+}
+
+fn post_score(url: &str, token: &str, config: &LtiConfig, student: &StudentSummary) -> Result<(), String> {
+    let user_id = config.users.get(&student.student).unwrap_or(&student.student);
+    let score = json!({
+        "userId": user_id,
+        "scoreGiven": student.final_score(),
+        "scoreMaximum": 100.0,
+        "activityProgress": "Completed",
+        "gradingProgress": "FullyGraded",
+        "timestamp": OffsetDateTime::now_utc().format(&Rfc3339).unwrap_or_default(),
+    });
+    ureq::post(url)
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("Content-Type", SCORE_CONTENT_TYPE)
+        .timeout(Duration::from_secs(10))
+        .send_string(&score.to_string())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
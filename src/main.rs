@@ -1,142 +1,798 @@
-use std::process::{Command, exit};
-use std::fs::{self, File};
+use std::fs;
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::io::{self, Write};
-use serde::{Serialize, Deserialize};
-use std::time::Instant;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct ExerciseResult {
-    name: String,
-    result: bool,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct Statistics {
-    total_exercations: usize,
-    total_succeeds: usize,
-    total_failures: usize,
-    total_time: u64,
-}
+use std::io;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use clap::Parser;
+use cargotest::*;
+use cargotest::cli::{Cli, CoursesCommand, Mode, ReportCommand};
+use cargotest::cache::{self, ResultCache};
+use cargotest::checkpoint::Checkpoint;
+use cargotest::completion::{Completion, COMPLETION_FILE};
+use cargotest::config::{Config, Exclusions, CONFIG_FILE};
+use cargotest::display::{Status, Tone};
+use cargotest::instructor::Role;
+use cargotest::locale::{self, Locale};
+use cargotest::manifest::{self, RunManifest};
+use cargotest::matrix::{ToolchainInfo, ToolchainMatrix};
+use cargotest::metadata::ExerciseMeta;
+use cargotest::output::VERBOSE_LOG_FILE;
+use cargotest::precompile::{self, Precompiled};
+use cargotest::progress::Progress;
+use cargotest::progression::Unlocks;
+use cargotest::tap::OutputFormat;
+use cargotest::shared::SharedCrate;
+use cargotest::webhook::Webhook;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Report {
-    exercises: Vec<ExerciseResult>,
-    user_name: Option<String>,
-    statistics: Statistics,
-}
+// 评测报告的文件名，保存在当前课程的状态目录中
+const REPORT_FILE: &str = "report.json";
+// run 单题评测的报告，与完整评测的报告分开保存，避免覆盖
+const RUN_REPORT_FILE: &str = "run-report.json";
+// 班级汇总报告的保存位置
+const AGGREGATE_FILE: &str = "aggregate.json";
+// 班级统计 HTML 看板的保存位置
+const DASHBOARD_FILE: &str = "aggregate.html";
+// coordinate 保存各学生报告的默认位置（状态目录中的子目录）
+#[cfg(feature = "grpc")]
+const SUBMISSION_REPORTS_DIR: &str = "reports/submissions";
+// 相似度检查中超过该比例即视为可疑
+const SIMILARITY_THRESHOLD: f64 = 0.8;
+// 旧版本解密参考答案包的位置（状态目录中的子目录），其中的明文在启动时删除
+const LEGACY_UNSEALED_DIR: &str = "unsealed";
+// 进程的退出状态：全部通过时为 0，有习题未通过时为 EXIT_FAILURES，
+// 评测器自身出错（配置错误、无法启动评测等）时为 EXIT_ERROR，与 clap 的参数错误一致
+const EXIT_FAILURES: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+// 总结中列出的耗时最长的习题数
+const SLOWEST_COUNT: usize = 3;
+// 计时的评测阶段，按执行顺序排列
+const PHASES: &[&str] = &["build", "test", "clippy", "run"];
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let exercises_dir = "exercises";
+    // 本地时区只能在单线程时读取，在启动任何线程之前记录
+    locale::capture_local_offset();
+    let mut args: Vec<String> = std::env::args().collect();
+    // 首次运行且没有任何参数时，用交互式向导生成配置文件，而不是只报告缺少子命令
+    if args.len() == 1 && onboarding::needed() {
+        let exercises_dir = std::env::var("EXERCISES_DIR").unwrap_or_else(|_| "exercises".to_string());
+        if let Err(e) = onboarding::run(&exercises_dir) {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+        return;
+    }
+    let mut cli = Cli::parse_from(&args);
+    // --course <name>：在 ~/.grader/courses/<name> 中运行，每门课程有独立的习题、清单和状态
+    if let Some(course) = &cli.course {
+        if let Err(e) = courses::enter(course) {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+    }
+    // rerun [--manifest <file>]：按运行清单重放一次运行，使用相同的参数、种子和习题集合
+    let mut replay_set = None;
+    if let Mode::Rerun { run_id, manifest } = &cli.mode {
+        let manifest = match manifest {
+            Some(manifest) => manifest.clone(),
+            None => manifest::find(run_id.as_deref()).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(EXIT_ERROR);
+            }),
+        };
+        // 在容器中评测的运行，在同一镜像中重放
+        if hermetic::image().is_none() {
+            match hermetic::rerun(&manifest) {
+                Ok(Some(code)) => exit(code),
+                Ok(None) => {}
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_ERROR);
+                }
+            }
+        }
+        match prepare_rerun(&args, &manifest) {
+            Ok((replay_args, replay_cli, set)) => {
+                args = replay_args;
+                cli = replay_cli;
+                replay_set = Some(set);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(EXIT_ERROR);
+            }
+        }
+    }
+    if let Mode::Courses { command } = &cli.mode {
+        // courses 在读取课程配置之前执行，只检查命令行指定的第一个习题目录
+        let exercises_dir = cli.exercises_dir.first().map(String::as_str).unwrap_or(roots::DEFAULT_ROOT);
+        let result = match command {
+            Some(CoursesCommand::List) | None => courses::list(exercises_dir),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+        return;
+    }
+    let verbose = cli.verbose;
+    let role = cli.role;
+    let grade_args = cli.mode.grade_args().cloned().unwrap_or_default();
+    // --format tap：标准输出只保留最后生成的 TAP，评测过程中的其他输出都转到标准错误
+    let mut tap_output = match grade_args.format {
+        OutputFormat::Tap => match tap::take_stdout() {
+            Ok(stdout) => Some(stdout),
+            Err(e) => {
+                eprintln!("Error redirecting stdout for TAP output: {}", e);
+                exit(EXIT_ERROR);
+            }
+        },
+        OutputFormat::Human => None,
+    };
+    // 随机种子，记录在运行清单中并通过 GRADER_SEED 传给习题程序
+    let seed = grade_args.seed.unwrap_or_else(unix_time);
+    std::env::set_var(SEED_ENV, seed.to_string());
+    let shard = grade_args.shard;
+    let start_time = Instant::now(); // 记录开始时间
+    let started_at = unix_time();
+
+    // 读取评测器配置
+    let mut config = match Config::load(CONFIG_FILE) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            exit(EXIT_ERROR);
+        }
+    };
+    let exclusions = match config.exclusions() {
+        Ok(exclusions) => exclusions,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            exit(EXIT_ERROR);
+        }
+    };
 
-    if args.len() < 2 {
-        eprintln!("Please provide a command: 'watch' or 'all'");
-        exit(1);
+    // 习题根目录；第一个根目录同时用于公共 crate、参考答案、隐藏测试和课堂模式
+    let roots = roots::resolve(&cli.exercises_dir, &config.roots);
+    let exercises_dir = roots[0].path.display().to_string();
+    let exercises_dir = exercises_dir.as_str();
+    let root_dirs: Vec<PathBuf> = roots.iter().map(|root| root.path.clone()).collect();
+
+    // --hermetic：在容器中以去掉 --hermetic 的参数重新运行，退出状态即容器中评测器的退出状态
+    if grade_args.hermetic {
+        if matches!(cli.mode, Mode::Watch { .. }) {
+            eprintln!("--hermetic is not supported in watch mode");
+            exit(EXIT_ERROR);
+        }
+        match hermetic::run(&args[1..], &config, &root_dirs) {
+            Ok(code) => exit(code),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(EXIT_ERROR);
+            }
+        }
     }
 
-    let mode = &args[1]; // 'watch' or 'all'
-    let start_time = Instant::now(); // 记录开始时间
+    display::set_ascii(cli.ascii || config.ascii);
+    display::set_theme(config.theme);
+
+    // 教师模式下，若只有加密包而没有明文目录，则解密到临时目录后使用，main 返回或 exit 时删除
+    let _ = fs::remove_dir_all(paths::state_file(LEGACY_UNSEALED_DIR));
+    let _unsealed = if role == Role::Instructor
+        && !Path::new(&config.solutions_dir).exists()
+        && Path::new(&config.solutions_bundle).exists()
+    {
+        match bundle::instructor_key().and_then(|key| bundle::unseal_temporary(Path::new(&config.solutions_bundle), &key)) {
+            Ok(unsealed) => {
+                config.solutions_dir = unsealed.path().join("solutions").display().to_string();
+                config.hidden_tests_dir = unsealed.path().join("hidden_tests").display().to_string();
+                Some(unsealed)
+            }
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // 登录、提交等不需要评测的命令
+    let mode = cli.mode.name();
+    let grading = matches!(cli.mode, Mode::Watch { .. } | Mode::All { .. } | Mode::Run { .. } | Mode::Share { .. } | Mode::Validate);
+    if !grading {
+        let result = match &cli.mode {
+            Mode::Login => config
+                .server
+                .as_ref()
+                .ok_or_else(|| format!("No [server] section configured in {}", CONFIG_FILE))
+                .and_then(auth::login),
+            Mode::Submit { flush } => config
+                .server
+                .as_ref()
+                .ok_or_else(|| format!("No [server] section configured in {}", CONFIG_FILE))
+                .and_then(|server| {
+                    if *flush {
+                        submit::flush_queue(server)
+                    } else {
+                        submit::submit_report(server, &paths::state_file(REPORT_FILE))
+                    }
+                }),
+            Mode::Report { command: ReportCommand::Merge { reports, output } } => {
+                report::merge_reports(reports).and_then(|merged| {
+                    let output = output.clone().unwrap_or_else(|| paths::state_file(REPORT_FILE));
+                    save_report_to_json(&output, &merged)
+                        .map_err(|e| format!("Error saving report to JSON file: {}", e))?;
+                    println!(
+                        "Merged {} exercise(s) into {}: {} passed, {} failed",
+                        merged.statistics.total_exercations,
+                        output.display(),
+                        merged.statistics.total_succeeds,
+                        merged.statistics.total_failures
+                    );
+                    Ok(())
+                })
+            }
+            Mode::Report { command: ReportCommand::Diff { old, new } } => {
+                let old = old.clone().or_else(|| archive::archived().pop()).ok_or_else(|| {
+                    format!("No archived reports in {} to compare with", archive::archive_dir().display())
+                });
+                let new = new.clone().unwrap_or_else(|| paths::state_file(REPORT_FILE));
+                old.and_then(|old| {
+                    let diff = report::diff_reports(&report::load(&old)?, &report::load(&new)?);
+                    println!("Comparing {} -> {}", old.display(), new.display());
+                    print!("{}", diff);
+                    Ok(())
+                })
+            }
+            Mode::Replay { run_id, realtime } => transcript::replay(run_id.as_deref(), *realtime),
+            Mode::Hint { exercise } => resolve_exercise(&root_dirs, exercise, &exclusions, &config).and_then(|exercise| {
+                let name = exercise.display().to_string();
+                let hints = hints::hints_for(&exercise, &config.exercise_meta(&exercise))?;
+                let revealed = hints::reveal_next(&name, &hints)?;
+                print!("{}", hints::render(&revealed, hints.len()));
+                Ok(())
+            }),
+            Mode::Show { exercise } => markdown::show_readme(exercise).map(|rendered| print!("{}", rendered)),
+            Mode::List { json } => list_exercises(&root_dirs, &exclusions, &config, role, *json),
+            Mode::Clean => {
+                clean_roots(&root_dirs, &exclusions)
+                    .map_err(|e| format!("Error cleaning target directories: {}", e))
+                    .and_then(|()| build_cache::remove().map_err(|e| format!("Error removing {}: {}", build_cache::CACHE_DIR, e)))
+                    .map(|removed| {
+                        if removed {
+                            println!("Removed the build cache in {}", build_cache::CACHE_DIR);
+                        }
+                    })
+            }
+            Mode::Bundle => role.require_instructor(mode).and_then(|_| {
+                let key = bundle::instructor_key()?;
+                let dirs = [
+                    ("solutions", Path::new(&config.solutions_dir)),
+                    ("hidden_tests", Path::new(&config.hidden_tests_dir)),
+                ];
+                let count = bundle::seal(&dirs, Path::new(&config.solutions_bundle), &key)?;
+                println!("Sealed {} file(s) into {}", count, config.solutions_bundle);
+                Ok(())
+            }),
+            Mode::Diff { exercise } => role.require_instructor(mode).and_then(|_| {
+                instructor::solution_diff(exercise, Path::new(exercises_dir), Path::new(&config.solutions_dir))
+                    .map(|diff| print!("{}", diff))
+            }),
+            Mode::Aggregate { reports } => role.require_instructor(mode).and_then(|_| aggregate(reports, &config)),
+            Mode::Similarity { submissions } => role
+                .require_instructor(mode)
+                .and_then(|_| instructor::similarity_check(submissions, SIMILARITY_THRESHOLD)),
+            Mode::Personalize { student, output, course_id, seed } => role.require_instructor(mode).and_then(|_| {
+                // 未指定课程标识时使用 --course 给出的课程名
+                let course = course_id.clone().or_else(|| cli.course.clone()).unwrap_or_default();
+                let variables = template::Variables::new(student, &course, *seed);
+                let count = template::personalize(Path::new(exercises_dir), output, &variables)?;
+                println!(
+                    "Generated {} file(s) for {} in {} (seed {})",
+                    count,
+                    student,
+                    output.display(),
+                    variables.seed
+                );
+                Ok(())
+            }),
+            #[cfg(feature = "grpc")]
+            Mode::Serve { addr, workers, allow_dirs } => {
+                // 不在沙箱中运行的习题可能在课程目录的任何位置写文件，检查新文件时无法区分是哪个请求的习题产生的，
+                // 同 all 一样逐个评测
+                let serialize = config.artifacts.is_some() && config.runs_outside_sandbox();
+                let workers = match workers {
+                    Some(workers) if *workers > 1 && serialize => {
+                        println!("Note: grading one request at a time because [artifacts] checks are enabled and some exercises run outside the sandbox");
+                        1
+                    }
+                    Some(workers) => *workers as usize,
+                    None if serialize => 1,
+                    None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+                };
+                // 各请求的 Cargo 项目习题共用构建缓存，公共依赖只编译一次
+                if config.shared_target {
+                    if let Err(e) = build_cache::enable() {
+                        eprintln!("Warning: failed to create {}, exercises build on their own: {}", build_cache::CACHE_DIR, e);
+                    }
+                }
+                if config.sccache && !build_cache::enable_sccache() {
+                    eprintln!("Warning: sccache is not installed; compiling without it");
+                }
+                grpc::serve(*addr, workers, config.clone(), role, PathBuf::from(exercises_dir), allow_dirs)
+            }
+            #[cfg(feature = "grpc")]
+            Mode::Coordinate { submissions, workers, slots, output } => role.require_instructor(mode).and_then(|_| {
+                let output = output.clone().unwrap_or_else(|| paths::state_dir().join(SUBMISSION_REPORTS_DIR));
+                let reports = coordinator::coordinate(submissions, workers, *slots as usize, &output)?;
+                aggregate(&reports, &config)
+            }),
+            // 评测模式、rerun 和 courses 不会走到这里
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+        return;
+    }
+
+    // Ctrl+C 时结束正在运行的编译和测试（包括下面的工具链检查和共享依赖预编译），
+    // 写出已评测部分的报告后退出；章节监视模式直接退出
+    process::handle_interrupts();
+
+    // 检查编译器版本是否满足课程要求；--toolchains 会逐个指定工具链，不做检查
+    let matrix_run = matches!(cli.mode, Mode::All { toolchains: Some(_), .. });
+    if let Some(required) = config.toolchain.as_ref().filter(|_| !matrix_run) {
+        if let Err(e) = required.ensure() {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+    }
+
+    // Cargo 项目习题共用 .grader-cache 中的构建缓存，公共依赖只编译一次
+    if config.shared_target {
+        if let Err(e) = build_cache::enable() {
+            eprintln!("Warning: failed to create {}, exercises build on their own: {}", build_cache::CACHE_DIR, e);
+        }
+    }
+    if config.sccache && !build_cache::enable_sccache() {
+        eprintln!("Warning: sccache is not installed; compiling without it");
+    }
+
+    // 预先编译课程公共 crate，供习题以 path 依赖的方式引用
+    let mut shared_crates = Vec::new();
+    for path in &config.shared_crates {
+        match SharedCrate::prepare(Path::new(exercises_dir).join(path)) {
+            Ok(shared) => shared_crates.push(shared),
+            Err(e) => {
+                eprintln!("Error preparing shared crate: {}", e);
+                exit(EXIT_ERROR);
+            }
+        }
+    }
 
-    // 扫描 exercises 目录，获取所有的直接子目录和文件
-    let exercise_dirs = match scan_directory(exercises_dir) {
-        Ok(entries) => entries,
+    // --grade-solutions：对参考答案目录跑完整评测流程，期望全部通过，
+    // 课程仓库可在 CI 中用它保证每道习题在工具链升级后仍然可解
+    let grade_solutions = grade_args.grade_solutions;
+    let metadata_dir = PathBuf::from(exercises_dir);
+    let solutions_dir = config.solutions_dir.clone();
+    let exercises_dir = if grade_solutions { solutions_dir.as_str() } else { exercises_dir };
+    let root_dirs = if grade_solutions { vec![PathBuf::from(&solutions_dir)] } else { root_dirs };
+
+    // 扫描各习题根目录，获取所有的直接子目录和文件
+    let exercise_dirs = match scan_roots(&root_dirs, &exclusions) {
+        Ok(exercise_dirs) => exercise_dirs,
         Err(e) => {
-            eprintln!("Error scanning exercises directory: {}", e);
-            exit(1);
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
         }
     };
 
-    let mut report = Report {
-        exercises: Vec::new(),
-        user_name: None,
-        statistics: Statistics {
-            total_exercations: 0,
-            total_succeeds: 0,
-            total_failures: 0,
-            total_time: 0,
-        },
+    let mut report = Report::new(config.user_name.clone());
+    report.shard = shard.map(|shard| shard.to_string());
+
+    // 记录评测过程中终端上的全部输出，日后可用 replay <运行 ID> 原样重新显示；
+    // TAP 输出不经过终端的标准输出，不记录
+    let recorded = matches!(cli.mode, Mode::Watch { .. } | Mode::All { .. } | Mode::Run { .. });
+    if recorded && grade_args.format == OutputFormat::Human {
+        if let Err(e) = transcript::start(&report.run_id, started_at, &args[1..], config.transcript_retention) {
+            eprintln!("Warning: {}", e);
+        }
+    }
+
+    // 课堂模式：评测前校验提交的 git 状态，记录被评测的确切提交
+    if let Some(classroom) = config.classroom.as_ref().filter(|_| !grade_solutions) {
+        match classroom.verify(Path::new(exercises_dir)) {
+            Ok(submission) => {
+                println!(
+                    "Grading commit {}{}",
+                    submission.commit,
+                    if submission.dirty { " (with uncommitted changes)" } else { "" }
+                );
+                report.submission = Some(submission);
+            }
+            Err(e) => {
+                eprintln!("Submission verification failed: {}", e);
+                exit(EXIT_ERROR);
+            }
+        }
+    }
+
+    // 后台线程先补发上次运行中投递失败的结果；评测参考答案时不推送结果
+    let webhook = config
+        .webhook
+        .clone()
+        .filter(|_| !grade_solutions)
+        .map(|webhook| Webhook::new(webhook, report.user_name.clone(), &report.run_id));
+
+    let ctx = RunContext {
+        exercises_dir: PathBuf::from(exercises_dir),
+        roots: roots.clone(),
+        metadata_dir,
+        hidden_tests_dir: PathBuf::from(&config.hidden_tests_dir),
+        shared_crates,
+        webhook,
+        // 评测参考答案时诊断指向参考答案，不写注解
+        annotations: (config.annotations && !grade_solutions).then(annotations::dir),
+        shard,
+        filter: grade_args.filter.clone(),
+        replay_set,
+        timeout_secs: grade_args.timeout.or(config.timeout_secs),
+        precompiled: Precompiled::default(),
+        config,
+        role,
+        verbose,
+    };
+
+    // 按进度解锁：学生模式下只评测已解锁的习题，评测后记录新通过的习题
+    let mut unlocks = if ctx.config.progression && ctx.role == Role::Student && !grade_solutions {
+        match Unlocks::load() {
+            Ok(unlocks) => Some(unlocks),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(EXIT_ERROR);
+            }
+        }
+    } else {
+        None
+    };
+    // 按顺序排列的全部习题，评测后据此列出仍被锁定的习题
+    let mut course = Vec::new();
+    // 仓库中的 .progress.json：记录各习题的完成状态，评测参考答案时不记录
+    let mut completion = (!grade_solutions).then(load_completion);
+
+    // verbose 模式下同时把输出保存到日志文件，结束时打印失败习题在其中的位置
+    let verbose_log = if verbose {
+        let path = paths::state_file(VERBOSE_LOG_FILE);
+        match output::start_verbose_log(&path) {
+            Ok(()) => Some(path),
+            Err(e) => {
+                eprintln!("Warning: failed to create {}: {}", path.display(), e);
+                None
+            }
+        }
+    } else {
+        None
     };
 
     // 根据模式选择执行逐题评测或一次性评测
-    if mode == "watch" {
-        // 逐题评测
-        for exercise_dir in exercise_dirs {
-            if exercise_dir.is_dir() {
-                let name = exercise_dir.display().to_string();
-                if exercise_dir.join("Cargo.toml").exists() {
-                    // 如果目录下有 Cargo.toml 文件，认为这是一个完整的 Cargo 项目
-                    println!("\nEvaluating Cargo project: {}", name);
-                    let result = evaluate_cargo_project(&exercise_dir);
-                    print_evaluation_result(&name, result);
-                    report.exercises.push(ExerciseResult { name, result });
-                    if result {
-                        report.statistics.total_succeeds += 1;
-                    } else {
-                        report.statistics.total_failures += 1;
+    match &cli.mode {
+        Mode::Watch { chapter: Some(chapter), .. } => watch_chapter(&ctx, chapter, &exclusions),
+        Mode::Watch { .. } => {
+            // 按顺序评测，停在第一道未通过的习题上，保存文件时自动重新评测
+            let exercises = collect_exercises(&ctx, exercise_dirs, &exclusions);
+            course = exercises.clone();
+            for result in watch_exercises(&ctx, &exercises, &root_dirs, completion.as_mut()) {
+                record_result(&mut report, result);
+            }
+        }
+        Mode::All { toolchains, jobs, force, resume, changed_only, .. } => {
+            // 一次性评测所有题目：先确定评测范围，以便报告整体进度
+            let exercises = collect_exercises(&ctx, exercise_dirs, &exclusions);
+            course = exercises.clone();
+            let exercises = match &unlocks {
+                Some(unlocks) => unlocks.partition(&exercises, &ctx.config).0,
+                None => exercises,
+            };
+            // --resume：沿用被中断的运行中已经评测完的习题结果，只评测其余的习题；
+            // 没有 --resume 时新的运行会覆盖检查点
+            let checkpoint = match Checkpoint::load() {
+                Ok(checkpoint) => checkpoint,
+                Err(e) => {
+                    eprintln!("Warning: {}; ignoring it", e);
+                    None
+                }
+            };
+            let (exercises, checkpoint) = match checkpoint {
+                Some(checkpoint) if *resume => {
+                    println!("Resuming run {}", checkpoint.run_id);
+                    report.run_id = checkpoint.run_id.clone();
+                    transcript::resume(&report.run_id);
+                    if let Some(webhook) = &ctx.webhook {
+                        webhook.set_run_id(&report.run_id);
                     }
-                } else {
-                    // 如果目录下没有 Cargo.toml 文件，则认为目录中的每个 .rs 文件都是单文件习题
-                    let rs_files = get_rs_files_in_directory(&exercise_dir);
-                    for rs_file in rs_files {
-                        let file_name = rs_file.display().to_string();
-                        println!("\nEvaluating single file: {}", file_name);
-                        let result = evaluate_single_file(&rs_file);
-                        print_evaluation_result(&file_name, result);
-                        report.exercises.push(ExerciseResult { name: file_name, result });
-                        if result {
-                            report.statistics.total_succeeds += 1;
-                        } else {
-                            report.statistics.total_failures += 1;
+                    let mut graded = HashSet::new();
+                    for result in checkpoint.results() {
+                        if exercises.iter().any(|exercise| exercise.display().to_string() == result.name) {
+                            println!("{}: {} (graded before the interruption)", result.name, if result.result { "PASSED" } else { "FAILED" });
+                            graded.insert(result.name.clone());
+                            record_result(&mut report, result);
                         }
-                        // 打印详细的编译器输出和cargo test输出
-                        print_compiler_output(&rs_file);
-                        print_cargo_test_output(&rs_file);
-                        // 在每个文件评测结束后，等待用户输入以进行下一道题目
-                        if !ask_to_continue() {
-                            break;
+                    }
+                    let remaining = exercises.into_iter().filter(|exercise| !graded.contains(&exercise.display().to_string())).collect();
+                    (remaining, checkpoint)
+                }
+                Some(checkpoint) => {
+                    println!("Note: starting over; run `all --resume` to continue the interrupted run {} instead", checkpoint.run_id);
+                    (exercises, Checkpoint::new(&report.run_id))
+                }
+                None if *resume => {
+                    println!("Note: there is no interrupted run to resume; grading all exercises");
+                    (exercises, Checkpoint::new(&report.run_id))
+                }
+                None => (exercises, Checkpoint::new(&report.run_id)),
+            };
+            let checkpoint = Mutex::new(checkpoint);
+            // 缓存键未变的习题直接沿用上次的结果，只评测其余的习题；
+            // --force、多工具链评测和 rerun 重放时全部重新评测
+            let incremental = !*force && toolchains.is_none() && ctx.replay_set.is_none();
+            let mut cache = incremental.then(ResultCache::load);
+            let environment = if incremental { cache::environment(ctx.role) } else { String::new() };
+            let (mut reused, mut skipped) = (0, 0);
+            let exercises = match cache.as_mut() {
+                Some(cache) => {
+                    let mut remaining = Vec::new();
+                    for exercise in exercises {
+                        let name = exercise.display().to_string();
+                        // --changed-only：文件戳未变的习题不读取内容，也不显示
+                        let stamp = cache::exercise_stamp(&ctx, &exercise, &environment);
+                        if let Some(result) = stamp.as_ref().filter(|_| *changed_only).and_then(|stamp| cache.get_unchanged(&name, stamp)) {
+                            record_result(&mut report, result);
+                            skipped += 1;
+                            continue;
+                        }
+                        match cache::exercise_key(&ctx, &exercise, &environment).and_then(|key| cache.get(&name, &key)) {
+                            Some(result) => {
+                                println!("{}: {} (unchanged, result reused)", name, if result.result { "PASSED" } else { "FAILED" });
+                                record_result(&mut report, result);
+                                reused += 1;
+                                if let Some(stamp) = stamp {
+                                    cache.update_stamp(&name, stamp);
+                                }
+                            }
+                            None => remaining.push(exercise),
                         }
                     }
+                    remaining
                 }
+                None => exercises,
+            };
+            if reused > 0 {
+                println!("Reused the results of {} unchanged exercise(s); run `all --force` to grade them again", reused);
             }
-        }
-    } else if mode == "all" {
-        // 一次性评测所有题目
-        for exercise_dir in exercise_dirs {
-            if exercise_dir.is_dir() {
-                let name = exercise_dir.display().to_string();
-                if exercise_dir.join("Cargo.toml").exists() {
-                    // 如果目录下有 Cargo.toml 文件，认为这是一个完整的 Cargo 项目
-                    println!("\nEvaluating Cargo project: {}", name);
-                    let result = evaluate_cargo_project(&exercise_dir);
-                    print_evaluation_result(&name, result);
-                    report.exercises.push(ExerciseResult { name, result });
-                    if result {
-                        report.statistics.total_succeeds += 1;
-                    } else {
-                        report.statistics.total_failures += 1;
+            if *changed_only {
+                match (skipped, exercises.len()) {
+                    (0, _) => {}
+                    (_, 0) => println!("No exercise changed since the last run ({} skipped)", skipped),
+                    _ => println!("Skipped {} exercise(s) unchanged since the last run", skipped),
+                }
+            }
+            // --toolchains stable,beta,nightly：在每个工具链下各评测一遍，生成矩阵报告；
+            // 评测报告记录第一个工具链的结果
+            let toolchains = toolchains.as_deref().map(matrix::parse_toolchains).unwrap_or_default();
+            let mut toolchain_matrix = ToolchainMatrix::new(&report.run_id);
+            for toolchain in &toolchains {
+                match matrix::toolchain_version(toolchain) {
+                    Ok(version) => toolchain_matrix.toolchains.push(ToolchainInfo { name: toolchain.clone(), version }),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        exit(EXIT_ERROR);
                     }
-                } else {
-                    // 如果目录下没有 Cargo.toml 文件，则认为目录中的每个 .rs 文件都是单文件习题
-                    let rs_files = get_rs_files_in_directory(&exercise_dir);
-                    for rs_file in rs_files {
-                        let file_name = rs_file.display().to_string();
-                        println!("\nEvaluating single file: {}", file_name);
-                        let result = evaluate_single_file(&rs_file);
-                        print_evaluation_result(&file_name, result);
-                        report.exercises.push(ExerciseResult { name: file_name, result });
-                        if result {
-                            report.statistics.total_succeeds += 1;
-                        } else {
-                            report.statistics.total_failures += 1;
+                }
+            }
+
+            // 单文件习题的编译只写临时目录，总是按 --jobs（默认为 CPU 核数）并行
+            let compile_jobs = match jobs {
+                Some(jobs) => *jobs as usize,
+                None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            };
+            // 不在沙箱中运行的习题要检查整个课程目录中的新文件，无法区分是哪道习题产生的，只能逐题评测；
+            // 其他习题只检查各自的习题目录，可以并行
+            let serialize = ctx.config.artifacts.is_some()
+                && exercises.iter().any(|exercise| !ctx.sandboxed(exercise));
+            let jobs = match jobs {
+                Some(jobs) if *jobs > 1 && serialize => {
+                    println!("Note: grading one exercise at a time because [artifacts] checks are enabled and some exercises run outside the sandbox");
+                    1
+                }
+                Some(jobs) => *jobs as usize,
+                None if serialize => 1,
+                None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            };
+            let passes = toolchains.len().max(1);
+            let progress = Mutex::new(Progress::new(&report.run_id, exercises.len() * passes));
+            for pass in 0..passes {
+                if process::interrupted() {
+                    break;
+                }
+                if let Some(toolchain) = toolchains.get(pass) {
+                    println!("\n=== Toolchain: {} ===", toolchain);
+                    std::env::set_var(matrix::TOOLCHAIN_ENV, toolchain);
+                }
+                // 依赖按工具链分别编译，每个工具链都要预热
+                if let Some(build_dir) = build_cache::build_dir().filter(|_| ctx.config.warm_up) {
+                    if let Err(e) = warmup::warm_up(&exercises, &build_dir) {
+                        eprintln!("Warning: dependency warm-up failed, exercises build their own dependencies: {}", e);
+                    }
+                }
+                precompile::precompile(&ctx, &exercises, compile_jobs);
+                // 周期性地把评测完的结果写入检查点，运行被中断后可以用 --resume 继续；
+                // 检查点只记录第一个工具链的结果
+                let on_result = |result: &ExerciseResult| {
+                    if pass == 0 {
+                        if let Err(e) = checkpoint.lock().unwrap_or_else(|e| e.into_inner()).record(result) {
+                            eprintln!("Error saving checkpoint: {}", e);
                         }
                     }
+                };
+                for result in grade_parallel(&ctx, &exercises, jobs, &progress, on_result) {
+                    toolchain_matrix.record(pass, &result.name, result.result);
+                    if pass == 0 {
+                        record_result(&mut report, result);
+                    }
+                }
+            }
+            progress.into_inner().unwrap_or_else(|e| e.into_inner()).done();
+            // 被中断时写出检查点中尚未保存的结果，以便用 --resume 继续
+            if process::interrupted() {
+                if let Err(e) = checkpoint.into_inner().unwrap_or_else(|e| e.into_inner()).save() {
+                    eprintln!("Error saving checkpoint: {}", e);
+                }
+            } else {
+                Checkpoint::remove();
+            }
+            // 缓存键在评测后计算：评测时 cargo 可能生成 Cargo.lock，下次运行时它已经存在
+            if let Some(cache) = cache.as_mut() {
+                for exercise in &exercises {
+                    let name = exercise.display().to_string();
+                    let key = cache::exercise_key(&ctx, exercise, &environment);
+                    let stamp = cache::exercise_stamp(&ctx, exercise, &environment).unwrap_or_default();
+                    if let (Some(key), Some(result)) = (key, report.exercises.iter().find(|result| result.name == name)) {
+                        cache.insert(&name, key, stamp, result);
+                    }
+                }
+                if let Err(e) = cache.save() {
+                    eprintln!("Error saving the result cache: {}", e);
+                }
+            }
+
+            if !toolchains.is_empty() {
+                std::env::remove_var(matrix::TOOLCHAIN_ENV);
+                toolchain_matrix.print();
+                let path = paths::state_file(matrix::MATRIX_FILE);
+                match toolchain_matrix.save(&path) {
+                    Ok(()) => println!("Toolchain matrix saved to {}", path.display()),
+                    Err(e) => eprintln!("Error saving toolchain matrix: {}", e),
                 }
             }
         }
-    } else {
-        eprintln!("Invalid command. Please use 'watch' or 'all'.");
-        exit(1);
+        Mode::Run { exercise, .. } => {
+            // 评测单道习题并打印详细输出
+            let exercise = match resolve_exercise(&root_dirs, exercise, &exclusions, &ctx.config) {
+                Ok(exercise) => exercise,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_ERROR);
+                }
+            };
+            if let Some(unlocks) = &unlocks {
+                let exercises = discover_exercises(exercise_dirs, &exclusions, &ctx.config);
+                let (_, locked) = unlocks.partition(&exercises, &ctx.config);
+                if let Some(locked) = locked.iter().find(|locked| locked.exercise == exercise) {
+                    eprintln!("{} is locked; pass {} first", exercise.display(), locked.waiting_for.join(", "));
+                    exit(EXIT_ERROR);
+                }
+            }
+            let result = if exercise.is_dir() {
+                grade_cargo_project(&ctx, &exercise)
+            } else {
+                grade_single_file(&ctx, &exercise, true)
+            };
+            if !process::interrupted() {
+                record_result(&mut report, result);
+            }
+        }
+        Mode::Share { exercise } => {
+            // 评测指定习题，并分享其失败日志
+            let result = if exercise.join("Cargo.toml").exists() {
+                grade_cargo_project(&ctx, exercise)
+            } else {
+                grade_single_file(&ctx, exercise, false)
+            };
+            if ctx.config.clean_targets {
+                let _ = clean_roots(&root_dirs, &exclusions);
+            }
+            if result.result {
+                println!("{} passes; there is nothing to share.", result.name);
+                return;
+            }
+            let log = share::anonymize(&format!("{}\n{}", result.name, result.log), report.user_name.as_deref());
+            match share::upload(&log, ctx.config.share.as_ref(), ctx.config.server.as_ref()) {
+                Ok(url) => println!("Shared failure log: {}", url),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(EXIT_ERROR);
+                }
+            }
+            return;
+        }
+        Mode::Validate => {
+            // 课程作者校验：题面、题面中的代码片段和参考答案
+            if let Err(e) = ctx.role.require_instructor(mode) {
+                eprintln!("{}", e);
+                exit(EXIT_ERROR);
+            }
+            validate::warn_mixed_layouts(&ctx.config, &exercise_dirs, &exclusions);
+            let exercises = collect_exercises(&ctx, exercise_dirs, &exclusions);
+            // 习题名称冲突在收集习题时已经列出
+            let problems = validate::validate(&ctx, &exercises) + name_collisions(&exercises).len();
+            if ctx.config.clean_targets {
+                let _ = clean_roots(&root_dirs, &exclusions);
+            }
+            if problems > 0 {
+                eprintln!("\n{} problem(s) found in {} exercise(s)", problems, exercises.len());
+                exit(EXIT_FAILURES);
+            }
+            println!("\nAll {} exercise(s) are valid.", exercises.len());
+            return;
+        }
+        // 其他子命令在前面已经处理并返回
+        _ => unreachable!("{} is not a grading mode", mode),
+    }
+
+    // 等待后台线程把结果推送完
+    if let Some(webhook) = &ctx.webhook {
+        webhook.finish();
+    }
+
+    // 被中断时只报告已经评测完的习题，删除未用上的预编译产物
+    if process::interrupted() {
+        report.interrupted = true;
+        ctx.precompiled.clear();
+        println!("\nInterrupted; reporting the {} exercise(s) graded so far", report.exercises.len());
+        if matches!(cli.mode, Mode::All { .. }) {
+            println!("Run `all --resume` to grade the rest");
+        }
+    }
+
+    // 记录新通过的习题，解锁后面的习题
+    if let Some(unlocks) = unlocks.as_mut() {
+        if unlocks.record(&report.exercises) > 0 {
+            if let Err(e) = unlocks.save() {
+                eprintln!("Error saving unlock state: {}", e);
+            }
+        }
+    }
+
+    if let Some(completion) = completion.as_mut() {
+        completion.record(&report.exercises);
+        if let Err(e) = completion.save() {
+            eprintln!("Error saving completion state: {}", e);
+        }
+        if matches!(cli.mode, Mode::All { .. }) {
+            println!("Course progress: {}/{} exercises done", completion.done_count(&course), course.len());
+        }
+    }
+
+    // --copy-errors：把第一道失败习题的错误信息复制到剪贴板
+    if grade_args.copy_errors {
+        match report.exercises.iter().find(|e| !e.result && !e.log.is_empty()) {
+            Some(failed) => copy_errors(failed),
+            None => println!("No failure details to copy."),
+        }
     }
 
     // 修正统计，total_exercations 为通过题目 + 失败题目
@@ -144,10 +800,17 @@ fn main() {
 
     // 计算总时间
     report.statistics.total_time = start_time.elapsed().as_secs(); // 评测结束时间 - 开始时间
+    report.summary = Some(Locale::resolve(ctx.config.locale.as_deref()).summary(
+        report.statistics.total_time,
+        report.statistics.total_succeeds,
+        report.statistics.total_exercations,
+    ));
 
-    // 清理 exercises 目录下的所有 target 目录
-    if let Err(e) = clean_target_dirs(exercises_dir) {
-        eprintln!("Error cleaning target directories: {}", e);
+    // 按配置在评测后清理 exercises 目录下的所有 target 目录
+    if ctx.config.clean_targets {
+        if let Err(e) = clean_roots(&root_dirs, &exclusions) {
+            eprintln!("Error cleaning target directories: {}", e);
+        }
     }
 
     // 输出总结信息
@@ -155,173 +818,571 @@ fn main() {
     println!("Total exercises: {}", report.statistics.total_exercations);
     println!("Total successes: {}", report.statistics.total_succeeds);
     println!("Total failures: {}", report.statistics.total_failures);
+    if ctx.roots.len() > 1 {
+        for root in &ctx.roots {
+            let label = root.label();
+            let exercises: Vec<&ExerciseResult> =
+                report.exercises.iter().filter(|exercise| exercise.root.as_deref() == Some(label.as_str())).collect();
+            let passed = exercises.iter().filter(|exercise| exercise.result).count();
+            println!("  {}: {}/{} passed", label, passed, exercises.len());
+        }
+    }
+    failure::print_summary(
+        report
+            .exercises
+            .iter()
+            .filter_map(|exercise| {
+                let error = exercise.diagnostics.iter().find(|diagnostic| diagnostic.level == "error");
+                exercise.code.map(|code| (exercise.name.as_str(), code, error))
+            }),
+    );
+    if let Some(unlocks) = &unlocks {
+        print_locked(unlocks, &course, &ctx.config);
+    }
+    if let Some(log_file) = &verbose_log {
+        print_log_index(&report, log_file);
+    }
+    // run 已经打印了单题的详细输出，不需要再给出建议
+    let single_run = matches!(cli.mode, Mode::Run { .. });
+    if !single_run {
+        print_slowest(&report.exercises);
+        failure::print_next_steps(
+            report
+                .exercises
+                .iter()
+                .filter_map(|exercise| exercise.reason.map(|reason| (exercise.name.as_str(), reason))),
+            ctx.config.server.is_some(),
+        );
+    }
+
+    // 按截止日期策略计算迟交扣分
+    if let Some(deadline) = ctx.config.deadline.as_ref().filter(|_| !grade_solutions) {
+        match deadline.apply(&ctx.exercises_dir, &report.exercises) {
+            Ok(penalty) => {
+                if penalty.days_late > 0 {
+                    println!(
+                        "Submitted {} day(s) late (due {}): score {:.1} -> {:.1} (-{:.0}%)",
+                        penalty.days_late, penalty.due, penalty.raw_score, penalty.adjusted_score, penalty.penalty_percent
+                    );
+                } else {
+                    println!("Score: {:.1} (submitted before the deadline)", penalty.raw_score);
+                }
+                report.late_penalty = Some(penalty);
+            }
+            Err(e) => eprintln!("Error applying deadline policy: {}", e),
+        }
+    }
 
     // 保存评测结果到 JSON 文件
-    if let Err(e) = save_report_to_json("report.json", &report) {
-        eprintln!("Error saving report to JSON file: {}", e);
+    let report_file = paths::state_file(if single_run { RUN_REPORT_FILE } else { REPORT_FILE });
+    // 完整评测的报告不直接覆盖，上一份移到归档目录中保留
+    if !single_run {
+        if let Err(e) = archive::archive(&report_file, ctx.config.report_retention) {
+            eprintln!("Error archiving the previous report: {}", e);
+        }
+    }
+    let saved = match save_report_to_json(&report_file, &report) {
+        Ok(()) => {
+            println!("Report saved to {}", report_file.display());
+            // 登录时服务器下发了签名密钥的，签名报告，submit 时校验
+            if let Err(e) = submit::sign_report(&report_file) {
+                eprintln!("Error signing the report: {}", e);
+            }
+            true
+        }
+        Err(e) => {
+            eprintln!("Error saving report to JSON file: {}", e);
+            false
+        }
+    };
+    if let Some(stdout) = tap_output.as_mut() {
+        if let Err(e) = stdout.write_all(tap::render(&report).as_bytes()).and_then(|()| stdout.flush()) {
+            eprintln!("Error writing TAP output: {}", e);
+        }
+    }
+    // JSON 报告已经写出，扩展名同为 json 的格式会覆盖它，跳过
+    for format in grade_args.report_format.iter().filter(|format| format.extension() != "json") {
+        let file = report_file.with_extension(format.extension());
+        match fs::write(&file, format.render(&report)) {
+            Ok(()) => println!("Report saved to {}", file.display()),
+            Err(e) => eprintln!("Error saving report to {}: {}", file.display(), e),
+        }
+    }
+    if let (true, false, Some(classroom), Some(submission)) = (saved, single_run, &ctx.config.classroom, &report.submission) {
+        // 课堂模式下在学生仓库中留下评测记录（标签或 grades 分支）
+        let summary = format!("{}/{} passed", report.statistics.total_succeeds, report.statistics.total_exercations);
+        match classroom::record_grades(classroom, &ctx.exercises_dir, submission, &report.run_id, &report_file, &summary) {
+            Ok(Some(name)) => println!("Recorded grading run in {}", name),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error recording grading run: {}", e),
+        }
+    }
+    // 在 GitHub Actions 中把摘要写入作业摘要，并按配置在 PR 上发布置顶评论
+    if !single_run {
+        github::publish(&report, &ctx.config.github);
     }
-}
 
-// 扫描目录并返回其直接子目录（不递归）
-fn scan_directory<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>, io::Error> {
-    let mut result = Vec::new();
-    let entries = fs::read_dir(dir)?;
+    // 写出运行清单，供日后用 rerun 复现本次运行
+    let mut manifest_args = args[1..].to_vec();
+    if grade_args.seed.is_none() {
+        manifest_args.extend(["--seed".to_string(), seed.to_string()]);
+    }
+    let names: Vec<String> = report.exercises.iter().map(|e| e.name.clone()).collect();
+    let manifest = RunManifest::new(&report.run_id, started_at, &manifest_args, Some(seed), &names);
+    if let Err(e) = manifest.save_run(ctx.config.manifest_retention) {
+        eprintln!("Error saving run manifest: {}", e);
+    }
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            // 如果是目录，直接添加到结果列表
-            result.push(path);
-        }
+    if report.interrupted {
+        exit(process::EXIT_INTERRUPTED);
+    }
+    if grade_solutions && report.statistics.total_failures > 0 {
+        eprintln!(
+            "{} reference solution(s) failed; every exercise must remain solvable",
+            report.statistics.total_failures
+        );
+        exit(EXIT_FAILURES);
+    }
+    // 有习题未通过时以非零状态退出，CI 可以据此判定失败；--no-fail-exit 时总是返回 0
+    if report.statistics.total_failures > 0 && !grade_args.no_fail_exit {
+        exit(EXIT_FAILURES);
     }
+    transcript::finish();
+}
 
-    Ok(result)
+// 退出前结束运行记录（管道中剩余的输出照常写到终端和记录文件），并删除解密出的参考答案
+fn exit(code: i32) -> ! {
+    transcript::finish();
+    bundle::remove_unsealed();
+    std::process::exit(code)
 }
 
-// 获取目录下所有的 .rs 文件
-fn get_rs_files_in_directory<P: AsRef<Path>>(dir: P) -> Vec<PathBuf> {
-    let mut result = Vec::new();
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
-                    result.push(path);
-                }
-            }
-        }
+// 汇总学生报告，生成班级统计和看板；配置了 LTI 时把汇总后的成绩回传到 LMS 成绩册
+fn aggregate(reports: &[String], config: &Config) -> Result<(), String> {
+    let locale = Locale::resolve(config.locale.as_deref());
+    let aggregate = instructor::aggregate_reports(reports, AGGREGATE_FILE, DASHBOARD_FILE, locale)?;
+    match &config.lti {
+        Some(lti) => lti::pass_back(&aggregate, lti).map(|count| println!("Posted {} score(s) to the gradebook", count)),
+        None => Ok(()),
     }
-    result
 }
 
-// 评测完整的 Cargo 项目
-fn evaluate_cargo_project(exercise_dir: &PathBuf) -> bool {
-    let build_result = run_cargo_command(exercise_dir, "build");
-    let test_result = run_cargo_command(exercise_dir, "test");
-    let clippy_result = run_cargo_command(exercise_dir, "clippy");
+// 读取运行清单，检查与当前环境的差异，返回重放用的参数、解析结果和习题集合
+fn prepare_rerun(args: &[String], path: &Path) -> Result<(Vec<String>, Cli, HashSet<PathBuf>), String> {
+    let manifest = RunManifest::load(path)?;
+    let differences = manifest.differences();
+    if differences.is_empty() {
+        println!("Replaying run {} with an identical configuration.", manifest.run_id);
+    } else {
+        eprintln!("Warning: the environment differs from run {}:", manifest.run_id);
+        for difference in &differences {
+            eprintln!("  - {}", difference);
+        }
+    }
 
-    build_result && test_result && clippy_result
+    let mut replay_args = vec![args[0].clone()];
+    replay_args.extend(manifest.args.iter().cloned());
+    let cli = Cli::try_parse_from(&replay_args)
+        .map_err(|e| format!("{} does not describe a grading run: {}", path.display(), e))?;
+    if cli.mode.grade_args().is_none() {
+        return Err(format!("{} does not describe a grading run", path.display()));
+    }
+    Ok((replay_args, cli, manifest.exercise_set()))
 }
 
-// 评测单文件习题
-fn evaluate_single_file(exercise_file: &PathBuf) -> bool {
-    run_rustc_command(exercise_file).is_ok()
-}
+// list：列出课程中的全部习题及其类型
+fn list_exercises(roots: &[PathBuf], exclusions: &Exclusions, config: &Config, role: Role, json: bool) -> Result<(), String> {
+    let exercise_dirs = scan_roots(roots, exclusions)?;
+    let exercises = discover_exercises(exercise_dirs, exclusions, config);
+    // 按进度解锁时不列出被锁定的习题，只给出数量
+    let (exercises, locked) = if config.progression && role == Role::Student {
+        let (unlocked, locked) = Unlocks::load()?.partition(&exercises, config);
+        (unlocked, locked.len())
+    } else {
+        (exercises, 0)
+    };
+    let results = last_results();
+    // 完成状态优先取仓库中的 .progress.json，其中没有的习题取最近的报告
+    let completion = load_completion();
+    struct Row<'a> {
+        name: String,
+        kind: &'static str,
+        result: Option<&'a ExerciseResult>,
+        status: Option<ExerciseStatus>,
+        done: bool,
+        meta: ExerciseMeta,
+    }
+    let rows: Vec<Row> = exercises
+        .iter()
+        .map(|exercise| {
+            let name = exercise.display().to_string();
+            let result = results.get(&name);
+            Row {
+                kind: if exercise.is_dir() { "cargo" } else { "single" },
+                status: completion.exercises.get(&name).map(|state| state.status).or(result.map(|result| result.status)),
+                done: completion.is_done(exercise),
+                meta: config.exercise_meta(exercise),
+                name,
+                result,
+            }
+        })
+        .collect();
 
-// 运行 rustc 编译并执行单文件习题
-fn run_rustc_command(exercise_file: &PathBuf) -> Result<(), String> {
-    let output = Command::new("rustc")
-        .arg(exercise_file)
-        .output()
-        .map_err(|e| format!("Failed to execute rustc: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("rustc compilation failed: {}", stderr));
-    }
-
-    // 执行编译后的文件
-    let compiled_file = exercise_file.with_extension(""); // 生成编译后的可执行文件路径
-    let output = Command::new(compiled_file)
-        .output()
-        .map_err(|e| format!("Failed to execute compiled file: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Execution failed: {}", stderr));
+    if json {
+        let entries: Vec<serde_json::Value> = rows
+            .iter()
+            .map(|Row { name, kind, result, status, done, meta }| {
+                serde_json::json!({
+                    "name": name,
+                    "kind": kind,
+                    "status": status,
+                    "done": done,
+                    "points": result.and_then(|result| result.points),
+                    "max_points": meta.points,
+                    "tags": meta.tags,
+                })
+            })
+            .collect();
+        let listing = serde_json::json!({ "exercises": entries, "locked": locked });
+        println!("{}", serde_json::to_string_pretty(&listing).map_err(|e| e.to_string())?);
+        return Ok(());
     }
 
+    let width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0).max("EXERCISE".len());
+    println!("{:<8}{:<13}{:<10}{:<width$}  TAGS", "TYPE", "STATUS", "POINTS", "EXERCISE");
+    for Row { name, kind, result, status, done, meta } in &rows {
+        let status = match status {
+            // 通过后又修改过，需要重新评测
+            Some(ExerciseStatus::Passed) if !done && completion.exercises.contains_key(name) => "changed",
+            Some(ExerciseStatus::Passed) => "passed",
+            Some(ExerciseStatus::Failed) => "failed",
+            Some(ExerciseStatus::InProgress) => "in progress",
+            None => "not run",
+        };
+        let points = match (result.and_then(|result| result.points), meta.points) {
+            (Some(points), Some(max_points)) => format!("{}/{}", points, max_points),
+            (None, Some(max_points)) => format!("-/{}", max_points),
+            _ => "-".to_string(),
+        };
+        let line = format!("{:<8}{:<13}{:<10}{:<width$}  {}", kind, status, points, name, meta.tags.join(", "));
+        println!("{}", line.trim_end());
+    }
+    if locked > 0 {
+        println!("({} more exercise(s) unlock as you pass these)", locked);
+    }
     Ok(())
 }
 
-// 运行 cargo 命令（如 build, test, clippy 等）
-fn run_cargo_command(exercise_dir: &PathBuf, command: &str) -> bool {
-    let output = Command::new("cargo")
-        .arg(command)
-        .current_dir(exercise_dir)
-        .output()
-        .map_err(|e| format!("Failed to execute cargo {}: {}", command, e));
+// 各习题最近一次的评测结果：先取批量评测的报告，再用更新的单题评测报告覆盖
+fn last_results() -> BTreeMap<String, ExerciseResult> {
+    let mut reports: Vec<(std::time::SystemTime, Report)> = [REPORT_FILE, RUN_REPORT_FILE]
+        .iter()
+        .map(|file| paths::state_file(file))
+        .filter_map(|file| {
+            let modified = fs::metadata(&file).and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, report::load(&file).ok()?))
+        })
+        .collect();
+    reports.sort_by_key(|(modified, _)| *modified);
+    reports
+        .into_iter()
+        .flat_map(|(_, report)| report.exercises)
+        .map(|result| (result.name.clone(), result))
+        .collect()
+}
 
-    match output {
-        Ok(output) => {
-            if !output.stdout.is_empty() {
-                println!("{}", String::from_utf8_lossy(&output.stdout));
-            }
-            if !output.stderr.is_empty() {
-                eprintln!("{}", String::from_utf8_lossy(&output.stderr));
-            }
-            output.status.success()
-        },
-        Err(_) => false,
+// 按进度解锁时列出仍被锁定的习题数，以及解锁下一道习题需要先通过的习题
+fn print_locked(unlocks: &Unlocks, course: &[PathBuf], config: &Config) {
+    let (_, locked) = unlocks.partition(course, config);
+    if let Some(next) = locked.first() {
+        println!(
+            "{} exercise(s) locked; pass {} to unlock {}",
+            locked.len(),
+            next.waiting_for.join(", "),
+            next.exercise.display()
+        );
     }
 }
 
-// 打印每道题目的评测结果，并使用颜色输出
-fn print_evaluation_result(name: &str, result: bool) {
-    if result {
-        println!("\x1b[32m{}: PASSED\x1b[0m", name); // 绿色表示成功
-    } else {
-        println!("\x1b[31m{}: FAILED\x1b[0m", name); // 红色表示失败
+// 列出耗时最长的几道习题及其各阶段耗时，便于发现拖慢评测的习题
+fn print_slowest(exercises: &[ExerciseResult]) {
+    let mut timed: Vec<&ExerciseResult> = exercises.iter().filter(|exercise| exercise.duration_ms.is_some()).collect();
+    if timed.len() < 2 {
+        return;
+    }
+    timed.sort_by_key(|exercise| std::cmp::Reverse(exercise.duration_ms));
+    println!("Slowest exercises:");
+    for exercise in timed.iter().take(SLOWEST_COUNT) {
+        let phases: Vec<String> = PHASES
+            .iter()
+            .filter_map(|phase| exercise.phases_ms.get(*phase).map(|ms| format!("{} {}", phase, seconds(*ms))))
+            .collect();
+        println!("  {}: {} ({})", exercise.name, seconds(exercise.duration_ms.unwrap_or_default()), phases.join(", "));
     }
 }
 
-// 提示用户是否继续评测下一题
-fn ask_to_continue() -> bool {
-    let mut input = String::new();
-    println!("\nPress any key to continue, or 'q' to quit.");
-    io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_lowercase() != "q"
+fn seconds(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
 }
 
-// 打印编译器输出
-fn print_compiler_output(exercise_file: &PathBuf) {
-    let output = Command::new("rustc")
-        .arg(exercise_file)
-        .output()
-        .expect("Failed to execute rustc");
-    println!("Compiler Output for {}: \n{}", exercise_file.display(), String::from_utf8_lossy(&output.stdout));
-    if !output.stderr.is_empty() {
-        eprintln!("Compiler Errors for {}: \n{}", exercise_file.display(), String::from_utf8_lossy(&output.stderr));
+// verbose 模式结束时打印失败习题在日志文件中的位置，便于在很长的 CI 日志中直接跳转
+fn print_log_index(report: &Report, log_file: &Path) {
+    let failed: Vec<&ExerciseResult> = report.exercises.iter().filter(|e| !e.result).collect();
+    if failed.is_empty() {
+        return;
+    }
+    println!("\nFailure index ({}):", log_file.display());
+    let width = failed.iter().map(|e| e.name.len()).max().unwrap_or(0);
+    for exercise in failed {
+        if let Some((line, byte)) = output::log_offset(&exercise.name) {
+            println!("  {:<width$}  line {} (byte {})", exercise.name, line, byte, width = width);
+        }
     }
 }
 
-// 打印 cargo test 输出
-fn print_cargo_test_output(exercise_file: &PathBuf) {
-    let output = Command::new("cargo")
-        .arg("test")
-        .current_dir(exercise_file.parent().unwrap())
-        .output()
-        .expect("Failed to execute cargo test");
-    println!("Cargo Test Output for {}: \n{}", exercise_file.display(), String::from_utf8_lossy(&output.stdout));
-    if !output.stderr.is_empty() {
-        eprintln!("Cargo Test Errors for {}: \n{}", exercise_file.display(), String::from_utf8_lossy(&output.stderr));
+// 将习题的错误输出（去掉颜色控制符）复制到剪贴板
+fn copy_errors(result: &ExerciseResult) {
+    let text = format!("{}\n{}", result.name, clipboard::strip_ansi(&result.log));
+    match clipboard::copy(&text) {
+        Ok(()) => println!("Copied the errors of {} to the clipboard.", result.name),
+        Err(e) => eprintln!("Error copying to the clipboard: {}", e),
     }
 }
 
-// 清理 exercises 目录下的所有 target 目录
-fn clean_target_dirs<P: AsRef<Path>>(base_dir: P) -> Result<(), io::Error> {
-    let entries = fs::read_dir(base_dir)?;
+// watch：按顺序评测习题，遇到未通过的习题时停下，监视习题目录，
+// 该习题的文件保存后重新评测，通过后继续下一题；已通过的习题被改动时也会重新评测。
+// 输入 q 结束监视，返回已评测习题的最新结果
+fn watch_exercises(ctx: &RunContext, exercises: &[PathBuf], roots: &[PathBuf], mut completion: Option<&mut Completion>) -> Vec<ExerciseResult> {
+    let mut watcher = match watcher::Watcher::new(roots) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+    };
+    // 在单独的线程中读取键盘输入，监视文件的同时响应命令
+    let (input_sender, input) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lines().map_while(Result::ok) {
+            if input_sender.send(line.trim().to_lowercase()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut results: Vec<Option<ExerciseResult>> = exercises.iter().map(|_| None).collect();
+    // 从上次停下的地方继续：跳过开头已经完成且之后没有修改过的习题
+    let done: Vec<bool> = exercises
+        .iter()
+        .map(|exercise| completion.as_ref().is_some_and(|completion| completion.is_done(exercise)))
+        .collect();
+    let mut current = done.iter().take_while(|done| **done).count();
+    if current > 0 {
+        println!("Resuming after {} completed exercise(s) recorded in {}", current, COMPLETION_FILE);
+    }
+    let mut regrade: Vec<usize> = Vec::new();
+    loop {
+        if !display::ascii() {
+            print!("\x1b[2J\x1b[H");
+        }
+        for index in regrade.drain(..) {
+            let result = grade_exercise(ctx, &exercises[index], false);
+            if process::interrupted() {
+                break;
+            }
+            results[index] = Some(result);
+            record_completion(&mut completion, results[index].as_ref());
+        }
+        // 从当前习题开始依次评测，直到遇到未通过的习题
+        while current < exercises.len() && !process::interrupted() {
+            let result = grade_exercise(ctx, &exercises[current], true);
+            if process::interrupted() {
+                break;
+            }
+            let passed = result.result;
+            results[current] = Some(result);
+            record_completion(&mut completion, results[current].as_ref());
+            if !passed {
+                break;
+            }
+            current += 1;
+        }
+        // 跳过未评测的习题按已完成计
+        if process::interrupted() {
+            break;
+        }
+        let passed = results
+            .iter()
+            .zip(&done)
+            .filter(|(result, done)| result.as_ref().map_or(**done, |result| result.result))
+            .count();
+        println!("\nProgress: {}/{} exercises pass", passed, exercises.len());
+        for result in results[..current].iter().flatten().filter(|result| !result.result) {
+            println!("{}", display::paint(&format!("{}{} no longer passes", display::prefix(Status::Fail), result.name), Tone::Bad));
+        }
+        let Some(exercise) = exercises.get(current) else {
+            println!("All exercises pass!");
+            break;
+        };
+        let mut keys = vec!["'q' to quit"];
+        if markdown::readme_for(exercise).is_some() {
+            keys.push("'r' to read the README");
+        }
+        let hints = hints::hints_for(exercise, &ctx.config.exercise_meta(exercise)).unwrap_or_default();
+        if !hints.is_empty() {
+            keys.push("'h' for a hint");
+        }
+        if results[current].as_ref().map(|result| !result.log.is_empty()).unwrap_or(false) {
+            keys.push("'c' to copy the errors");
+        }
+        println!("Save {} to re-check it. Type {}, then Enter.", exercise.display(), keys.join(", "));
 
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            // 如果是目录，检查是否包含 target 目录
-            let target_dir = path.join("target");
-            if target_dir.exists() {
-                fs::remove_dir_all(target_dir)?;
-                println!("Successfully cleaned target directory in: {}", path.display());
+        watcher.refresh();
+        let mut quit = false;
+        let changed = watcher.wait_for_changes_until(|| {
+            while let Ok(command) = input.try_recv() {
+                match command.as_str() {
+                    "q" => quit = true,
+                    "r" => match markdown::show_readme(exercise) {
+                        Ok(rendered) => print!("{}", rendered),
+                        Err(e) => eprintln!("{}", e),
+                    },
+                    "c" => {
+                        if let Some(result) = results[current].as_ref().filter(|result| !result.log.is_empty()) {
+                            copy_errors(result);
+                        }
+                    }
+                    "h" if !hints.is_empty() => match hints::reveal_next(&exercise.display().to_string(), &hints) {
+                        Ok(revealed) => print!("{}", hints::render(&revealed, hints.len())),
+                        Err(e) => eprintln!("{}", e),
+                    },
+                    _ => {}
+                }
             }
+            quit || process::interrupted()
+        });
+        let Some(changed) = changed else {
+            break;
+        };
+        // 当前习题总是重新评测（可能改动的是它依赖的共用文件），之前已评测的习题只在被改动时重新评测
+        regrade = watcher::affected_exercises(exercises, &changed)
+            .iter()
+            .filter_map(|path| exercises.iter().position(|exercise| exercise == path))
+            .filter(|index| *index < current)
+            .collect();
+    }
+    results.into_iter().flatten().collect()
+}
+
+// 读取仓库中的完成状态；文件损坏（如合并冲突）时给出警告并从头记录
+fn load_completion() -> Completion {
+    Completion::load().unwrap_or_else(|e| {
+        eprintln!("Warning: {}; starting a new {}", e, COMPLETION_FILE);
+        Completion::default()
+    })
+}
+
+// 监视模式下每评测完一道习题就更新完成状态，中途按 Ctrl+C 退出也不会丢失
+fn record_completion(completion: &mut Option<&mut Completion>, result: Option<&ExerciseResult>) {
+    if let (Some(completion), Some(result)) = (completion.as_mut(), result) {
+        completion.record([result]);
+        if let Err(e) = completion.save() {
+            eprintln!("Error saving completion state: {}", e);
         }
     }
+}
+
+// 监视整个章节：先评测章节中的全部习题，之后每当文件变化时只重新评测受影响的习题，
+// 并在每次评测后显示章节的状态面板；按 Ctrl+C 退出
+fn watch_chapter(ctx: &RunContext, chapter: &Path, exclusions: &Exclusions) -> ! {
+    if !chapter.is_dir() {
+        eprintln!("Chapter {} is not a directory", chapter.display());
+        exit(EXIT_ERROR);
+    }
+    let mut exercises = discover_exercises(vec![chapter.to_path_buf()], exclusions, &ctx.config);
+    exercises.retain(|exercise| ctx.includes(exercise));
+    if exercises.is_empty() {
+        eprintln!("No exercises found in {}", chapter.display());
+        exit(EXIT_ERROR);
+    }
 
-    Ok(())
+    let mut statuses: BTreeMap<PathBuf, ExerciseStatus> = BTreeMap::new();
+    let mut watcher = match watcher::Watcher::new(&[chapter.to_path_buf()]) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(EXIT_ERROR);
+        }
+    };
+    let mut pending = exercises.clone();
+    loop {
+        if !display::ascii() {
+            // 清屏，让状态面板始终位于最新输出的下方
+            print!("\x1b[2J\x1b[H");
+        }
+        // 在后台线程中评测，同时继续监视文件；评测期间有新的改动时取消评测，
+        // 结束正在运行的编译或测试，避免学生连续保存时排队执行过时的评测
+        let graded = thread::scope(|scope| {
+            let worker = scope.spawn(|| {
+                let mut graded = Vec::new();
+                for exercise in &pending {
+                    let result = grade_exercise(ctx, exercise, false);
+                    if process::cancelled() {
+                        break;
+                    }
+                    graded.push((exercise.clone(), result.status));
+                }
+                graded
+            });
+            while !worker.is_finished() {
+                if watcher.modified() {
+                    process::cancel();
+                    println!("\nChange detected, cancelling the current evaluation...");
+                    break;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            worker.join().unwrap_or_default()
+        });
+        // Ctrl+C 已结束正在运行的编译或测试，直接退出
+        if process::interrupted() {
+            exit(process::EXIT_INTERRUPTED);
+        }
+        let cancelled = process::cancelled();
+        process::reset_cancel();
+        pending.retain(|exercise| !graded.iter().any(|(graded, _)| graded == exercise));
+        statuses.extend(graded);
+
+        if !cancelled {
+            print_chapter_dashboard(chapter, &exercises, &statuses);
+            watcher.refresh();
+        }
+        let changed = watcher.wait_for_changes_until(process::interrupted).unwrap_or_default();
+        if process::interrupted() {
+            exit(process::EXIT_INTERRUPTED);
+        }
+        // 被取消的习题和受新改动影响的习题都需要重新评测
+        let affected = watcher::affected_exercises(&exercises, &changed);
+        pending = exercises
+            .iter()
+            .filter(|exercise| pending.contains(exercise) || affected.contains(exercise))
+            .cloned()
+            .collect();
+    }
 }
 
-// 保存评测结果到 JSON 文件
-fn save_report_to_json(file_name: &str, report: &Report) -> io::Result<()> {
-    let file = File::create(file_name)?;
-    serde_json::to_writer_pretty(file, report)?;
-    Ok(())
+// 章节状态面板：每道习题最近一次的评测结果
+fn print_chapter_dashboard(chapter: &Path, exercises: &[PathBuf], statuses: &BTreeMap<PathBuf, ExerciseStatus>) {
+    let passed = statuses.values().filter(|status| **status == ExerciseStatus::Passed).count();
+    println!("\n== {}: {}/{} passed ==", chapter.display(), passed, exercises.len());
+    for exercise in exercises {
+        let line = match statuses.get(exercise) {
+            Some(ExerciseStatus::Passed) => display::paint(&format!("  {}PASSED       {}", display::prefix(Status::Pass), exercise.display()), Tone::Good),
+            Some(ExerciseStatus::Failed) => display::paint(&format!("  {}FAILED       {}", display::prefix(Status::Fail), exercise.display()), Tone::Bad),
+            Some(ExerciseStatus::InProgress) => display::paint(&format!("  {}IN PROGRESS  {}", display::prefix(Status::Partial), exercise.display()), Tone::Warn),
+            None => format!("  {}", exercise.display()),
+        };
+        println!("{}", line);
+    }
+    println!("\nWatching {} for changes (Ctrl+C to stop)...", chapter.display());
 }
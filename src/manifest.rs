@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::hashing::content_hash;
+use crate::hermetic;
+use crate::paths;
+
+// 与评测报告一同写出的运行清单，记录复现一次运行所需的全部信息
+pub const MANIFEST_FILE: &str = "run-manifest.json";
+// 每次运行的清单另存到状态目录的这个子目录中（<运行 ID>.json），归档的报告日后仍能按其运行 ID 重放
+const MANIFESTS_DIR: &str = "manifests";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Toolchain {
+    pub rustc: String,
+    pub cargo: String,
+}
+
+impl Toolchain {
+    // 读取当前环境的 rustc / cargo 版本
+    pub fn detect() -> Toolchain {
+        Toolchain {
+            rustc: command_version("rustc"),
+            cargo: command_version("cargo"),
+        }
+    }
+}
+
+fn command_version(program: &str) -> String {
+    Command::new(program)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub hash: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RunManifest {
+    pub run_id: String,
+    // 运行开始时间（Unix 秒）
+    pub started_at: u64,
+    // 命令及全部参数（不含程序名）
+    pub args: Vec<String>,
+    pub seed: Option<u64>,
+    pub toolchain: Toolchain,
+    pub exercises: Vec<ManifestEntry>,
+    // --hermetic 运行所用的镜像 ID，重放时在同一镜像中评测
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+}
+
+impl RunManifest {
+    pub fn new(run_id: &str, started_at: u64, args: &[String], seed: Option<u64>, exercises: &[String]) -> RunManifest {
+        RunManifest {
+            run_id: run_id.to_string(),
+            started_at,
+            args: args.to_vec(),
+            seed,
+            toolchain: Toolchain::detect(),
+            exercises: exercises
+                .iter()
+                .map(|name| ManifestEntry {
+                    name: name.clone(),
+                    hash: content_hash(Path::new(name)).unwrap_or_else(|_| "missing".to_string()),
+                })
+                .collect(),
+            image: hermetic::image(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<RunManifest, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        serde_json::from_reader(file).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        serde_json::to_writer_pretty(file, self).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // 保存为最近一次运行的清单，并按运行 ID 另存一份，只保留最近 retention 份（0 表示不另存）
+    pub fn save_run(&self, retention: usize) -> Result<(), String> {
+        self.save(&paths::state_file(MANIFEST_FILE))?;
+        if retention == 0 {
+            return Ok(());
+        }
+        let dir = paths::state_file(MANIFESTS_DIR);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        self.save(&dir.join(format!("{}.json", self.run_id)))?;
+        let manifests = manifests();
+        let excess = manifests.len().saturating_sub(retention);
+        for path in &manifests[..excess] {
+            let _ = fs::remove_file(path);
+        }
+        Ok(())
+    }
+
+    // 清单中的习题集合，重放时只评测这些习题
+    pub fn exercise_set(&self) -> HashSet<PathBuf> {
+        self.exercises.iter().map(|e| PathBuf::from(&e.name)).collect()
+    }
+
+    // 与当前环境对比，返回所有不一致之处
+    pub fn differences(&self) -> Vec<String> {
+        let mut differences = Vec::new();
+        let toolchain = Toolchain::detect();
+        if toolchain.rustc != self.toolchain.rustc {
+            differences.push(format!("rustc: recorded '{}', now '{}'", self.toolchain.rustc, toolchain.rustc));
+        }
+        if toolchain.cargo != self.toolchain.cargo {
+            differences.push(format!("cargo: recorded '{}', now '{}'", self.toolchain.cargo, toolchain.cargo));
+        }
+        for entry in &self.exercises {
+            let hash = content_hash(Path::new(&entry.name)).unwrap_or_else(|_| "missing".to_string());
+            if hash != entry.hash {
+                differences.push(format!("{}: content changed since the recorded run", entry.name));
+            }
+        }
+        differences
+    }
+}
+
+// 查找运行的清单：run_id 为运行 ID 或其唯一前缀，未指定时为最近一次运行的清单
+pub fn find(run_id: Option<&str>) -> Result<PathBuf, String> {
+    let Some(run_id) = run_id else {
+        return Ok(paths::state_file(MANIFEST_FILE));
+    };
+    let dir = paths::state_file(MANIFESTS_DIR);
+    let manifests = manifests();
+    let matches: Vec<&PathBuf> = manifests
+        .iter()
+        .filter(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy().starts_with(run_id)))
+        .collect();
+    match matches.as_slice() {
+        [path] => Ok(path.to_path_buf()),
+        [] => Err(format!("No manifest for run {} in {}", run_id, dir.display())),
+        paths => {
+            let exact = paths.iter().find(|path| path.file_stem().is_some_and(|stem| stem == run_id));
+            exact.map(|path| path.to_path_buf()).ok_or_else(|| {
+                let ids: Vec<String> =
+                    paths.iter().map(|path| path.file_stem().unwrap_or_default().to_string_lossy().to_string()).collect();
+                format!("Run ID {} is ambiguous: {}", run_id, ids.join(", "))
+            })
+        }
+    }
+}
+
+// 按运行 ID 保存的全部清单，按修改时间从旧到新排列
+fn manifests() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(paths::state_file(MANIFESTS_DIR)) else {
+        return Vec::new();
+    };
+    let mut manifests: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|metadata| metadata.modified()).unwrap_or(std::time::UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+    manifests.sort();
+    manifests.into_iter().map(|(_, path)| path).collect()
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use colored::Colorize;
+use crate::display;
+
+// 习题的题目说明：目录习题取目录下的 README.md，单文件习题取所在目录的 README.md
+pub fn readme_for(exercise: &Path) -> Option<PathBuf> {
+    let dir = if exercise.is_dir() { exercise } else { exercise.parent()? };
+    let readme = dir.join("README.md");
+    readme.exists().then_some(readme)
+}
+
+// 读取并渲染习题的 README
+pub fn show_readme(exercise: &Path) -> Result<String, String> {
+    let readme = readme_for(exercise)
+        .ok_or_else(|| format!("No README.md found for {}", exercise.display()))?;
+    let content = fs::read_to_string(&readme)
+        .map_err(|e| format!("Failed to read {}: {}", readme.display(), e))?;
+    Ok(render(&content))
+}
+
+// 以基本的终端样式渲染 markdown：标题、列表、代码块、行内代码、粗体和链接
+pub fn render(markdown: &str) -> String {
+    let mut result = String::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            result.push_str(&format!("    {}\n", line.dimmed()));
+            continue;
+        }
+
+        let rendered = if let Some(heading) = trimmed.strip_prefix('#') {
+            let text = heading.trim_start_matches('#').trim();
+            if heading.starts_with('#') {
+                render_inline(text).bold().to_string()
+            } else {
+                render_inline(text).bold().underline().cyan().to_string()
+            }
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            let indent = &line[..line.len() - trimmed.len()];
+            format!("{}  {} {}", indent, display::glyph("•", "-"), render_inline(item))
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            format!("  {} {}", display::glyph("│", "|"), render_inline(quote.trim()).italic())
+        } else {
+            render_inline(line)
+        };
+        result.push_str(&rendered);
+        result.push('\n');
+    }
+    result
+}
+
+// 渲染行内元素
+fn render_inline(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('`') {
+            if let Some(end) = after.find('`') {
+                result.push_str(&after[..end].yellow().to_string());
+                rest = &after[end + 1..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("**") {
+            if let Some(end) = after.find("**") {
+                result.push_str(&after[..end].bold().to_string());
+                rest = &after[end + 2..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix('[') {
+            if let Some((label, url, consumed)) = parse_link(after) {
+                result.push_str(&format!("{} ({})", label.underline(), url.blue()));
+                rest = &after[consumed..];
+                continue;
+            }
+        }
+        let ch = rest.chars().next().unwrap_or_default();
+        result.push(ch);
+        rest = &rest[ch.len_utf8()..];
+    }
+    result
+}
+
+// 解析 `label](url)`，返回标签、地址和消耗的字节数
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let label_end = text.find("](")?;
+    let url_end = text[label_end + 2..].find(')')? + label_end + 2;
+    Some((&text[..label_end], &text[label_end + 2..url_end], url_end + 1))
+}
@@ -0,0 +1,119 @@
+use std::fmt::Write;
+use crate::clipboard::strip_ansi;
+use crate::display::{self, Status};
+use crate::steps;
+use crate::report::ReportWriter;
+use crate::{ExerciseStatus, Report};
+
+// 每道失败习题最多保留的日志行数，避免超出 PR 评论的长度限制
+const MAX_LOG_LINES: usize = 40;
+
+// Markdown 摘要，可以直接作为 PR 评论发布
+pub struct MarkdownWriter;
+
+impl ReportWriter for MarkdownWriter {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn aliases(&self) -> &[&str] {
+        &["md"]
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        render(report)
+    }
+}
+
+// 将评测报告渲染为 Markdown：摘要、习题结果表格，以及折叠起来的失败日志，
+// 供课堂自动化脚本粘贴或发布为 PR 评论
+pub fn render(report: &Report) -> String {
+    let stats = &report.statistics;
+    let mut md = String::from("## Grading report\n\n");
+    let _ = write!(md, "**{}/{} exercises passed**", stats.total_succeeds, stats.total_exercations);
+    if let Some(summary) = &report.summary {
+        let _ = write!(md, " ({}) in {}", summary.pass_rate.display, summary.duration.display);
+    }
+    md.push('\n');
+    if report.interrupted {
+        md.push_str("\n> The run was interrupted; only the exercises graded before that are listed.\n");
+    }
+    if let Some(user_name) = &report.user_name {
+        let _ = writeln!(md, "\nStudent: {}", cell(user_name));
+    }
+    if let Some(penalty) = report.late_penalty.as_ref().filter(|penalty| penalty.days_late > 0) {
+        let _ = writeln!(
+            md,
+            "\nScore: {:.1} ({} day(s) late, -{:.0}% from {:.1})",
+            penalty.adjusted_score, penalty.days_late, penalty.penalty_percent, penalty.raw_score
+        );
+    }
+
+    md.push_str("\n| | Exercise | Result | Steps | Time |\n|---|---|---|---|---|\n");
+    for exercise in &report.exercises {
+        let (status, result) = match (exercise.status, exercise.reason, exercise.code) {
+            (ExerciseStatus::Passed, ..) => (Status::Pass, "passed".to_string()),
+            (ExerciseStatus::InProgress, ..) => (Status::Partial, "in progress".to_string()),
+            (ExerciseStatus::Failed, Some(reason), Some(code)) => {
+                (Status::Fail, format!("{} `{}`", reason.label(), code.as_str()))
+            }
+            (ExerciseStatus::Failed, Some(reason), None) => (Status::Fail, reason.label().to_string()),
+            (ExerciseStatus::Failed, None, _) => (Status::Fail, "failed".to_string()),
+        };
+        let time = exercise.duration_ms.map(|ms| format!("{:.2} s", ms as f64 / 1000.0)).unwrap_or_default();
+        let _ = writeln!(
+            md,
+            "| {} | `{}` | {} | {} | {} |",
+            display::symbol(status),
+            cell(&exercise.name),
+            result,
+            steps::summary(&exercise.steps),
+            time
+        );
+    }
+
+    for exercise in report.exercises.iter().filter(|exercise| !exercise.result) {
+        let log = strip_ansi(&exercise.log);
+        let log = log.trim();
+        if log.is_empty() {
+            continue;
+        }
+        // 错误信息通常在日志末尾，过长时只保留最后几行
+        let lines: Vec<&str> = log.lines().collect();
+        let omitted = lines.len().saturating_sub(MAX_LOG_LINES);
+        let mut shown = String::new();
+        if omitted > 0 {
+            let _ = writeln!(shown, "... ({} earlier lines omitted)", omitted);
+        }
+        shown.push_str(&lines[omitted..].join("\n"));
+        let fence = fence_for(&shown);
+        let _ = write!(
+            md,
+            "\n<details><summary><code>{}</code></summary>\n\n{}text\n{}\n{}\n\n</details>\n",
+            html_escape(&exercise.name),
+            fence,
+            shown,
+            fence
+        );
+    }
+    md
+}
+
+// 表格单元格中的 | 和换行会破坏表格
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+// 代码块的围栏要比日志中最长的连续反引号更长
+fn fence_for(text: &str) -> String {
+    let longest = text.split(|ch| ch != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
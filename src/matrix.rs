@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+use serde::Serialize;
+use crate::display::{self, Status, Theme, Tone};
+
+// 工具链矩阵报告的文件名，保存在当前课程的状态目录中
+pub const MATRIX_FILE: &str = "toolchain-matrix.json";
+// rustup 据此选择 cargo / rustc 等命令使用的工具链
+pub const TOOLCHAIN_ENV: &str = "RUSTUP_TOOLCHAIN";
+
+#[derive(Serialize, Debug)]
+pub struct ToolchainInfo {
+    pub name: String,
+    // rustc --version 的输出
+    pub version: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MatrixRow {
+    pub name: String,
+    // 与 toolchains 一一对应的评测结果
+    pub results: Vec<bool>,
+}
+
+// 同一批习题在多个工具链下的评测结果
+#[derive(Serialize, Debug)]
+pub struct ToolchainMatrix {
+    pub run_id: String,
+    pub toolchains: Vec<ToolchainInfo>,
+    pub exercises: Vec<MatrixRow>,
+}
+
+// 解析 --toolchains stable,beta,nightly
+pub fn parse_toolchains(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+// 检查工具链已安装，返回其 rustc 版本
+pub fn toolchain_version(toolchain: &str) -> Result<String, String> {
+    rustc_version(Command::new("rustc").env(TOOLCHAIN_ENV, toolchain)).map_err(|_| {
+        format!(
+            "Toolchain '{}' is not installed (try `rustup toolchain install {}`)",
+            toolchain, toolchain
+        )
+    })
+}
+
+// 当前默认使用的 rustc 版本
+pub fn current_version() -> Result<String, String> {
+    rustc_version(&mut Command::new("rustc"))
+}
+
+fn rustc_version(command: &mut Command) -> Result<String, String> {
+    let output = command
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Failed to execute rustc: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("rustc --version failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+impl ToolchainMatrix {
+    pub fn new(run_id: &str) -> Self {
+        ToolchainMatrix {
+            run_id: run_id.to_string(),
+            toolchains: Vec::new(),
+            exercises: Vec::new(),
+        }
+    }
+
+    // 记录某个工具链下的一道习题结果；习题行按首次出现的顺序排列
+    pub fn record(&mut self, toolchain_index: usize, name: &str, passed: bool) {
+        let position = self.exercises.iter().position(|row| row.name == name);
+        let row = match position {
+            Some(position) => &mut self.exercises[position],
+            None => {
+                self.exercises.push(MatrixRow { name: name.to_string(), results: Vec::new() });
+                self.exercises.last_mut().unwrap()
+            }
+        };
+        if row.results.len() <= toolchain_index {
+            row.results.resize(toolchain_index + 1, false);
+        }
+        row.results[toolchain_index] = passed;
+    }
+
+    // 以表格形式打印矩阵，并指出在部分工具链下失败的习题；纯 ASCII 模式下使用带边框的表格
+    pub fn print(&self) {
+        let width = self.exercises.iter().map(|row| row.name.len()).max().unwrap_or(0).max(8);
+        let separator = if display::ascii() { " | " } else { "  " };
+        print!("\n{:<width$}", "Exercise", width = width);
+        for toolchain in &self.toolchains {
+            print!("{}{:^9}", separator, toolchain.name);
+        }
+        println!();
+        if display::ascii() {
+            let columns: Vec<String> = std::iter::once("-".repeat(width))
+                .chain(self.toolchains.iter().map(|_| "-".repeat(9)))
+                .collect();
+            println!("{}", columns.join("-+-"));
+        }
+        for row in &self.exercises {
+            print!("{:<width$}", row.name, width = width);
+            for i in 0..self.toolchains.len() {
+                let cell = match row.results.get(i) {
+                    Some(true) => display::paint(&cell(Status::Pass, "ok"), Tone::Good),
+                    Some(false) => display::paint(&cell(Status::Fail, "FAIL"), Tone::Bad),
+                    None => "    -    ".to_string(),
+                };
+                print!("{}{}", separator, cell);
+            }
+            println!();
+        }
+
+        let inconsistent: Vec<&str> = self
+            .exercises
+            .iter()
+            .filter(|row| row.results.iter().any(|r| *r) && row.results.iter().any(|r| !*r))
+            .map(|row| row.name.as_str())
+            .collect();
+        if !inconsistent.is_empty() {
+            println!("\nExercises whose result depends on the toolchain: {}", inconsistent.join(", "));
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        serde_json::to_writer_pretty(file, self).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+// 居中的单元格内容（宽 9 列）：图标主题用图标，否则用文字
+fn cell(status: Status, word: &str) -> String {
+    let (text, width) = match display::theme() {
+        Theme::Emoji | Theme::NerdFont => (display::symbol(status), display::symbol_width(status)),
+        Theme::Default | Theme::Plain => (word, word.len()),
+    };
+    let left = (9 - width) / 2;
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(9 - width - left))
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use serde::Deserialize;
+use crate::cases::TestCase;
+use crate::clippy::ClippyConfig;
+use crate::compile_time::CompileTimePolicy;
+use crate::generator::GeneratorConfig;
+
+// 单道习题的元数据，在 grader.toml 的 [exercises."<习题路径>"] 中配置
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ExerciseMeta {
+    // 习题名称，可用于 run <name> 和前置习题引用；未配置时以路径标识习题
+    pub name: Option<String>,
+    // 评测方式：compile 只检查能否编译，test 运行测试，run 运行程序；未配置时按习题内容推断
+    pub mode: Option<ExerciseMode>,
+    // 提示，按顺序逐条提供给学生
+    pub hints: Vec<String>,
+    // 习题分值：通过得满分，配置了评分细则时按得分折算
+    pub points: Option<f64>,
+    // 前置习题的名称
+    pub dependencies: Vec<String>,
+    // 习题在课程中的顺序，配置了顺序的习题按顺序排在前面，其余保持扫描顺序
+    pub order: Option<u32>,
+    // 标签，如 ["ownership", "traits"]，在习题列表中显示，便于按主题查找
+    pub tags: Vec<String>,
+    // 期望程序 panic，且 panic 信息匹配该正则表达式时才算通过
+    pub expect_panic: Option<String>,
+    // 程序运行时的峰值内存上限（MB），超出即判为失败
+    pub memory_limit_mb: Option<u64>,
+    // 输出比对用例：逐个喂入标准输入并比对标准输出
+    pub cases: Vec<TestCase>,
+    // 每个用例的默认时间限制（毫秒）
+    pub case_time_limit_ms: Option<u64>,
+    // 整道习题的评测时限（秒），覆盖 --timeout 和全局配置
+    pub timeout_secs: Option<u64>,
+    // 随机输入生成器，生成的用例追加到 cases 之后
+    pub generator: Option<GeneratorConfig>,
+    // 评分细则：评分项 -> 权重，如 { compiles = 20, tests = 60, clippy = 10, fmt = 10 }；
+    // 配置后报告中记录按权重计算的得分和各项明细
+    pub rubric: BTreeMap<String, f64>,
+    // Cargo 习题的 feature 组合，每个组合分别构建、测试并作为子结果记录；为空时只按默认 feature 评测
+    pub feature_sets: Vec<FeatureSet>,
+    // 覆盖全局的构建脚本/过程宏策略，如讲授过程宏的习题设为 allow
+    pub compile_time_code: Option<CompileTimePolicy>,
+    // 覆盖全局的网络隔离设置，如讲授网络编程的习题设为 false
+    pub isolate_network: Option<bool>,
+    // 覆盖全局的文件系统隔离设置
+    pub isolate_fs: Option<bool>,
+    pub expose_exercise: Option<bool>,
+    // 在全局允许列表之外，本题还允许新建的文件（如程序按题意写出的结果文件）
+    pub artifacts_allow: Vec<String>,
+    // 覆盖章节和全局的 clippy 设置，如 { deny_warnings = true, groups = ["pedantic"] }
+    pub clippy: Option<ClippyConfig>,
+    // 同时含有 Cargo.toml 和散落 .rs 文件的目录如何评测，配置在该目录的元数据中；未声明时按 Cargo 项目评测
+    pub layout: Option<DirLayout>,
+}
+
+impl ExerciseMeta {
+    // 逐项合并：本元数据中未配置的项取 fallback 中的配置
+    pub fn or(self, fallback: ExerciseMeta) -> ExerciseMeta {
+        fn or_vec<T>(items: Vec<T>, fallback: Vec<T>) -> Vec<T> {
+            if items.is_empty() { fallback } else { items }
+        }
+        ExerciseMeta {
+            name: self.name.or(fallback.name),
+            mode: self.mode.or(fallback.mode),
+            hints: or_vec(self.hints, fallback.hints),
+            points: self.points.or(fallback.points),
+            dependencies: or_vec(self.dependencies, fallback.dependencies),
+            order: self.order.or(fallback.order),
+            tags: or_vec(self.tags, fallback.tags),
+            expect_panic: self.expect_panic.or(fallback.expect_panic),
+            memory_limit_mb: self.memory_limit_mb.or(fallback.memory_limit_mb),
+            cases: or_vec(self.cases, fallback.cases),
+            case_time_limit_ms: self.case_time_limit_ms.or(fallback.case_time_limit_ms),
+            timeout_secs: self.timeout_secs.or(fallback.timeout_secs),
+            generator: self.generator.or(fallback.generator),
+            rubric: if self.rubric.is_empty() { fallback.rubric } else { self.rubric },
+            feature_sets: or_vec(self.feature_sets, fallback.feature_sets),
+            compile_time_code: self.compile_time_code.or(fallback.compile_time_code),
+            isolate_network: self.isolate_network.or(fallback.isolate_network),
+            isolate_fs: self.isolate_fs.or(fallback.isolate_fs),
+            expose_exercise: self.expose_exercise.or(fallback.expose_exercise),
+            artifacts_allow: or_vec(self.artifacts_allow, fallback.artifacts_allow),
+            clippy: self.clippy.or(fallback.clippy),
+            layout: self.layout.or(fallback.layout),
+        }
+    }
+}
+
+// 习题的评测方式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseMode {
+    // 只检查能否编译
+    Compile,
+    // 编译并运行测试，单文件习题不运行 main
+    Test,
+    // 编译并运行程序，不运行测试
+    Run,
+}
+
+// 含有 Cargo.toml 的习题目录的布局
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DirLayout {
+    // 整个目录是一个 Cargo 项目，目录中散落的 .rs 文件（如草稿）不参与评测
+    #[default]
+    Cargo,
+    // 目录中的每个 .rs 文件都是单文件习题，Cargo.toml 只供编辑器使用
+    Files,
+    // 既评测 Cargo 项目，也评测目录中的每个 .rs 文件
+    Both,
+}
+
+// 一个 feature 组合，如 { features = ["fast"], no_default_features = true }
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FeatureSet {
+    // 报告中显示的名称，未配置时由参数生成
+    pub name: Option<String>,
+    pub features: Vec<String>,
+    pub no_default_features: bool,
+    pub all_features: bool,
+}
+
+impl FeatureSet {
+    // 传给 cargo build/test/clippy 的参数
+    pub fn cargo_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.no_default_features {
+            args.push("--no-default-features".to_string());
+        }
+        if self.all_features {
+            args.push("--all-features".to_string());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_string());
+            args.push(self.features.join(","));
+        }
+        args
+    }
+
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None if self.cargo_args().is_empty() => "default".to_string(),
+            None => self.cargo_args().join(" "),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+use crate::config::CONFIG_FILE;
+use crate::matrix;
+use crate::paths;
+
+// 可选的界面语言：配置值和显示名称
+const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("zh", "中文"),
+    ("de", "Deutsch"),
+    ("fr", "Français"),
+    ("es", "Español"),
+];
+
+// 首次运行：在终端中不带任何参数启动，且仓库和用户配置目录中都没有配置文件
+pub fn needed() -> bool {
+    io::stdin().is_terminal()
+        && !Path::new(CONFIG_FILE).exists()
+        && !paths::user_config_file().map(|path| path.exists()).unwrap_or(false)
+}
+
+// 交互式向导：选择语言、设置用户名、定位或下载习题、检查工具链，最后写出 grader.toml
+pub fn run(exercises_dir: &str) -> Result<(), String> {
+    println!("Welcome! No {} was found, so let's set up the grader.\n", CONFIG_FILE);
+
+    // 1. 语言
+    println!("Language for reports and dates:");
+    for (i, (_, name)) in LANGUAGES.iter().enumerate() {
+        println!("  {}) {}", i + 1, name);
+    }
+    let language = loop {
+        let answer = ask("Choose a language", "1")?;
+        match answer.parse::<usize>().ok().and_then(|i| LANGUAGES.get(i.wrapping_sub(1))) {
+            Some((tag, _)) => break *tag,
+            None => println!("Please enter a number between 1 and {}.", LANGUAGES.len()),
+        }
+    };
+
+    // 2. 用户名，写入评测报告
+    let default_name = std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default();
+    let user_name = ask("Your name (recorded in reports)", &default_name)?;
+
+    // 3. 习题目录：不存在时可以从 git 仓库下载
+    println!();
+    if Path::new(exercises_dir).is_dir() {
+        println!("Found exercises in {}", exercises_dir);
+    } else {
+        println!("No exercises found in {}", exercises_dir);
+        let url = ask("Git URL of the exercises to download (leave empty to skip)", "")?;
+        if url.is_empty() {
+            println!("Skipped. Put the exercises in {} or pass --exercises-dir <PATH> later.", exercises_dir);
+        } else {
+            fetch_exercises(&url, exercises_dir)?;
+        }
+    }
+
+    // 4. 工具链
+    match matrix::current_version() {
+        Ok(version) => println!("Found {}", version),
+        Err(e) => println!("Warning: {}\nInstall Rust from https://rustup.rs before grading.", e),
+    }
+
+    let mut config = format!("locale = {}\n", toml::Value::String(language.to_string()));
+    if !user_name.is_empty() {
+        config.push_str(&format!("user_name = {}\n", toml::Value::String(user_name)));
+    }
+    fs::write(CONFIG_FILE, config).map_err(|e| format!("Failed to write {}: {}", CONFIG_FILE, e))?;
+    println!("\nWrote {}. Run `cargotest watch` to start, or `cargotest --help` for all commands.", CONFIG_FILE);
+    Ok(())
+}
+
+fn fetch_exercises(url: &str, exercises_dir: &str) -> Result<(), String> {
+    println!("Cloning {} into {}...", url, exercises_dir);
+    let status = Command::new("git")
+        .args(["clone", "--depth", "1", url, exercises_dir])
+        .status()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !status.success() {
+        return Err(format!("git clone {} failed", url));
+    }
+    Ok(())
+}
+
+// 提问并读取一行回答，直接回车时使用默认值
+fn ask(question: &str, default: &str) -> Result<String, String> {
+    if default.is_empty() {
+        print!("{}: ", question);
+    } else {
+        print!("{} [{}]: ", question, default);
+    }
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let mut answer = String::new();
+    let read = io::stdin().read_line(&mut answer).map_err(|e| format!("Failed to read input: {}", e))?;
+    if read == 0 {
+        return Err("Setup cancelled".to_string());
+    }
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default.to_string() } else { answer.to_string() })
+}
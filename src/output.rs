@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use crate::clipboard;
+use crate::process;
+
+// verbose 模式下完整输出的副本，保存在当前课程的状态目录中
+pub const VERBOSE_LOG_FILE: &str = "verbose.log";
+
+// 静默模式：丢弃评测过程的输出，嵌入评测的程序只需要结构化的结果
+static QUIET: AtomicBool = AtomicBool::new(false);
+// 全局输出锁，保证不同习题的输出块不会相互穿插
+static PRINT_LOCK: Mutex<()> = Mutex::new(());
+// verbose 日志文件及其中各习题输出的起始位置
+static LOG: Mutex<Option<VerboseLog>> = Mutex::new(None);
+
+struct VerboseLog {
+    writer: BufWriter<File>,
+    // 已写入的行数和字节数
+    lines: usize,
+    bytes: u64,
+    // 习题名 -> 其第一行输出的 (行号, 字节偏移)
+    offsets: HashMap<String, (usize, u64)>,
+}
+
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+// 开始把 verbose 输出（去掉颜色控制符）同时写入日志文件
+pub fn start_verbose_log(path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    *LOG.lock().unwrap_or_else(|e| e.into_inner()) = Some(VerboseLog {
+        writer: BufWriter::new(file),
+        lines: 0,
+        bytes: 0,
+        offsets: HashMap::new(),
+    });
+    Ok(())
+}
+
+// 习题输出在日志文件中的起始行号（从 1 开始）和字节偏移；同时把缓冲内容写入文件
+pub fn log_offset(name: &str) -> Option<(usize, u64)> {
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let log = log.as_mut()?;
+    let _ = log.writer.flush();
+    log.offsets.get(name).copied()
+}
+
+fn write_log(name: &str, line: &str) {
+    let mut log = LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(log) = log.as_mut() else {
+        return;
+    };
+    let (lines, bytes) = (log.lines, log.bytes);
+    log.offsets.entry(name.to_string()).or_insert((lines + 1, bytes));
+    let text = format!("[{}] {}\n", name, clipboard::strip_ansi(line));
+    if log.writer.write_all(text.as_bytes()).is_ok() {
+        log.lines += 1;
+        log.bytes += text.len() as u64;
+    }
+}
+
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// 单道习题的输出缓冲区
+// 默认模式下缓存全部输出，评测结束后一次性打印；
+// verbose 模式下逐行实时输出，并在每行前加上习题名前缀
+pub struct ExerciseOutput {
+    name: String,
+    verbose: bool,
+    chunks: Vec<(Stream, String)>,
+    // 全部错误输出的副本，打印后仍保留，用于复制到剪贴板等
+    errors: String,
+}
+
+impl ExerciseOutput {
+    pub fn new(name: &str, verbose: bool) -> Self {
+        ExerciseOutput {
+            name: name.to_string(),
+            verbose,
+            chunks: Vec::new(),
+            errors: String::new(),
+        }
+    }
+
+    // 写入一段标准输出内容
+    pub fn out(&mut self, text: &str) {
+        self.push(Stream::Stdout, text);
+    }
+
+    // 写入一段标准错误内容
+    pub fn err(&mut self, text: &str) {
+        self.errors.push_str(text);
+        if !text.ends_with('\n') {
+            self.errors.push('\n');
+        }
+        self.push(Stream::Stderr, text);
+    }
+
+    // 到目前为止的全部错误输出
+    pub fn errors(&self) -> &str {
+        &self.errors
+    }
+
+    fn push(&mut self, stream: Stream, text: &str) {
+        if self.verbose {
+            let _guard = PRINT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            for line in text.lines() {
+                write_log(&self.name, line);
+                match stream {
+                    Stream::Stdout => println!("[{}] {}", self.name, line),
+                    Stream::Stderr => eprintln!("[{}] {}", self.name, line),
+                }
+            }
+        } else {
+            let mut text = text.to_string();
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+            self.chunks.push((stream, text));
+        }
+    }
+
+    // 丢弃缓存的输出（不需要展示过程输出时使用）
+    pub fn discard(&mut self) {
+        self.chunks.clear();
+    }
+
+    // 一次性打印缓存的全部输出
+    pub fn flush(&mut self) {
+        // 被取消的评测结果已经过时，不再输出
+        if process::cancelled() || QUIET.load(Ordering::Relaxed) {
+            self.discard();
+        }
+        if self.chunks.is_empty() {
+            return;
+        }
+        let _guard = PRINT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let stdout = io::stdout();
+        let stderr = io::stderr();
+        let mut stdout = stdout.lock();
+        let mut stderr = stderr.lock();
+        for (stream, text) in self.chunks.drain(..) {
+            // 输出失败（如管道关闭）时没有更好的处理方式，直接忽略
+            let _ = match stream {
+                Stream::Stdout => stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush()),
+                Stream::Stderr => stderr.write_all(text.as_bytes()).and_then(|_| stderr.flush()),
+            };
+        }
+    }
+}
+
+impl Drop for ExerciseOutput {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
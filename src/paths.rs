@@ -0,0 +1,67 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use directories::ProjectDirs;
+use sha2::{Digest, Sha256};
+use crate::hashing::hex;
+
+// 覆盖状态目录的环境变量（如 CI 中希望把报告写到指定位置）
+pub const STATE_DIR_ENV: &str = "GRADER_STATE_DIR";
+// 用户级默认配置文件名，位于配置目录中，课程目录没有 grader.toml 时使用
+const USER_CONFIG_FILE: &str = "grader.toml";
+// 无法确定平台目录时退回到当前目录下的状态目录
+const FALLBACK_STATE_DIR: &str = ".grader";
+
+// 评测器在平台标准目录中的位置：Linux 上遵循 XDG，Windows 为 AppData，macOS 为 Application Support
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "rust-basic-grader")
+}
+
+// 用户级默认配置文件
+pub fn user_config_file() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join(USER_CONFIG_FILE))
+}
+
+// 当前工作区（课程目录）的状态目录，保存报告、运行清单、提交队列、webhook outbox 等。
+// 不同课程目录的状态互不干扰：<状态目录>/workspaces/<目录名>-<路径哈希>
+pub fn state_dir() -> &'static Path {
+    static STATE_DIR: OnceLock<PathBuf> = OnceLock::new();
+    STATE_DIR.get_or_init(|| {
+        let dir = match env::var_os(STATE_DIR_ENV).filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => match project_dirs() {
+                Some(dirs) => {
+                    let base = dirs.state_dir().unwrap_or_else(|| dirs.data_local_dir());
+                    base.join("workspaces").join(workspace_key())
+                }
+                None => PathBuf::from(FALLBACK_STATE_DIR),
+            },
+        };
+        let _ = fs::create_dir_all(&dir);
+        dir
+    })
+}
+
+// 状态目录中的文件
+pub fn state_file(name: &str) -> PathBuf {
+    state_dir().join(name)
+}
+
+// 评测用的临时目录，名称包含进程号和递增序号，并行评测的各习题互不冲突
+pub fn scratch_dir(kind: &str) -> PathBuf {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    env::temp_dir().join(format!("grader-{}-{}-{}", kind, std::process::id(), id))
+}
+
+fn workspace_key() -> String {
+    let cwd = env::current_dir().ok().and_then(|dir| fs::canonicalize(dir).ok()).unwrap_or_default();
+    let name = cwd
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "root".to_string());
+    let digest = Sha256::digest(cwd.to_string_lossy().as_bytes());
+    format!("{}-{}", name, &hex(&digest)[..12])
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+// 发现未修改的模板占位符时的处理方式
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderAction {
+    Warn,
+    Fail,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PlaceholderConfig {
+    // 模板中的占位符标记
+    pub markers: Vec<String>,
+    pub action: PlaceholderAction,
+}
+
+impl Default for PlaceholderConfig {
+    fn default() -> Self {
+        PlaceholderConfig {
+            markers: vec!["todo!()".to_string(), "unimplemented!()".to_string()],
+            action: PlaceholderAction::Warn,
+        }
+    }
+}
+
+// 一处残留的占位符
+pub struct Placeholder {
+    pub file: PathBuf,
+    pub line: usize,
+    pub marker: String,
+}
+
+// 在习题源码中查找残留的占位符
+pub fn find_placeholders(exercise: &Path, markers: &[String]) -> Vec<Placeholder> {
+    let mut found = Vec::new();
+    for file in exercise_sources(exercise) {
+        let content = match fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for (i, line) in content.lines().enumerate() {
+            if let Some(marker) = markers.iter().find(|m| line.contains(m.as_str())) {
+                found.push(Placeholder {
+                    file: file.clone(),
+                    line: i + 1,
+                    marker: marker.clone(),
+                });
+            }
+        }
+    }
+    found
+}
+
+// 习题的全部源码文件：单文件习题即其本身，Cargo 项目为目录下所有 .rs 文件（忽略 target）
+pub fn exercise_sources(exercise: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if exercise.is_dir() {
+        collect_rs_files(exercise, &mut files);
+        files.sort();
+    } else {
+        files.push(exercise.to_path_buf());
+    }
+    files
+}
+
+fn collect_rs_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                if path.file_name().map(|n| n != "target").unwrap_or(true) {
+                    collect_rs_files(&path, files);
+                }
+            } else if path.extension().map(|ext| ext == "rs").unwrap_or(false) {
+                files.push(path);
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::metadata::ExerciseMode;
+use crate::paths;
+use crate::process;
+use crate::RunContext;
+
+// 单文件习题的编译结果，可执行文件位于该习题独立的临时目录中
+pub struct Compiled {
+    pub output: Output,
+    pub binary: PathBuf,
+    pub build_dir: PathBuf,
+    pub duration: Duration,
+}
+
+// 编译单文件习题；编译产物放到临时目录，避免污染当前目录和习题目录，并行编译的各习题互不冲突
+pub fn compile(exercise_file: &Path) -> Result<Compiled, String> {
+    let build_dir = paths::scratch_dir("build");
+    fs::create_dir_all(&build_dir).map_err(|e| format!("Failed to create {}: {}", build_dir.display(), e))?;
+    let stem = exercise_file.file_stem().unwrap_or_default();
+    let binary = build_dir.join(stem).with_extension(std::env::consts::EXE_EXTENSION);
+
+    let started = Instant::now();
+    let output = process::output(Command::new("rustc").arg("--error-format=json").arg(exercise_file).arg("-o").arg(&binary))
+        .map_err(|e| {
+            let _ = fs::remove_dir_all(&build_dir);
+            format!("Failed to execute rustc: {}", e)
+        })?;
+    Ok(Compiled { output, binary, build_dir, duration: started.elapsed() })
+}
+
+// 批量评测前预先编译好的单文件习题，评测时取出使用；未取用的编译产物在丢弃时删除
+#[derive(Default)]
+pub struct Precompiled {
+    compiled: Mutex<HashMap<PathBuf, Compiled>>,
+}
+
+impl Precompiled {
+    // 取出习题的编译结果，没有预先编译时返回 None
+    pub fn take(&self, exercise_file: &Path) -> Option<Compiled> {
+        self.compiled.lock().unwrap_or_else(|e| e.into_inner()).remove(exercise_file)
+    }
+
+    // 删除尚未取用的编译产物；评测被中断后没有机会取用，进程退出前调用
+    pub fn clear(&self) {
+        for (_, compiled) in self.compiled.lock().unwrap_or_else(|e| e.into_inner()).drain() {
+            let _ = fs::remove_dir_all(&compiled.build_dir);
+        }
+    }
+}
+
+impl Drop for Precompiled {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// 用最多 jobs 个 rustc 进程并行编译本次评测的单文件习题，返回编译的习题数。
+// 编译只写临时目录，即使评测本身需要逐题进行（如检查课程目录中的新文件），编译也可以并行；
+// 习题的评测时限同样适用于编译，超时的习题不保留编译结果，评测时重新编译并按超时记录
+pub fn precompile(ctx: &RunContext, exercises: &[PathBuf], jobs: usize) -> usize {
+    let files: Vec<(&PathBuf, Option<u64>)> = exercises
+        .iter()
+        .filter(|exercise| exercise.is_file())
+        .filter_map(|exercise| {
+            let meta = ctx.exercise_meta(exercise);
+            // 以测试方式评测的习题不编译 main
+            (meta.mode != Some(ExerciseMode::Test)).then_some((exercise, meta.timeout_secs))
+        })
+        .collect();
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, files.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some((file, timeout_secs)) = files.get(index).filter(|_| !process::interrupted()) else {
+                    break;
+                };
+                process::set_deadline(timeout_secs.map(|secs| Instant::now() + Duration::from_secs(secs)));
+                let compiled = compile(file);
+                let timed_out = process::hit_deadline();
+                process::set_deadline(None);
+                match compiled {
+                    Ok(compiled) if !timed_out => {
+                        ctx.precompiled.compiled.lock().unwrap_or_else(|e| e.into_inner()).insert(file.to_path_buf(), compiled);
+                    }
+                    Ok(compiled) => {
+                        let _ = fs::remove_dir_all(&compiled.build_dir);
+                    }
+                    Err(_) => {}
+                }
+            });
+        }
+    });
+    files.len()
+}
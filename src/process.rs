@@ -0,0 +1,368 @@
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 取消标记：watch 模式中评测期间出现新的改动时设置，正在运行的子进程会被结束
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+pub fn cancel() {
+    CANCELLED.store(true, Ordering::Relaxed);
+}
+
+pub fn reset_cancel() {
+    CANCELLED.store(false, Ordering::Relaxed);
+}
+
+pub fn cancelled() -> bool {
+    CANCELLED.load(Ordering::Relaxed) || interrupted()
+}
+
+// 中断标记：用户按下 Ctrl+C 时设置，与取消不同，之后不会被清除
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+// 中断后的退出状态，与 shell 中被 SIGINT 结束的进程一致
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+// 是否已接管 Ctrl+C；接管后子进程才各自成为进程组，否则它们留在终端的前台进程组中，照常收到 Ctrl+C
+static HANDLING_INTERRUPTS: AtomicBool = AtomicBool::new(false);
+// 正在运行的子进程的进程组号，0 表示空位；再次按下 Ctrl+C 时信号处理函数据此结束全部子进程
+const MAX_GROUPS: usize = 256;
+static GROUPS: [AtomicI32; MAX_GROUPS] = [const { AtomicI32::new(0) }; MAX_GROUPS];
+
+// 接管 Ctrl+C：第一次只设置中断标记，正在运行的子进程随即被结束，评测器写出已完成部分的报告后退出；
+// 再按一次时结束全部子进程组后立即退出
+#[cfg(unix)]
+pub fn handle_interrupts() {
+    extern "C" fn on_interrupt(_: libc::c_int) {
+        // 信号处理函数中只做异步信号安全的操作：原子变量读写、killpg 和 _exit
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            for group in &GROUPS {
+                let pgid = group.load(Ordering::Relaxed);
+                if pgid > 0 {
+                    // SAFETY: killpg 是异步信号安全的，只发送信号
+                    unsafe { libc::killpg(pgid, libc::SIGKILL) };
+                }
+            }
+            // SAFETY: _exit 是异步信号安全的
+            unsafe { libc::_exit(EXIT_INTERRUPTED) };
+        }
+    }
+    // SAFETY: 处理函数只访问原子变量并调用 killpg 和 _exit
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+    HANDLING_INTERRUPTS.store(true, Ordering::Relaxed);
+}
+
+// 其他平台保持默认的 Ctrl+C 行为
+#[cfg(not(unix))]
+pub fn handle_interrupts() {}
+
+fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "evaluation cancelled")
+}
+
+thread_local! {
+    // 当前线程正在评测的习题的截止时间；之后启动的子进程到时即被结束
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    // 是否有子进程因到达截止时间而被结束
+    static HIT_DEADLINE: Cell<bool> = const { Cell::new(false) };
+}
+
+// 设置当前线程的评测截止时间（None 表示不限时），同时清除超时标记
+pub fn set_deadline(deadline: Option<Instant>) {
+    DEADLINE.with(|cell| cell.set(deadline));
+    HIT_DEADLINE.with(|cell| cell.set(false));
+}
+
+// 自上次 set_deadline 以来是否有子进程因超时被结束
+pub fn hit_deadline() -> bool {
+    HIT_DEADLINE.with(Cell::get)
+}
+
+fn deadline() -> Option<Instant> {
+    DEADLINE.with(Cell::get)
+}
+
+fn deadline_passed() -> bool {
+    deadline().map(|deadline| Instant::now() >= deadline).unwrap_or(false)
+}
+
+fn timed_out_error() -> io::Error {
+    HIT_DEADLINE.with(|cell| cell.set(true));
+    io::Error::new(io::ErrorKind::TimedOut, "time limit exceeded")
+}
+
+// 与 Command::output 相同，但评测被取消或超过截止时间时结束子进程及其后代进程
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    if deadline_passed() {
+        return Err(timed_out_error());
+    }
+    let mut child = own_process_group(command).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    let group = track_group(&child);
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if cancelled() || deadline_passed() {
+            kill_tree(&mut child);
+            let _ = child.wait();
+            return Err(if cancelled() { cancelled_error() } else { timed_out_error() });
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    drop(group);
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// 运行选项
+#[derive(Default)]
+pub struct RunOptions<'a> {
+    // 写入子进程标准输入的内容
+    pub stdin: Option<&'a [u8]>,
+    // 超时后结束子进程
+    pub timeout: Option<Duration>,
+    // 在隔离的网络命名空间中运行，子进程无法访问网络（仅支持 Linux）
+    pub isolate_network: bool,
+    // 子进程的工作目录，未指定时继承评测器的当前目录
+    pub working_dir: Option<&'a Path>,
+}
+
+// 执行结果及资源使用情况
+pub struct Measured {
+    pub output: Output,
+    // 峰值常驻内存（KB），平台不支持时为 None
+    // 注意：子进程在 exec 之前与评测器共享地址空间，因此该值至少为评测器启动子进程时的内存占用
+    pub peak_memory_kb: Option<u64>,
+    pub duration: Duration,
+    pub timed_out: bool,
+}
+
+// 运行命令并统计其耗时和峰值内存
+pub fn run_measured(command: &mut Command, options: RunOptions) -> io::Result<Measured> {
+    if options.isolate_network {
+        isolate_network(command)?;
+    }
+    if let Some(dir) = options.working_dir {
+        command.current_dir(dir);
+    }
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    let start = Instant::now();
+    let mut child = own_process_group(command)
+        .stdin(if options.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            if options.isolate_network {
+                io::Error::new(e.kind(), format!("{} (network isolation could not be set up)", e))
+            } else {
+                e
+            }
+        })?;
+
+    // 在单独的线程中读写管道，避免管道写满导致子进程阻塞
+    let writer = options.stdin.map(|input| {
+        let input = input.to_vec();
+        let stdin = child.stdin.take();
+        thread::spawn(move || {
+            if let Some(mut stdin) = stdin {
+                // 子进程可能不读取输入就退出，此时写入失败是正常的
+                let _ = stdin.write_all(&input);
+            }
+        })
+    });
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    // 单次运行的时限和整道习题的截止时间取较早者
+    let exercise_deadline = deadline();
+    let deadline = options.timeout.map(|timeout| start + timeout).into_iter().chain(exercise_deadline).min();
+    let group = track_group(&child);
+    let (status, peak_memory_kb, timed_out) = wait_with_usage(&mut child, deadline)?;
+    drop(group);
+    if timed_out && exercise_deadline.map(|deadline| Instant::now() >= deadline).unwrap_or(false) {
+        HIT_DEADLINE.with(|cell| cell.set(true));
+    }
+    let duration = start.elapsed();
+
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    if cancelled() {
+        return Err(cancelled_error());
+    }
+    Ok(Measured {
+        output: Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        },
+        peak_memory_kb,
+        duration,
+        timed_out,
+    })
+}
+
+// 让子进程在 exec 前进入新的网络命名空间（只有回环接口）；
+// 没有 CAP_SYS_ADMIN 时借助非特权用户命名空间
+#[cfg(target_os = "linux")]
+fn isolate_network(command: &mut Command) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    // SAFETY: 闭包在 fork 之后、exec 之前运行，只调用了异步信号安全的 unshare 系统调用
+    unsafe {
+        command.pre_exec(|| {
+            if libc::unshare(libc::CLONE_NEWNET) == 0 || libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn isolate_network(_command: &mut Command) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "network isolation is only supported on Linux"))
+}
+
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+// 通过 wait4 回收子进程，同时取得它的资源使用统计；到达 deadline 时结束子进程
+#[cfg(unix)]
+fn wait_with_usage(child: &mut Child, deadline: Option<Instant>) -> io::Result<(std::process::ExitStatus, Option<u64>, bool)> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let pid = child.id() as libc::pid_t;
+    let mut status: libc::c_int = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let mut timed_out = false;
+    // 轮询等待，以便在超时或评测被取消时结束子进程
+    let flags = libc::WNOHANG;
+    loop {
+        // SAFETY: pid 是我们创建且尚未回收的子进程，status 和 usage 指向有效的栈上变量
+        let ret = unsafe { libc::wait4(pid, &mut status, flags, &mut usage) };
+        if ret == pid {
+            break;
+        }
+        if ret == 0 {
+            // 子进程仍在运行
+            if !timed_out && deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+                timed_out = true;
+                kill_tree(child);
+            }
+            if cancelled() {
+                kill_tree(child);
+            }
+            thread::sleep(Duration::from_millis(5));
+            continue;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+
+    // Linux 上 ru_maxrss 单位为 KB，macOS 上为字节
+    let max_rss = usage.ru_maxrss.max(0) as u64;
+    let peak_kb = if cfg!(target_os = "macos") { max_rss / 1024 } else { max_rss };
+    Ok((std::process::ExitStatus::from_raw(status), Some(peak_kb), timed_out))
+}
+
+// 其他平台暂不统计内存
+#[cfg(not(unix))]
+fn wait_with_usage(child: &mut Child, deadline: Option<Instant>) -> io::Result<(std::process::ExitStatus, Option<u64>, bool)> {
+    let mut timed_out = false;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, None, timed_out));
+        }
+        if !timed_out && deadline.map(|d| Instant::now() >= d).unwrap_or(false) {
+            timed_out = true;
+            kill_tree(child);
+        }
+        if cancelled() {
+            kill_tree(child);
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+// 接管了 Ctrl+C 时子进程自成一个进程组（进程组号即其进程号），它启动的后代进程默认留在同一组中，
+// 结束时可以整组结束
+#[cfg(unix)]
+fn own_process_group(command: &mut Command) -> &mut Command {
+    use std::os::unix::process::CommandExt;
+    if HANDLING_INTERRUPTS.load(Ordering::Relaxed) {
+        command.process_group(0);
+    }
+    command
+}
+
+// 登记的子进程组，离开作用域（子进程已被回收）时注销，进程号被重用后不会误杀其他进程
+struct TrackedGroup(Option<usize>);
+
+// 登记自成进程组的子进程；空位用完时不登记，只是再次按下 Ctrl+C 时不能结束它
+fn track_group(child: &Child) -> TrackedGroup {
+    if !HANDLING_INTERRUPTS.load(Ordering::Relaxed) {
+        return TrackedGroup(None);
+    }
+    let pgid = child.id() as i32;
+    let slot = GROUPS
+        .iter()
+        .position(|group| group.compare_exchange(0, pgid, Ordering::Relaxed, Ordering::Relaxed).is_ok());
+    TrackedGroup(slot)
+}
+
+impl Drop for TrackedGroup {
+    fn drop(&mut self) {
+        if let Some(slot) = self.0 {
+            GROUPS[slot].store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn own_process_group(command: &mut Command) -> &mut Command {
+    command
+}
+
+// 结束子进程及其全部后代进程（如 cargo test 启动的测试程序）；
+// 只结束子进程的话，后代进程会继续运行并占着输出管道，读取输出的线程也会一直阻塞
+fn kill_tree(child: &mut Child) {
+    // 向整个进程组发送信号，不必逐个查找后代进程，也不会漏掉查找之后才启动的进程
+    #[cfg(unix)]
+    // SAFETY: killpg 只发送信号；子进程尚未被回收，它的进程组号不会被其他进程重用
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
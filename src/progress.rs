@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use serde::Serialize;
+use crate::paths;
+
+// 进度文件名，保存在当前课程的状态目录中
+pub const PROGRESS_FILE: &str = "progress.json";
+
+// 供状态栏、课堂监控等外部工具轮询的进度快照
+#[derive(Serialize, Debug)]
+struct Snapshot<'a> {
+    run_id: &'a str,
+    // 最早开始、仍在评测的习题，全部完成后为空
+    current: Option<&'a str>,
+    // 并行评测时正在评测的全部习题
+    running: Vec<&'a str>,
+    completed: usize,
+    total: usize,
+    elapsed_secs: u64,
+    // 按已完成习题的平均耗时估算的剩余时间
+    eta_secs: Option<u64>,
+    done: bool,
+}
+
+// 评测过程中持续更新 progress.json
+pub struct Progress {
+    path: PathBuf,
+    run_id: String,
+    total: usize,
+    completed: usize,
+    running: Vec<String>,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(run_id: &str, total: usize) -> Self {
+        let progress = Progress {
+            path: paths::state_file(PROGRESS_FILE),
+            run_id: run_id.to_string(),
+            total,
+            completed: 0,
+            running: Vec::new(),
+            started: Instant::now(),
+        };
+        progress.write(false);
+        progress
+    }
+
+    // 开始评测一道习题
+    pub fn start(&mut self, exercise: &Path) {
+        self.running.push(exercise.display().to_string());
+        self.write(false);
+    }
+
+    // 一道习题评测完成
+    pub fn finish(&mut self, exercise: &Path) {
+        let name = exercise.display().to_string();
+        self.running.retain(|running| *running != name);
+        self.completed += 1;
+        self.write(false);
+    }
+
+    // 整个运行结束
+    pub fn done(self) {
+        self.write(true);
+    }
+
+    fn write(&self, done: bool) {
+        let elapsed = self.started.elapsed();
+        let eta_secs = if self.completed > 0 && !done {
+            let remaining = self.total.saturating_sub(self.completed) as u32;
+            Some((elapsed / self.completed as u32 * remaining).as_secs())
+        } else {
+            None
+        };
+        let snapshot = Snapshot {
+            run_id: &self.run_id,
+            current: self.running.first().map(String::as_str),
+            running: self.running.iter().map(String::as_str).collect(),
+            completed: self.completed,
+            total: self.total,
+            elapsed_secs: elapsed.as_secs(),
+            eta_secs,
+            done,
+        };
+        // 先写临时文件再重命名，避免读取方看到写了一半的内容
+        let temp = self.path.with_extension("json.tmp");
+        let written = serde_json::to_vec_pretty(&snapshot)
+            .map_err(|e| e.to_string())
+            .and_then(|data| fs::write(&temp, data).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&temp, &self.path).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            eprintln!("Error writing {}: {}", self.path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::config::Config;
+use crate::paths;
+use crate::ExerciseResult;
+
+// 解锁状态文件，保存在当前课程的状态目录中
+pub const UNLOCKS_FILE: &str = "unlocks.json";
+
+// 按进度解锁：学生通过过的习题，通过后一直保持解锁，之后即使改坏也不会重新锁定后面的习题
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Unlocks {
+    pub passed: BTreeSet<String>,
+}
+
+// 被锁定的习题及其尚未通过的前置习题
+pub struct Locked {
+    pub exercise: PathBuf,
+    pub waiting_for: Vec<String>,
+}
+
+impl Unlocks {
+    // 读取解锁状态，文件不存在时所有习题都未通过
+    pub fn load() -> Result<Unlocks, String> {
+        let path = paths::state_file(UNLOCKS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Unlocks::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = paths::state_file(UNLOCKS_FILE);
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // 记录通过的习题，返回新解锁的习题数
+    pub fn record<'a>(&mut self, results: impl IntoIterator<Item = &'a ExerciseResult>) -> usize {
+        results
+            .into_iter()
+            .filter(|result| result.result && self.passed.insert(result.name.clone()))
+            .count()
+    }
+
+    // 把习题分为已解锁和被锁定两部分。习题的前置习题是元数据中声明的 dependencies，
+    // 未声明时为顺序中排在它前面的全部习题；前置习题都通过过才解锁
+    pub fn partition(&self, exercises: &[PathBuf], config: &Config) -> (Vec<PathBuf>, Vec<Locked>) {
+        let names: Vec<Option<String>> = exercises.iter().map(|exercise| config.exercise_meta(exercise).name).collect();
+        let mut unlocked = Vec::new();
+        let mut locked = Vec::new();
+        for (index, exercise) in exercises.iter().enumerate() {
+            let dependencies = config.exercise_meta(exercise).dependencies;
+            let prerequisites: Vec<&PathBuf> = if dependencies.is_empty() {
+                exercises[..index].iter().collect()
+            } else {
+                exercises
+                    .iter()
+                    .zip(&names)
+                    .filter(|(path, name)| {
+                        dependencies.iter().any(|dependency| {
+                            name.as_deref() == Some(dependency.as_str()) || Path::new(dependency) == path.as_path()
+                        })
+                    })
+                    .map(|(path, _)| path)
+                    .collect()
+            };
+            let waiting_for: Vec<String> = prerequisites
+                .iter()
+                .map(|path| path.display().to_string())
+                .filter(|name| !self.passed.contains(name))
+                .collect();
+            if waiting_for.is_empty() {
+                unlocked.push(exercise.clone());
+            } else {
+                locked.push(Locked { exercise: exercise.clone(), waiting_for });
+            }
+        }
+        (unlocked, locked)
+    }
+}
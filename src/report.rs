@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use crate::display::{self, Status};
+use crate::{csv, html, junit, markdown_report, sarif};
+use crate::Report;
+
+// 读取 JSON 报告
+pub fn load(file: &Path) -> Result<Report, String> {
+    let reader = File::open(file).map_err(|e| format!("Failed to open {}: {}", file.display(), e))?;
+    serde_json::from_reader(reader).map_err(|e| format!("Failed to parse {}: {}", file.display(), e))
+}
+
+// 合并各 CI 分片生成的部分报告
+pub fn merge_reports(files: &[String]) -> Result<Report, String> {
+    if files.is_empty() {
+        return Err("Please provide the partial reports to merge".to_string());
+    }
+
+    let mut merged = Report::new(None);
+    let mut seen = HashSet::new();
+    for file in files {
+        let report = load(Path::new(file))?;
+        if merged.user_name.is_none() {
+            merged.user_name = report.user_name;
+        }
+        // 各分片并行运行，总耗时取最长的一片
+        merged.statistics.total_time = merged.statistics.total_time.max(report.statistics.total_time);
+        for exercise in report.exercises {
+            if !seen.insert(exercise.name.clone()) {
+                return Err(format!("Exercise {} appears in more than one partial report", exercise.name));
+            }
+            if exercise.result {
+                merged.statistics.total_succeeds += 1;
+            } else {
+                merged.statistics.total_failures += 1;
+            }
+            merged.exercises.push(exercise);
+        }
+    }
+    merged.exercises.sort_by(|a, b| a.name.cmp(&b.name));
+    merged.statistics.total_exercations = merged.statistics.total_succeeds + merged.statistics.total_failures;
+    Ok(merged)
+}
+
+// 比较两份报告：通过数的变化，以及结果发生变化、新增和移除的习题
+pub fn diff_reports(old: &Report, new: &Report) -> String {
+    let old_results: BTreeMap<&str, bool> = old.exercises.iter().map(|e| (e.name.as_str(), e.result)).collect();
+    let new_results: BTreeMap<&str, bool> = new.exercises.iter().map(|e| (e.name.as_str(), e.result)).collect();
+    let mut fixed = Vec::new();
+    let mut broken = Vec::new();
+    let mut added = Vec::new();
+    for (name, result) in &new_results {
+        match old_results.get(name) {
+            Some(previous) if previous == result => {}
+            Some(_) if *result => fixed.push(*name),
+            Some(_) => broken.push(*name),
+            None => added.push((*name, *result)),
+        }
+    }
+    let removed: Vec<&str> = old_results.keys().filter(|name| !new_results.contains_key(*name)).copied().collect();
+
+    let old_passed = old.statistics.total_succeeds;
+    let new_passed = new.statistics.total_succeeds;
+    let mut text = format!(
+        "Passed: {}/{} -> {}/{} ({:+})\n",
+        old_passed,
+        old.statistics.total_exercations,
+        new_passed,
+        new.statistics.total_exercations,
+        new_passed as i64 - old_passed as i64
+    );
+    let pass = display::symbol(Status::Pass);
+    let fail = display::symbol(Status::Fail);
+    let sections = [
+        ("Now passing", fixed.iter().map(|name| format!("{} {}", pass, name)).collect::<Vec<_>>()),
+        ("Now failing", broken.iter().map(|name| format!("{} {}", fail, name)).collect()),
+        (
+            "New exercises",
+            added.iter().map(|(name, result)| format!("{} {}", if *result { pass } else { fail }, name)).collect(),
+        ),
+        ("Removed exercises", removed.iter().map(|name| name.to_string()).collect()),
+    ];
+    if sections.iter().all(|(_, lines)| lines.is_empty()) {
+        text.push_str("No exercise changed its result\n");
+    }
+    for (title, lines) in sections.iter().filter(|(_, lines)| !lines.is_empty()) {
+        text.push_str(&format!("{}:\n", title));
+        for line in lines {
+            text.push_str(&format!("  {}\n", line));
+        }
+    }
+    text
+}
+
+// 报告写出器：把评测报告渲染为一种格式。内置格式各在自己的模块中实现，
+// 其他 crate 可以通过 register 添加自定义格式，之后即可用 --report-format 选用
+pub trait ReportWriter: Send + Sync {
+    // 格式名称，如 junit
+    fn name(&self) -> &str;
+    // 格式的其他名称，如 markdown 的 md
+    fn aliases(&self) -> &[&str] {
+        &[]
+    }
+    // 报告文件的扩展名，文件名与 JSON 报告相同
+    fn extension(&self) -> &str;
+    fn render(&self, report: &Report) -> String;
+}
+
+// JSON 报告，与 report.json 内容相同
+pub struct JsonWriter;
+
+impl ReportWriter for JsonWriter {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn extension(&self) -> &str {
+        "json"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        serde_json::to_string_pretty(report).unwrap_or_default()
+    }
+}
+
+// 已注册的报告写出器，按注册顺序排列
+fn registry() -> &'static RwLock<Vec<Arc<dyn ReportWriter>>> {
+    static WRITERS: OnceLock<RwLock<Vec<Arc<dyn ReportWriter>>>> = OnceLock::new();
+    WRITERS.get_or_init(|| {
+        RwLock::new(vec![
+            Arc::new(JsonWriter),
+            Arc::new(junit::JunitWriter),
+            Arc::new(html::HtmlWriter),
+            Arc::new(markdown_report::MarkdownWriter),
+            Arc::new(csv::CsvWriter),
+            Arc::new(sarif::SarifWriter),
+        ])
+    })
+}
+
+// 注册报告写出器；与已注册的格式同名时替换原有的写出器
+pub fn register(writer: impl ReportWriter + 'static) {
+    let mut writers = registry().write().unwrap_or_else(|e| e.into_inner());
+    writers.retain(|existing| existing.name() != writer.name());
+    writers.push(Arc::new(writer));
+}
+
+// 已注册的全部格式名称
+pub fn formats() -> Vec<String> {
+    let writers = registry().read().unwrap_or_else(|e| e.into_inner());
+    writers.iter().map(|writer| writer.name().to_string()).collect()
+}
+
+// 选定的报告格式
+#[derive(Clone)]
+pub struct ReportFormat(Arc<dyn ReportWriter>);
+
+impl ReportFormat {
+    // 按名称或别名查找已注册的格式
+    pub fn parse(text: &str) -> Result<ReportFormat, String> {
+        let writers = registry().read().unwrap_or_else(|e| e.into_inner());
+        writers
+            .iter()
+            .find(|writer| writer.name() == text || writer.aliases().contains(&text))
+            .map(|writer| ReportFormat(writer.clone()))
+            .ok_or_else(|| format!("unknown report format '{}' (expected one of: {})", text, formats().join(", ")))
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    pub fn extension(&self) -> &str {
+        self.0.extension()
+    }
+
+    pub fn render(&self, report: &Report) -> String {
+        self.0.render(report)
+    }
+}
+
+impl fmt::Debug for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReportFormat").field(&self.name()).finish()
+    }
+}
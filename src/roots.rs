@@ -0,0 +1,45 @@
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+// 未通过命令行或配置指定时使用的习题目录
+pub const DEFAULT_ROOT: &str = "exercises";
+
+// 一个习题根目录（如 exercises/、challenges/），报告中按 label 标注习题所属的根目录
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExerciseRoot {
+    pub path: PathBuf,
+    // 未配置时使用目录名
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl ExerciseRoot {
+    pub fn new(path: impl Into<PathBuf>) -> ExerciseRoot {
+        ExerciseRoot { path: path.into(), label: None }
+    }
+
+    pub fn label(&self) -> String {
+        self.label.clone().unwrap_or_else(|| {
+            self.path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.path.display().to_string())
+        })
+    }
+}
+
+// 命令行（--exercises-dir，可重复）或 EXERCISES_DIR 优先，其次是配置中的 [[roots]]，都没有时使用 exercises
+pub fn resolve(cli: &[String], configured: &[ExerciseRoot]) -> Vec<ExerciseRoot> {
+    if !cli.is_empty() {
+        cli.iter().map(ExerciseRoot::new).collect()
+    } else if !configured.is_empty() {
+        configured.to_vec()
+    } else {
+        vec![ExerciseRoot::new(DEFAULT_ROOT)]
+    }
+}
+
+// 习题所在的根目录
+pub fn root_of<'a>(roots: &'a [ExerciseRoot], exercise: &Path) -> Option<&'a ExerciseRoot> {
+    roots.iter().find(|root| exercise.starts_with(&root.path))
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::clippy::ClippyConfig;
+use crate::display::{self, Status};
+use crate::output::ExerciseOutput;
+use crate::paths;
+use crate::process;
+use crate::steps::StepStatus;
+
+// 支持的评分项：能否编译、测试（没有 #[test] 函数的单文件习题为程序运行）是否通过、clippy 是否通过、格式是否符合 rustfmt
+pub const COMPONENTS: &[&str] = &["compiles", "tests", "clippy", "fmt"];
+
+// 评分细则中单个评分项的结果
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScoreComponent {
+    pub name: String,
+    pub weight: f64,
+    pub passed: bool,
+}
+
+// 按评分细则计算得分（0-100），steps 为评测过程中各步骤的结果，跳过的步骤不得分；
+// 评测流程中没有的评分项（如 fmt）在这里补充检查
+pub fn score(
+    rubric: &BTreeMap<String, f64>,
+    clippy: &ClippyConfig,
+    exercise: &Path,
+    steps: &BTreeMap<&'static str, StepStatus>,
+    out: &mut ExerciseOutput,
+) -> (f64, Vec<ScoreComponent>) {
+    let mut components = Vec::new();
+    for (name, weight) in rubric {
+        let passed = match steps.get(name.as_str()) {
+            Some(status) => *status == StepStatus::Passed,
+            None => match name.as_str() {
+                "fmt" => check_fmt(exercise, out),
+                "clippy" => check_clippy(exercise, clippy, out),
+                "compiles" | "tests" => false,
+                _ => {
+                    out.err(&format!(
+                        "Unknown rubric component '{}' (expected one of: {})",
+                        name,
+                        COMPONENTS.join(", ")
+                    ));
+                    false
+                }
+            },
+        };
+        components.push(ScoreComponent { name: name.clone(), weight: *weight, passed });
+    }
+
+    let total: f64 = components.iter().map(|c| c.weight).sum();
+    let earned: f64 = components.iter().filter(|c| c.passed).map(|c| c.weight).sum();
+    let score = if total > 0.0 { (earned / total * 1000.0).round() / 10.0 } else { 0.0 };
+    (score, components)
+}
+
+// 得分明细，如 "80.0/100 (compiles ✓, tests ✓, clippy ✗, fmt ✓)"，符号随主题变化
+pub fn describe(score: f64, components: &[ScoreComponent]) -> String {
+    let parts: Vec<String> = components
+        .iter()
+        .map(|c| format!("{} {}", c.name, display::symbol(if c.passed { Status::Pass } else { Status::Fail })))
+        .collect();
+    format!("{:.1}/100 ({})", score, parts.join(", "))
+}
+
+// 检查代码格式：Cargo 项目运行 cargo fmt --check，单文件运行 rustfmt --check
+fn check_fmt(exercise: &Path, out: &mut ExerciseOutput) -> bool {
+    let mut command = if exercise.is_dir() {
+        let mut command = Command::new("cargo");
+        command.args(["fmt", "--check"]).current_dir(exercise);
+        command
+    } else {
+        let mut command = Command::new("rustfmt");
+        command.args(["--check", "--edition", "2021"]).arg(exercise);
+        command
+    };
+    // 纯 ASCII 模式下差异输出不带颜色
+    if display::ascii() {
+        if exercise.is_dir() {
+            command.arg("--");
+        }
+        command.args(["--color", "never"]);
+    }
+    run_check(&mut command, "rustfmt", out)
+}
+
+// 单文件习题的 clippy 检查（Cargo 项目在评测流程中已执行 cargo clippy）
+fn check_clippy(exercise: &Path, clippy: &ClippyConfig, out: &mut ExerciseOutput) -> bool {
+    let build_dir = paths::scratch_dir("clippy");
+    if let Err(e) = std::fs::create_dir_all(&build_dir) {
+        out.err(&format!("Failed to create {}: {}", build_dir.display(), e));
+        return false;
+    }
+    let mut command = Command::new("clippy-driver");
+    command
+        .arg(exercise)
+        .args(["--edition", "2021", "--emit=metadata", "--out-dir"])
+        .arg(&build_dir)
+        .args(clippy.args());
+    let passed = run_check(&mut command, "clippy-driver", out);
+    if !passed {
+        out.err(&format!("Clippy settings: {}", clippy.describe()));
+    }
+    let _ = std::fs::remove_dir_all(&build_dir);
+    passed
+}
+
+fn run_check(command: &mut Command, program: &str, out: &mut ExerciseOutput) -> bool {
+    match process::output(command) {
+        Ok(output) => {
+            if !output.status.success() {
+                for stream in [&output.stdout, &output.stderr] {
+                    if !stream.is_empty() {
+                        out.err(&String::from_utf8_lossy(stream));
+                    }
+                }
+            }
+            output.status.success()
+        }
+        Err(e) => {
+            out.err(&format!("Failed to execute {}: {}", program, e));
+            false
+        }
+    }
+}
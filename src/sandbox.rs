@@ -0,0 +1,97 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::metadata::ExerciseMeta;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// 运行习题程序用的一次性工作目录，程序结束后整个删除；
+// 程序在其中的任何写入（包括误删文件）都不会影响课程仓库和评测器状态
+pub struct Sandbox {
+    dir: PathBuf,
+}
+
+impl Sandbox {
+    // 按元数据创建沙箱；关闭了文件系统隔离时返回 None（程序在当前目录运行）
+    pub fn for_exercise(meta: &ExerciseMeta, exercise_dir: &Path) -> Result<Option<Sandbox>, String> {
+        if !meta.isolate_fs.unwrap_or(true) {
+            return Ok(None);
+        }
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("grader-sandbox-{}-{}", std::process::id(), id));
+        let sandbox = Sandbox { dir };
+        fs::create_dir_all(&sandbox.dir)
+            .map_err(|e| format!("Failed to create sandbox {}: {}", sandbox.dir.display(), e))?;
+        // 可选地放入习题目录的只读副本，程序可以按相对路径读取其中的数据文件
+        if meta.expose_exercise.unwrap_or(false) {
+            copy_read_only(exercise_dir, &sandbox.dir)
+                .map_err(|e| format!("Failed to copy {} into the sandbox: {}", exercise_dir.display(), e))?;
+        }
+        Ok(Some(sandbox))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        // 只读目录中的文件无法删除（Windows 上只读文件本身也无法删除），先恢复写权限
+        let _ = set_writable(&self.dir);
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+// 复制目录（跳过 target），复制出的文件和子目录都设为只读，程序无法修改、删除或在其中新建文件；
+// 沙箱根目录本身仍可写，程序可以在其中创建自己的文件
+fn copy_read_only(source: &Path, dest: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let target = dest.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            if path.file_name().map(|name| name != "target").unwrap_or(true) {
+                fs::create_dir_all(&target)?;
+                copy_read_only(&path, &target)?;
+                // 目录的内容复制完成后再设为只读
+                set_read_only(&target, true)?;
+            }
+        } else {
+            fs::copy(&path, &target)?;
+            set_read_only(&target, true)?;
+        }
+    }
+    Ok(())
+}
+
+// 恢复沙箱中所有文件和子目录的写权限；先恢复目录本身，才能继续处理其中的条目
+fn set_writable(dir: &Path) -> io::Result<()> {
+    set_read_only(dir, false)?;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            set_writable(&path)?;
+        } else {
+            set_read_only(&path, false)?;
+        }
+    }
+    Ok(())
+}
+
+// Unix 上只增删所有者的写权限，避免 set_readonly(false) 让文件对所有用户可写
+#[cfg(unix)]
+fn set_read_only(path: &Path, read_only: bool) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    let mode = permissions.mode();
+    permissions.set_mode(if read_only { mode & !0o222 } else { mode | 0o200 });
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn set_read_only(path: &Path, read_only: bool) -> io::Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_readonly(read_only);
+    fs::set_permissions(path, permissions)
+}
@@ -0,0 +1,67 @@
+use std::collections::BTreeSet;
+use serde_json::{json, Value};
+use crate::report::ReportWriter;
+use crate::Report;
+
+// SARIF，编译器和 clippy 的诊断可以在 GitHub code scanning 和编辑器中显示为行内标注
+pub struct SarifWriter;
+
+impl ReportWriter for SarifWriter {
+    fn name(&self) -> &str {
+        "sarif"
+    }
+
+    fn extension(&self) -> &str {
+        "sarif"
+    }
+
+    fn render(&self, report: &Report) -> String {
+        render(report)
+    }
+}
+
+// 生成 SARIF 2.1.0 报告：评测过程中编译器和 clippy 的每条诊断是一个结果，
+// 上传到 GitHub code scanning 或在编辑器中打开后显示为代码行内的标注
+pub fn render(report: &Report) -> String {
+    let mut rules = BTreeSet::new();
+    let mut results = Vec::new();
+    for exercise in &report.exercises {
+        for diagnostic in &exercise.diagnostics {
+            let rule = diagnostic.code.clone().unwrap_or_else(|| "rustc".to_string());
+            results.push(json!({
+                "ruleId": rule,
+                "level": diagnostic.level,
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": diagnostic.file.replace('\\', "/") },
+                        "region": {
+                            "startLine": diagnostic.line,
+                            "startColumn": diagnostic.column,
+                            "endLine": diagnostic.end_line,
+                            "endColumn": diagnostic.end_column,
+                        },
+                    },
+                }],
+                "properties": { "exercise": exercise.name },
+            }));
+            rules.insert(rule);
+        }
+    }
+    let rules: Vec<Value> = rules.into_iter().map(|rule| json!({ "id": rule })).collect();
+    let sarif = json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cargotest",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&sarif).unwrap_or_default()
+}
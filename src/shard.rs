@@ -0,0 +1,53 @@
+use std::path::Path;
+
+// CI 分片：第 index 片（从 1 开始），共 count 片
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: u64,
+    pub count: u64,
+}
+
+impl Shard {
+    // 解析 `i/n` 形式的分片参数
+    pub fn parse(value: &str) -> Result<Shard, String> {
+        let invalid = || format!("Invalid shard '{}': expected i/n with 1 <= i <= n", value);
+        let (index, count) = value.split_once('/').ok_or_else(invalid)?;
+        let index: u64 = index.trim().parse().map_err(|_| invalid())?;
+        let count: u64 = count.trim().parse().map_err(|_| invalid())?;
+        if count == 0 || index == 0 || index > count {
+            return Err(invalid());
+        }
+        Ok(Shard { index, count })
+    }
+
+    // 习题是否属于本分片；按路径的稳定哈希划分，与目录遍历顺序无关
+    pub fn contains(&self, exercise: &Path) -> bool {
+        let key = exercise.to_string_lossy().replace('\\', "/");
+        mix(fnv1a(key.as_bytes())) % self.count == self.index - 1
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+// FNV-1a 哈希，结果不随编译器版本变化，保证各 CI 任务划分一致
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// FNV 的低位分布较差，取模前再做一次 splitmix64 的末尾混合
+fn mix(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
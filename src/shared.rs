@@ -0,0 +1,53 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::hashing::content_hash;
+
+// 课程自带的公共辅助 crate（如 course_utils），习题通过 path 依赖引用它
+pub struct SharedCrate {
+    pub path: PathBuf,
+    fingerprint: String,
+}
+
+impl SharedCrate {
+    // 预先编译公共 crate 并记录其源码指纹
+    pub fn prepare(path: PathBuf) -> Result<SharedCrate, String> {
+        if !path.join("Cargo.toml").exists() {
+            return Err(format!("Shared crate {} has no Cargo.toml", path.display()));
+        }
+
+        println!("\nBuilding shared crate: {}", path.display());
+        let output = Command::new("cargo")
+            .arg("build")
+            .current_dir(&path)
+            .output()
+            .map_err(|e| format!("Failed to execute cargo build: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Shared crate {} failed to build:\n{}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        let fingerprint = content_hash(&path)
+            .map_err(|e| format!("Failed to read shared crate {}: {}", path.display(), e))?;
+        Ok(SharedCrate { path, fingerprint })
+    }
+
+    // 公共 crate 的源码是否被修改过（习题只能以只读方式使用它）
+    pub fn is_modified(&self) -> bool {
+        match content_hash(&self.path) {
+            Ok(fingerprint) => fingerprint != self.fingerprint,
+            Err(_) => true,
+        }
+    }
+}
+
+// 检查所有公共 crate 是否保持只读，返回被修改的 crate 列表
+pub fn modified_crates(shared: &[SharedCrate]) -> Vec<&Path> {
+    shared
+        .iter()
+        .filter(|c| c.is_modified())
+        .map(|c| c.path.as_path())
+        .collect()
+}
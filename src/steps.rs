@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::display::{self, Status};
+
+// 评测步骤在输出中的顺序：编译、测试、clippy、运行程序（Cargo 项目按元数据需要时才运行）
+const ORDER: &[&str] = &["compiles", "tests", "clippy", "run"];
+
+// 单个评测步骤的结果；编译失败后依赖编译结果的步骤不再执行，记为跳过
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn from_result(passed: bool) -> StepStatus {
+        if passed {
+            StepStatus::Passed
+        } else {
+            StepStatus::Failed
+        }
+    }
+
+    // 多个 feature 组合下同一步骤的合并结果：任一组合失败即为失败，其次是跳过
+    pub fn merge(self, other: StepStatus) -> StepStatus {
+        match (self, other) {
+            (StepStatus::Failed, _) | (_, StepStatus::Failed) => StepStatus::Failed,
+            (StepStatus::Skipped, _) | (_, StepStatus::Skipped) => StepStatus::Skipped,
+            _ => StepStatus::Passed,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StepStatus::Passed => "passed",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        display::symbol(match self {
+            StepStatus::Passed => Status::Pass,
+            StepStatus::Failed => Status::Fail,
+            StepStatus::Skipped => Status::Skip,
+        })
+    }
+}
+
+// 按评测顺序排列的各步骤结果
+pub fn ordered<S: AsRef<str>>(steps: &BTreeMap<S, StepStatus>) -> Vec<(&str, StepStatus)> {
+    let mut ordered: Vec<(&str, StepStatus)> = steps.iter().map(|(name, status)| (name.as_ref(), *status)).collect();
+    ordered.sort_by_key(|(name, _)| ORDER.iter().position(|step| step == name).unwrap_or(ORDER.len()));
+    ordered
+}
+
+// 各步骤结果的一行描述，如 "compiles ✓, tests ✗, clippy -"，符号随主题变化
+pub fn describe<S: AsRef<str>>(steps: &BTreeMap<S, StepStatus>) -> String {
+    join(steps, StepStatus::symbol)
+}
+
+// 写入报告文件用的纯文字描述，如 "compiles passed, tests failed, clippy skipped"
+pub fn summary<S: AsRef<str>>(steps: &BTreeMap<S, StepStatus>) -> String {
+    join(steps, StepStatus::label)
+}
+
+fn join<S: AsRef<str>>(steps: &BTreeMap<S, StepStatus>, text: fn(StepStatus) -> &'static str) -> String {
+    ordered(steps)
+        .iter()
+        .map(|(name, status)| format!("{} {}", name, text(*status)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,281 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use crate::auth;
+use crate::hashing::hex;
+use crate::paths;
+
+// 提交失败（如教室断网）的报告暂存在状态目录的这个子目录中，之后用 `submit --flush` 补交
+const QUEUE_DIR: &str = "queue";
+// 报告签名（报告内容的 HMAC-SHA256，密钥在登录时由服务器下发）所在的请求头，服务器据此确认报告生成后未被修改
+const SIGNATURE_HEADER: &str = "X-Report-Signature";
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ServerConfig {
+    // 课程服务器地址，如 https://course.example.com
+    pub url: String,
+    // 设备码登录时使用的 OAuth client id
+    #[serde(default = "default_client_id")]
+    pub client_id: String,
+}
+
+fn default_client_id() -> String {
+    "rust-basic-grader".to_string()
+}
+
+impl ServerConfig {
+    pub fn endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), path)
+    }
+}
+
+enum SubmitError {
+    // 网络不可用或服务器暂时故障，可以稍后重试
+    Retryable(String),
+    Fatal(String),
+}
+
+// 将评测报告提交到课程服务器，网络故障时放入本地队列
+pub fn submit_report(server: &ServerConfig, report_file: &Path) -> Result<(), String> {
+    let token = load_token()?;
+    let report = fs::read_to_string(report_file)
+        .map_err(|e| format!("Failed to read {}: {}", report_file.display(), e))?;
+    let run_id = run_id_of(&report)?;
+    let signature = verified_signature(report_file, &report)?;
+
+    match post_report(server, &token, &run_id, &report, signature.as_deref()) {
+        Ok(()) => {
+            println!("Report {} submitted.", run_id);
+            Ok(())
+        }
+        Err(SubmitError::Retryable(e)) => {
+            let path = enqueue(&run_id, &report, signature.as_deref())?;
+            println!("Submission failed ({}); report queued at {}", e, path.display());
+            println!("Run `submit --flush` once the network is available.");
+            Ok(())
+        }
+        Err(SubmitError::Fatal(e)) => Err(e),
+    }
+}
+
+// 上传队列中所有待提交的报告
+pub fn flush_queue(server: &ServerConfig) -> Result<(), String> {
+    let token = load_token()?;
+    let mut pending: Vec<PathBuf> = match fs::read_dir(paths::state_file(QUEUE_DIR)) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    pending.sort();
+    if pending.is_empty() {
+        println!("No pending submissions.");
+        return Ok(());
+    }
+
+    let mut remaining = 0;
+    for path in pending {
+        let report = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let run_id = run_id_of(&report)?;
+        // 排队期间被修改的报告不上传
+        let signature = match verified_signature(&path, &report) {
+            Ok(signature) => signature,
+            Err(e) => {
+                eprintln!("Report {} not submitted: {}", run_id, e);
+                remaining += 1;
+                continue;
+            }
+        };
+        match post_report(server, &token, &run_id, &report, signature.as_deref()) {
+            Ok(()) => {
+                println!("Report {} submitted.", run_id);
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+                let _ = fs::remove_file(signature_file(&path));
+            }
+            Err(SubmitError::Retryable(e)) | Err(SubmitError::Fatal(e)) => {
+                eprintln!("Report {} not submitted: {}", run_id, e);
+                remaining += 1;
+            }
+        }
+    }
+    if remaining > 0 {
+        return Err(format!("{} report(s) still pending", remaining));
+    }
+    Ok(())
+}
+
+fn load_token() -> Result<String, String> {
+    auth::load_token()?
+        .ok_or_else(|| "Not logged in. Run `login` first or set GRADER_TOKEN".to_string())
+}
+
+// 服务器根据 run id 去重，同一次运行的报告重复提交只记一次
+fn post_report(server: &ServerConfig, token: &str, run_id: &str, report: &str, signature: Option<&str>) -> Result<(), SubmitError> {
+    let mut request = ureq::post(&server.endpoint("api/reports"))
+        .set("Content-Type", "application/json")
+        .set("Authorization", &format!("Bearer {}", token))
+        .set("X-Run-Id", run_id)
+        .timeout(Duration::from_secs(30));
+    if let Some(signature) = signature {
+        request = request.set(SIGNATURE_HEADER, signature);
+    }
+    let response = request.send_string(report);
+    match response {
+        Ok(_) => Ok(()),
+        // 409 表示服务器已经收到过这次运行的报告
+        Err(ureq::Error::Status(409, _)) => Ok(()),
+        Err(ureq::Error::Status(code, _)) if code >= 500 => {
+            Err(SubmitError::Retryable(format!("server returned {}", code)))
+        }
+        Err(ureq::Error::Status(code, response)) => Err(SubmitError::Fatal(format!(
+            "Failed to submit report: server returned {}: {}",
+            code,
+            response.into_string().unwrap_or_default()
+        ))),
+        Err(e) => Err(SubmitError::Retryable(e.to_string())),
+    }
+}
+
+fn run_id_of(report: &str) -> Result<String, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(report).map_err(|e| format!("Invalid report: {}", e))?;
+    value
+        .get("run_id")
+        .and_then(|id| id.as_str())
+        .map(|id| id.to_string())
+        .ok_or_else(|| "Report has no run_id; re-run the grader before submitting".to_string())
+}
+
+// 报告和签名一起排队，补交时重新校验
+fn enqueue(run_id: &str, report: &str, signature: Option<&str>) -> Result<PathBuf, String> {
+    let queue = paths::state_file(QUEUE_DIR);
+    fs::create_dir_all(&queue).map_err(|e| format!("Failed to create {}: {}", queue.display(), e))?;
+    let path = queue.join(format!("{}.json", run_id));
+    fs::write(&path, report).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    if let Some(signature) = signature {
+        let file = signature_file(&path);
+        fs::write(&file, signature).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    }
+    Ok(path)
+}
+
+// 生成报告时签名：用登录时保存的密钥计算报告文件的 HMAC，写到同名的 .sig 文件中；
+// 没有密钥时删除旧的签名，报告不签名提交
+pub fn sign_report(report_file: &Path) -> Result<(), String> {
+    sign_with(auth::load_signing_key()?.as_deref(), report_file)
+}
+
+fn sign_with(key: Option<&[u8]>, report_file: &Path) -> Result<(), String> {
+    let file = signature_file(report_file);
+    let Some(key) = key else {
+        let _ = fs::remove_file(&file);
+        return Ok(());
+    };
+    let report = fs::read(report_file).map_err(|e| format!("Failed to read {}: {}", report_file.display(), e))?;
+    fs::write(&file, signature(key, &report)?).map_err(|e| format!("Failed to write {}: {}", file.display(), e))
+}
+
+// 校验报告的签名，返回要随报告上传的签名；有密钥时报告必须带有与内容相符的签名
+fn verified_signature(report_file: &Path, report: &str) -> Result<Option<String>, String> {
+    verify_with(auth::load_signing_key()?.as_deref(), report_file, report)
+}
+
+fn verify_with(key: Option<&[u8]>, report_file: &Path, report: &str) -> Result<Option<String>, String> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+    let file = signature_file(report_file);
+    let Ok(stored) = fs::read_to_string(&file) else {
+        return Err(format!("{} is not signed; re-run the grader to produce a signed report", report_file.display()));
+    };
+    let expected = signature(key, report.as_bytes())?;
+    if stored.trim() != expected {
+        return Err(format!("{} was modified after grading (signature mismatch); re-run the grader", report_file.display()));
+    }
+    Ok(Some(expected))
+}
+
+fn signature(key: &[u8], report: &[u8]) -> Result<String, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| format!("Invalid signing key: {}", e))?;
+    mac.update(report);
+    Ok(hex(&mac.finalize().into_bytes()))
+}
+
+fn signature_file(report_file: &Path) -> PathBuf {
+    report_file.with_extension("sig")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    // 每个测试独占的临时目录，其中有一份报告
+    fn report_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("grader-submit-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("report.json");
+        fs::write(&file, r#"{"run_id":"r1","total_successes":3}"#).unwrap();
+        file
+    }
+
+    #[test]
+    fn signature_depends_on_key_and_content() {
+        let report = br#"{"run_id":"r1"}"#;
+        let signed = signature(KEY, report).unwrap();
+        assert_eq!(signed.len(), 64);
+        assert_eq!(signed, signature(KEY, report).unwrap());
+        assert_ne!(signed, signature(b"another key", report).unwrap());
+        assert_ne!(signed, signature(KEY, br#"{"run_id":"r2"}"#).unwrap());
+    }
+
+    #[test]
+    fn signed_report_is_verified() {
+        let file = report_file("verified");
+        sign_with(Some(KEY), &file).unwrap();
+        let report = fs::read_to_string(&file).unwrap();
+        assert_eq!(verify_with(Some(KEY), &file, &report).unwrap(), Some(signature(KEY, report.as_bytes()).unwrap()));
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn modified_report_is_refused() {
+        let file = report_file("modified");
+        sign_with(Some(KEY), &file).unwrap();
+        let tampered = r#"{"run_id":"r1","total_successes":30}"#;
+        fs::write(&file, tampered).unwrap();
+        let err = verify_with(Some(KEY), &file, tampered).unwrap_err();
+        assert!(err.contains("signature mismatch"), "{}", err);
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn unsigned_report_is_refused_when_a_key_exists() {
+        let file = report_file("unsigned");
+        let report = fs::read_to_string(&file).unwrap();
+        let err = verify_with(Some(KEY), &file, &report).unwrap_err();
+        assert!(err.contains("is not signed"), "{}", err);
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn reports_are_not_signed_without_a_key() {
+        let file = report_file("no-key");
+        sign_with(Some(KEY), &file).unwrap();
+        // 没有密钥时删除旧签名，提交时不附带签名
+        sign_with(None, &file).unwrap();
+        assert!(!signature_file(&file).exists());
+        let report = fs::read_to_string(&file).unwrap();
+        assert_eq!(verify_with(None, &file, &report).unwrap(), None);
+        fs::remove_dir_all(file.parent().unwrap()).unwrap();
+    }
+}
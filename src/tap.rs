@@ -0,0 +1,95 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use crate::clipboard::strip_ansi;
+use crate::steps;
+use crate::{ExerciseStatus, Report};
+
+// 标准输出的格式：默认为人类可读的文本；tap 时标准输出只有 TAP version 13，
+// 其余输出都转到标准错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Tap,
+}
+
+impl OutputFormat {
+    pub fn parse(text: &str) -> Result<OutputFormat, String> {
+        match text {
+            "human" => Ok(OutputFormat::Human),
+            "tap" => Ok(OutputFormat::Tap),
+            _ => Err(format!("unknown output format '{}' (expected human or tap)", text)),
+        }
+    }
+}
+
+// 把标准输出重定向到标准错误，使评测过程中的文本输出不混入 TAP；返回原来的标准输出
+#[cfg(unix)]
+pub fn take_stdout() -> io::Result<Box<dyn Write>> {
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+    io::stdout().flush()?;
+    // SAFETY: dup/dup2 只操作文件描述符；dup 返回的新描述符由 File 独占
+    unsafe {
+        let original = libc::dup(libc::STDOUT_FILENO);
+        if original < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 {
+            let error = io::Error::last_os_error();
+            libc::close(original);
+            return Err(error);
+        }
+        Ok(Box::new(File::from_raw_fd(original)))
+    }
+}
+
+// 其他平台上无法重定向，TAP 与文本输出混在一起；TAP 解析器会忽略不认识的行
+#[cfg(not(unix))]
+pub fn take_stdout() -> io::Result<Box<dyn Write>> {
+    Ok(Box::new(io::stdout()))
+}
+
+// 生成 TAP version 13：每道习题一个测试点，未通过的习题附带 YAML 诊断块
+pub fn render(report: &Report) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", report.exercises.len());
+    for (i, exercise) in report.exercises.iter().enumerate() {
+        // 描述中的 # 会被当作指令的开始
+        let name = exercise.name.replace('#', "\\#");
+        if exercise.result {
+            let _ = writeln!(tap, "ok {} - {}", i + 1, name);
+            continue;
+        }
+        let _ = writeln!(tap, "not ok {} - {}", i + 1, name);
+        let message = match exercise.status {
+            ExerciseStatus::InProgress => "in progress",
+            _ => exercise.reason.map(|reason| reason.label()).unwrap_or("failed"),
+        };
+        let _ = writeln!(tap, "  ---\n  message: {}\n  severity: fail", message);
+        if let Some(code) = exercise.code {
+            let _ = writeln!(tap, "  code: {}", code.as_str());
+        }
+        if let Some(duration_ms) = exercise.duration_ms {
+            let _ = writeln!(tap, "  duration_ms: {}", duration_ms);
+        }
+        let steps = steps::ordered(&exercise.steps);
+        if !steps.is_empty() {
+            tap.push_str("  steps:\n");
+            for (step, status) in steps {
+                let _ = writeln!(tap, "    {}: {}", step, status.label());
+            }
+        }
+        let log: String = strip_ansi(&exercise.log)
+            .chars()
+            .filter(|ch| !ch.is_control() || *ch == '\n')
+            .collect();
+        if !log.trim().is_empty() {
+            tap.push_str("  log: |-\n");
+            for line in log.trim().lines() {
+                let _ = writeln!(tap, "    {}", line);
+            }
+        }
+        tap.push_str("  ...\n");
+    }
+    tap
+}
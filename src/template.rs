@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::Path;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+
+// 不复制到个性化习题中的目录：构建产物和版本库元数据
+const SKIPPED_DIRS: &[&str] = &["target", ".git"];
+
+// 生成个性化习题时可用的模板变量：
+// {{student}}、{{course}}、{{seed}}，以及由种子确定的随机整数 {{random MIN MAX}}（含两端）；
+// 其他 {{...}}（如格式化字符串中转义的花括号）保持原样
+pub struct Variables {
+    pub student: String,
+    pub course: String,
+    pub seed: u64,
+}
+
+impl Variables {
+    // 未指定种子时由课程和学生姓名派生，同一学生重新生成得到相同的习题
+    pub fn new(student: &str, course: &str, seed: Option<u64>) -> Variables {
+        let seed = seed.unwrap_or_else(|| {
+            let digest = Sha256::digest(format!("{}\n{}", course, student).as_bytes());
+            u64::from_le_bytes(digest[..8].try_into().unwrap_or_default())
+        });
+        Variables { student: student.to_string(), course: course.to_string(), seed }
+    }
+
+    // 替换文本中的模板变量；file 参与随机数的派生，使各文件中的随机数互不相关，
+    // 且一个文件的修改不会改变其他文件中的值
+    pub fn render(&self, text: &str, file: &str) -> Result<String, String> {
+        let pattern = Regex::new(r"\{\{\s*(student|course|seed|random\s+(-?\d+)\s+(-?\d+))\s*\}\}").map_err(|e| e.to_string())?;
+        let mut index = 0u64;
+        let mut error = None;
+        let rendered = pattern.replace_all(text, |caps: &Captures| match &caps[1] {
+            "student" => self.student.clone(),
+            "course" => self.course.clone(),
+            "seed" => self.seed.to_string(),
+            _ => {
+                let (min, max) = match (caps[2].parse::<i64>(), caps[3].parse::<i64>()) {
+                    (Ok(min), Ok(max)) if min <= max => (min, max),
+                    _ => {
+                        error.get_or_insert_with(|| format!("{}: invalid range in {}", file, &caps[0]));
+                        return caps[0].to_string();
+                    }
+                };
+                index += 1;
+                self.random(file, index, min, max).to_string()
+            }
+        });
+        match error {
+            Some(error) => Err(error),
+            None => Ok(rendered.into_owned()),
+        }
+    }
+
+    fn random(&self, file: &str, index: u64, min: i64, max: i64) -> i64 {
+        let digest = Sha256::digest(format!("{}\n{}\n{}", self.seed, file, index).as_bytes());
+        let value = u64::from_le_bytes(digest[..8].try_into().unwrap_or_default());
+        let span = (max as i128 - min as i128 + 1) as u128;
+        (min as i128 + (value as u128 % span) as i128) as i64
+    }
+}
+
+// 把模板习题目录复制到 output，并替换其中文本文件的模板变量；返回生成的文件数
+pub fn personalize(template: &Path, output: &Path, variables: &Variables) -> Result<usize, String> {
+    if !template.is_dir() {
+        return Err(format!("Template directory {} does not exist", template.display()));
+    }
+    if output.exists() && fs::read_dir(output).map(|mut entries| entries.next().is_some()).unwrap_or(true) {
+        return Err(format!("{} already exists and is not empty", output.display()));
+    }
+    copy_dir(template, template, output, variables)
+}
+
+fn copy_dir(root: &Path, dir: &Path, output: &Path, variables: &Variables) -> Result<usize, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut count = 0;
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?.path();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let target = output.join(relative);
+        if path.is_dir() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                count += copy_dir(root, &path, output, variables)?;
+            }
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let content = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // 非 UTF-8 的文件（如图片、数据文件）原样复制
+        let content = match String::from_utf8(content) {
+            Ok(text) => variables.render(&text, &relative.to_string_lossy().replace('\\', "/"))?.into_bytes(),
+            Err(e) => e.into_bytes(),
+        };
+        fs::write(&target, content).map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        count += 1;
+    }
+    Ok(count)
+}
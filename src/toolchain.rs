@@ -0,0 +1,129 @@
+use serde::Deserialize;
+use crate::matrix::{self, TOOLCHAIN_ENV};
+
+// 课程要求的编译器版本，在 grader.toml 的 [toolchain] 中配置
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ToolchainConfig {
+    // 发布通道：stable、beta 或 nightly
+    pub channel: Option<String>,
+    // 固定版本，如 "1.80" 匹配任意 1.80.x，"1.80.1" 只匹配该版本
+    pub version: Option<String>,
+    // 最低版本，如 "1.75"
+    pub min_version: Option<String>,
+    // 当前编译器不满足要求时，若 rustup 中已安装满足要求的工具链则自动切换过去
+    pub auto_select: bool,
+}
+
+// 从 rustc --version 的输出中解析出的版本信息
+struct RustcVersion {
+    numbers: Vec<u64>,
+    channel: String,
+}
+
+impl ToolchainConfig {
+    // 检查当前编译器；不满足要求时按配置尝试自动切换，失败则返回说明所需版本和安装方法的错误
+    pub fn ensure(&self) -> Result<(), String> {
+        let current = current_version(None)?;
+        let Some(problem) = self.mismatch(&current) else {
+            return Ok(());
+        };
+        let toolchain = self.toolchain_name();
+        if self.auto_select {
+            if let Ok(version) = current_version(Some(&toolchain)) {
+                if self.mismatch(&version).is_none() {
+                    println!("Using toolchain '{}' as required by the course", toolchain);
+                    std::env::set_var(TOOLCHAIN_ENV, &toolchain);
+                    return Ok(());
+                }
+            }
+        }
+        let install = match self.version.as_deref() {
+            None if toolchain == "stable" => "rustup update stable".to_string(),
+            _ => format!("rustup toolchain install {}", toolchain),
+        };
+        let mut message = format!(
+            "This course requires {}, but {}.\nInstall it with `{}`",
+            self.describe(),
+            problem,
+            install
+        );
+        if !self.auto_select {
+            message.push_str(&format!(", then run `rustup override set {}` in this directory", toolchain));
+        }
+        Err(message)
+    }
+
+    // 不满足要求时返回原因
+    fn mismatch(&self, current: &RustcVersion) -> Option<String> {
+        let found = format!("found rustc {} ({})", join(&current.numbers), current.channel);
+        if let Some(channel) = &self.channel {
+            if *channel != current.channel {
+                return Some(found);
+            }
+        }
+        if let Some(version) = &self.version {
+            let pinned = parse_numbers(version);
+            if current.numbers.len() < pinned.len() || current.numbers[..pinned.len()] != pinned[..] {
+                return Some(found);
+            }
+        }
+        if let Some(min_version) = &self.min_version {
+            if current.numbers < parse_numbers(min_version) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    // 满足要求的 rustup 工具链名称
+    fn toolchain_name(&self) -> String {
+        match (&self.version, &self.channel) {
+            (Some(version), _) => version.clone(),
+            (None, Some(channel)) => channel.clone(),
+            (None, None) => "stable".to_string(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(version) = &self.version {
+            parts.push(format!("rustc {}", version));
+        }
+        if let Some(min_version) = &self.min_version {
+            parts.push(format!("rustc >= {}", min_version));
+        }
+        if let Some(channel) = &self.channel {
+            parts.push(format!("the {} channel", channel));
+        }
+        if parts.is_empty() {
+            parts.push("rustc".to_string());
+        }
+        parts.join(" on ")
+    }
+}
+
+fn current_version(toolchain: Option<&str>) -> Result<RustcVersion, String> {
+    let version = match toolchain {
+        Some(toolchain) => matrix::toolchain_version(toolchain)?,
+        None => matrix::current_version()?,
+    };
+    // 形如 "rustc 1.80.1 (3f5fd8dd4 2024-08-06)" 或 "rustc 1.82.0-nightly (...)"
+    let number = version.split_whitespace().nth(1).unwrap_or_default();
+    let (numbers, channel) = match number.split_once('-') {
+        Some((numbers, pre)) => (numbers, pre.split('.').next().unwrap_or(pre)),
+        None => (number, "stable"),
+    };
+    Ok(RustcVersion {
+        numbers: parse_numbers(numbers),
+        channel: channel.to_string(),
+    })
+}
+
+fn parse_numbers(version: &str) -> Vec<u64> {
+    version.split('.').filter_map(|part| part.trim().parse().ok()).collect()
+}
+
+fn join(numbers: &[u64]) -> String {
+    numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(".")
+}
@@ -0,0 +1,334 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use crate::paths;
+
+// 运行记录的目录，位于状态目录中：每次评测一个 <运行 ID>.jsonl，保存评测过程中终端上的全部输出
+const TRANSCRIPT_DIR: &str = "transcripts";
+// 结束记录时等待读完管道中剩余输出的最长时间
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+// replay --realtime 时两段输出之间最长的停顿，watch 会话中长时间的空闲不必原样等待
+const MAX_PAUSE: Duration = Duration::from_secs(3);
+
+// 记录文件的第一行；--resume 接着评测时追加到被中断的运行的记录中，一个文件可以有多段
+#[derive(Serialize, Deserialize)]
+struct Header {
+    run_id: String,
+    started_at: u64,
+    args: Vec<String>,
+}
+
+// 之后每行一段输出，保留颜色控制符
+#[derive(Serialize, Deserialize)]
+struct Chunk {
+    // 距本段记录开始的毫秒数
+    at: u64,
+    stream: Stream,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Header(Header),
+    Chunk(Chunk),
+}
+
+// 正在记录的运行
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+// 记录文件，各管道的读取线程共用
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+struct Recorder {
+    path: PathBuf,
+    // 被替换的描述符及其原来指向的终端（dup 出的副本）
+    saved: Vec<(i32, i32)>,
+    // 读取线程读到管道结束时发送
+    done: mpsc::Receiver<()>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn dir() -> PathBuf {
+    paths::state_file(TRANSCRIPT_DIR)
+}
+
+// 开始记录：把标准输出和标准错误换成管道，读取线程把输出照常写到终端，同时写入记录文件；
+// 只保留最近 retention 次运行的记录，0 表示不记录
+#[cfg(unix)]
+pub fn start(run_id: &str, started_at: u64, args: &[String], retention: usize) -> Result<(), String> {
+    use std::os::fd::FromRawFd;
+    if retention == 0 {
+        return Ok(());
+    }
+    let dir = dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    prune(retention.saturating_sub(1));
+    let path = dir.join(format!("{}.jsonl", run_id));
+    let mut file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let header = Header { run_id: run_id.to_string(), started_at, args: args.to_vec() };
+    writeln!(file, "{}", serde_json::to_string(&header).unwrap_or_default())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    *lock(&FILE) = Some(file);
+
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+    let start = Instant::now();
+    let (tx, done) = mpsc::channel();
+    let mut saved = Vec::new();
+    for (fd, stream) in [(libc::STDOUT_FILENO, Stream::Stdout), (libc::STDERR_FILENO, Stream::Stderr)] {
+        // SAFETY: 只操作文件描述符；dup 出的终端副本和管道读端由 File 独占，
+        // 管道写端 dup2 到 fd 后立即关闭
+        let (original, terminal, reader) = unsafe {
+            let original = libc::dup(fd);
+            let terminal = libc::dup(fd);
+            let mut pipe = [0; 2];
+            if original < 0 || terminal < 0 || libc::pipe(pipe.as_mut_ptr()) < 0 {
+                let error = io::Error::last_os_error();
+                libc::close(original);
+                libc::close(terminal);
+                restore(&saved);
+                *lock(&FILE) = None;
+                return Err(format!("Failed to record the output: {}", error));
+            }
+            libc::fcntl(pipe[0], libc::F_SETFD, libc::FD_CLOEXEC);
+            libc::fcntl(original, libc::F_SETFD, libc::FD_CLOEXEC);
+            libc::fcntl(terminal, libc::F_SETFD, libc::FD_CLOEXEC);
+            libc::dup2(pipe[1], fd);
+            libc::close(pipe[1]);
+            (original, File::from_raw_fd(terminal), File::from_raw_fd(pipe[0]))
+        };
+        saved.push((fd, original));
+        let tx = tx.clone();
+        thread::spawn(move || {
+            forward(reader, terminal, stream, start);
+            let _ = tx.send(());
+        });
+    }
+    *lock(&RECORDER) = Some(Recorder { path, saved, done });
+    Ok(())
+}
+
+// 其他平台上无法替换描述符，不记录
+#[cfg(not(unix))]
+pub fn start(_run_id: &str, _started_at: u64, _args: &[String], _retention: usize) -> Result<(), String> {
+    Ok(())
+}
+
+// 把管道中的输出写到终端并记入文件；多字节字符被拆到两次读取中时，不完整的部分留到下一次
+#[cfg(unix)]
+fn forward(mut reader: File, mut terminal: File, stream: Stream, start: Instant) {
+    use std::io::Read;
+    let mut buffer = [0; 8192];
+    let mut pending = Vec::new();
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        let _ = terminal.write_all(&buffer[..n]);
+        pending.extend_from_slice(&buffer[..n]);
+        let complete = match std::str::from_utf8(&pending) {
+            Ok(_) => pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => pending.len(),
+        };
+        let text = String::from_utf8_lossy(&pending[..complete]).to_string();
+        pending.drain(..complete);
+        record(Chunk { at: start.elapsed().as_millis() as u64, stream, text });
+    }
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).to_string();
+        record(Chunk { at: start.elapsed().as_millis() as u64, stream, text });
+    }
+}
+
+fn record(chunk: Chunk) {
+    if chunk.text.is_empty() {
+        return;
+    }
+    if let Some(file) = lock(&FILE).as_mut() {
+        let _ = writeln!(file, "{}", serde_json::to_string(&chunk).unwrap_or_default());
+    }
+}
+
+// 把描述符恢复为原来的终端
+#[cfg(unix)]
+fn restore(saved: &[(i32, i32)]) {
+    for &(fd, original) in saved {
+        // SAFETY: original 是 start 中 dup 出的描述符，恢复后不再使用
+        unsafe {
+            libc::dup2(original, fd);
+            libc::close(original);
+        }
+    }
+}
+
+// --resume 接着评测被中断的运行时，本次的记录并入该运行的记录，replay 时两段依次显示
+pub fn resume(run_id: &str) {
+    let mut recorder = lock(&RECORDER);
+    let Some(recorder) = recorder.as_mut() else {
+        return;
+    };
+    let target = dir().join(format!("{}.jsonl", run_id));
+    if target == recorder.path {
+        return;
+    }
+    let mut file = lock(&FILE);
+    let merged = fs::read(&recorder.path).and_then(|content| {
+        let mut target_file = OpenOptions::new().create(true).append(true).open(&target)?;
+        target_file.write_all(&content)?;
+        Ok(target_file)
+    });
+    match merged {
+        Ok(target_file) => {
+            let _ = fs::remove_file(&recorder.path);
+            *file = Some(target_file);
+            recorder.path = target;
+        }
+        Err(e) => eprintln!("Warning: failed to add to the transcript of run {}: {}", run_id, e),
+    }
+}
+
+// 结束记录：恢复标准输出和标准错误，等待读取线程写完剩余的输出；进程退出前调用
+pub fn finish() {
+    let Some(recorder) = lock(&RECORDER).take() else {
+        return;
+    };
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+    #[cfg(unix)]
+    restore(&recorder.saved);
+    // 仍在运行的子进程持有管道写端时读取线程不会结束，最多等待 DRAIN_TIMEOUT
+    let deadline = Instant::now() + DRAIN_TIMEOUT;
+    for _ in &recorder.saved {
+        if recorder.done.recv_timeout(deadline.saturating_duration_since(Instant::now())).is_err() {
+            break;
+        }
+    }
+    if let Some(mut file) = lock(&FILE).take() {
+        let _ = file.flush();
+    }
+}
+
+// replay [<运行 ID>]：按记录原样重新显示一次运行的输出，不重新评测；
+// 运行 ID 可以只给出能唯一确定运行的前缀，不给出时显示最近一次运行
+pub fn replay(run_id: Option<&str>, realtime: bool) -> Result<(), String> {
+    let path = find(run_id)?;
+    let file = File::open(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    let mut previous = 0;
+    let mut resumed = false;
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let line: Line = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid transcript {} at line {}: {}", path.display(), number + 1, e))?;
+        match line {
+            Line::Header(header) => {
+                let started_at = OffsetDateTime::from_unix_timestamp(header.started_at as i64)
+                    .ok()
+                    .and_then(|at| at.format(&Rfc3339).ok())
+                    .unwrap_or_default();
+                let command = std::iter::once("cargotest").chain(header.args.iter().map(String::as_str)).collect::<Vec<_>>();
+                let _ = stdout.flush();
+                // 后面的段是 --resume 接着评测的部分
+                let _ = match resumed {
+                    false => writeln!(stderr, "Replaying run {} (`{}`, started {})", header.run_id, command.join(" "), started_at),
+                    true => writeln!(stderr, "Resumed with `{}`, started {}", command.join(" "), started_at),
+                };
+                resumed = true;
+                previous = 0;
+            }
+            Line::Chunk(chunk) => {
+                if realtime {
+                    let _ = stdout.flush();
+                    thread::sleep(Duration::from_millis(chunk.at.saturating_sub(previous)).min(MAX_PAUSE));
+                    previous = chunk.at;
+                }
+                let result = match chunk.stream {
+                    Stream::Stdout => stdout.write_all(chunk.text.as_bytes()),
+                    Stream::Stderr => {
+                        let _ = stdout.flush();
+                        stderr.write_all(chunk.text.as_bytes())
+                    }
+                };
+                // 输出被关闭（如接到 head）时停止
+                if result.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    let _ = stdout.flush();
+    Ok(())
+}
+
+// 查找运行的记录文件
+fn find(run_id: Option<&str>) -> Result<PathBuf, String> {
+    let transcripts = transcripts();
+    let Some(run_id) = run_id else {
+        return transcripts.last().cloned().ok_or_else(|| format!("No transcripts in {}", dir().display()));
+    };
+    let matches: Vec<&PathBuf> = transcripts
+        .iter()
+        .filter(|path| path.file_stem().is_some_and(|stem| stem.to_string_lossy().starts_with(run_id)))
+        .collect();
+    match matches.as_slice() {
+        [path] => Ok(path.to_path_buf()),
+        [] => Err(format!("No transcript for run {} in {}", run_id, dir().display())),
+        paths => {
+            let exact = paths.iter().find(|path| path.file_stem().is_some_and(|stem| stem == run_id));
+            exact.map(|path| path.to_path_buf()).ok_or_else(|| {
+                let ids: Vec<String> =
+                    paths.iter().map(|path| path.file_stem().unwrap_or_default().to_string_lossy().to_string()).collect();
+                format!("Run ID {} is ambiguous: {}", run_id, ids.join(", "))
+            })
+        }
+    }
+}
+
+// 全部记录文件，按修改时间从旧到新排列
+fn transcripts() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+    let mut transcripts: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .map(|path| (modified(&path), path))
+        .collect();
+    transcripts.sort();
+    transcripts.into_iter().map(|(_, path)| path).collect()
+}
+
+fn modified(path: &Path) -> std::time::SystemTime {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).unwrap_or(std::time::UNIX_EPOCH)
+}
+
+// 删除最旧的记录，只保留最近 keep 份
+fn prune(keep: usize) {
+    let transcripts = transcripts();
+    let excess = transcripts.len().saturating_sub(keep);
+    for path in &transcripts[..excess] {
+        let _ = fs::remove_file(path);
+    }
+}
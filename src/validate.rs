@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::display::{self, Status, Tone};
+use crate::output::ExerciseOutput;
+use crate::config::{Config, Exclusions};
+use crate::metadata::ExerciseMeta;
+use crate::{evaluate_cargo_project, evaluate_single_file, get_rs_files_in_directory, instructor, markdown, ExerciseMetrics, RunContext};
+
+// 课程作者用的内容校验：每道习题都要有题面，题面中的 Rust 代码片段能编译，
+// 参考答案能通过该习题自己的评测流程，前置习题都存在；返回发现的问题数
+pub fn validate(ctx: &RunContext, exercises: &[PathBuf]) -> usize {
+    let mut checked_readmes = HashSet::new();
+    let mut problems = 0;
+    let metas: Vec<ExerciseMeta> = exercises.iter().map(|exercise| ctx.exercise_meta(exercise)).collect();
+    let names: HashSet<&str> = metas.iter().filter_map(|meta| meta.name.as_deref()).collect();
+    for (exercise, meta) in exercises.iter().zip(&metas) {
+        let mut issues = Vec::new();
+        for dependency in &meta.dependencies {
+            if !names.contains(dependency.as_str()) {
+                issues.push(format!("unknown dependency '{}' (no exercise has this name)", dependency));
+            }
+        }
+        // 同一章节的单文件习题共用一份 README，只检查一次
+        match markdown::readme_for(exercise) {
+            Some(readme) => {
+                if checked_readmes.insert(readme.clone()) {
+                    issues.extend(check_statement(&readme));
+                }
+            }
+            None => issues.push("no problem statement (README.md)".to_string()),
+        }
+        if let Err(e) = check_solution(ctx, exercise) {
+            issues.push(e);
+        }
+
+        if issues.is_empty() {
+            let line = format!("{}{}: OK", display::prefix(Status::Pass), exercise.display());
+            println!("{}", display::paint(&line, Tone::Good));
+        } else {
+            let line = format!("{}{}: {} problem(s)", display::prefix(Status::Fail), exercise.display(), issues.len());
+            println!("{}", display::paint(&line, Tone::Bad));
+            for issue in &issues {
+                println!("  - {}", issue.trim_end().replace('\n', "\n    "));
+            }
+            problems += issues.len();
+        }
+    }
+    problems
+}
+
+// 同时含有 Cargo.toml 和散落 .rs 文件、却没有声明布局的目录：默认只评测 Cargo 项目，
+// 散落的 .rs 文件是草稿还是习题不明确，提醒课程作者在元数据中声明
+pub fn warn_mixed_layouts(config: &Config, exercise_dirs: &[PathBuf], exclusions: &Exclusions) {
+    for dir in exercise_dirs {
+        if !dir.join("Cargo.toml").exists() || config.exercise_meta(dir).layout.is_some() {
+            continue;
+        }
+        let root = dir.parent().unwrap_or(Path::new("."));
+        let files = get_rs_files_in_directory(root, dir, exclusions);
+        if files.is_empty() {
+            continue;
+        }
+        let names: Vec<String> = files
+            .iter()
+            .filter_map(|file| file.file_name().map(|name| name.to_string_lossy().to_string()))
+            .collect();
+        let line = format!(
+            "Warning: {} has both a Cargo.toml and loose .rs files ({}); only the Cargo project is graded. \
+             Declare layout = \"cargo\", \"files\" or \"both\" in [exercises.\"{}\"]",
+            dir.display(),
+            names.join(", "),
+            dir.display()
+        );
+        println!("{}", display::paint(&line, Tone::Warn));
+    }
+}
+
+// 检查题面：内容非空，且其中的 rust 代码块能编译
+fn check_statement(readme: &Path) -> Vec<String> {
+    let content = match fs::read_to_string(readme) {
+        Ok(content) => content,
+        Err(e) => return vec![format!("failed to read {}: {}", readme.display(), e)],
+    };
+    if content.trim().is_empty() {
+        return vec![format!("{} is empty", readme.display())];
+    }
+
+    let mut issues = Vec::new();
+    for (line, attributes, code) in rust_snippets(&content) {
+        // 与 rustdoc 一致：ignore 的片段不检查，compile_fail 的片段应当编译失败
+        if attributes.iter().any(|a| a == "ignore") {
+            continue;
+        }
+        let should_fail = attributes.iter().any(|a| a == "compile_fail");
+        match compile_snippet(&code) {
+            Ok(compiled) if compiled == should_fail => {
+                let expected = if should_fail { "fail to compile" } else { "compile" };
+                issues.push(format!("{}:{}: code snippet does not {}", readme.display(), line, expected));
+            }
+            Ok(_) => {}
+            Err(e) => issues.push(format!("{}:{}: {}", readme.display(), line, e)),
+        }
+    }
+    issues
+}
+
+// 提取 ```rust 代码块：(起始行号, 代码块属性, 代码)
+fn rust_snippets(markdown: &str) -> Vec<(usize, Vec<String>, String)> {
+    let mut snippets = Vec::new();
+    let mut current: Option<(usize, Vec<String>, String)> = None;
+    for (index, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(info) = trimmed.strip_prefix("```") {
+            match current.take() {
+                Some(snippet) => snippets.push(snippet),
+                None => {
+                    let attributes: Vec<String> = info.split(',').map(|a| a.trim().to_string()).collect();
+                    if attributes.first().map(|lang| lang == "rust").unwrap_or(false) {
+                        current = Some((index + 1, attributes, String::new()));
+                    } else {
+                        // 其他语言的代码块：跳过到结束标记
+                        current = Some((0, Vec::new(), String::new()));
+                    }
+                }
+            }
+        } else if let Some((_, _, code)) = current.as_mut() {
+            code.push_str(line);
+            code.push('\n');
+        }
+    }
+    snippets.into_iter().filter(|(line, _, _)| *line > 0).collect()
+}
+
+// 编译代码片段，返回是否编译成功；没有 main 函数的片段包装进 main 中
+fn compile_snippet(code: &str) -> Result<bool, String> {
+    let source = if code.contains("fn main") {
+        format!("#![allow(unused)]\n{}", code)
+    } else {
+        format!("#![allow(unused)]\nfn main() {{\n{}}}\n", code)
+    };
+    let dir = std::env::temp_dir().join(format!("grader-snippet-{}", std::process::id()));
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let file = dir.join("snippet.rs");
+    fs::write(&file, source).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    let output = Command::new("rustc")
+        .arg(&file)
+        .args(["--edition", "2021", "--emit=metadata", "--out-dir"])
+        .arg(&dir)
+        .output()
+        .map_err(|e| format!("Failed to execute rustc: {}", e))?;
+    let _ = fs::remove_dir_all(&dir);
+    Ok(output.status.success())
+}
+
+// 把参考答案覆盖到习题的临时副本上，用习题自己的元数据和隐藏测试跑一遍评测流程
+fn check_solution(ctx: &RunContext, exercise: &Path) -> Result<(), String> {
+    let relative = exercise.strip_prefix(&ctx.exercises_dir).unwrap_or(exercise);
+    let solution = Path::new(&ctx.config.solutions_dir).join(relative);
+    if !solution.exists() {
+        return Err(format!("no reference solution at {}", solution.display()));
+    }
+
+    let root = std::env::temp_dir().join(format!("grader-validate-{}", std::process::id()));
+    let copy = root.join(relative);
+    let prepared = if exercise.is_dir() {
+        copy_dir(exercise, &copy).and_then(|_| copy_dir(&solution, &copy))
+    } else {
+        // 单文件习题连同同目录的数据文件一起复制
+        let parent = copy.parent().unwrap_or(&root).to_path_buf();
+        copy_dir(exercise.parent().unwrap_or(Path::new(".")), &parent).and_then(|_| fs::copy(&solution, &copy).map(|_| ()))
+    };
+    let result = prepared
+        .map_err(|e| format!("Failed to prepare {}: {}", copy.display(), e))
+        .and_then(|_| run_pipeline(ctx, exercise, &root, &copy));
+    let _ = fs::remove_dir_all(&root);
+    result
+}
+
+fn run_pipeline(ctx: &RunContext, exercise: &Path, root: &Path, copy: &Path) -> Result<(), String> {
+    let meta = ctx.exercise_meta(exercise);
+    let mut metrics = ExerciseMetrics::default();
+    if copy.is_dir() {
+        let mut out = ExerciseOutput::new(&exercise.display().to_string(), false);
+        let hidden = instructor::inject_hidden_tests(copy, root, &ctx.hidden_tests_dir)?;
+        let passed = evaluate_cargo_project(copy, &meta, &mut metrics, &mut out);
+        instructor::remove_hidden_tests(&hidden);
+        let errors = out.errors().to_string();
+        out.discard();
+        if passed {
+            Ok(())
+        } else {
+            Err(format!("reference solution fails:\n{}", errors))
+        }
+    } else {
+        evaluate_single_file(copy, &meta, None, &mut metrics)
+            .map_err(|e| format!("reference solution fails:\n{}", e))
+    }
+}
+
+// 递归复制目录（跳过 target），已存在的文件被覆盖
+fn copy_dir(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        let target = dest.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            if path.file_name().map(|name| name != "target").unwrap_or(true) {
+                copy_dir(&path, &target)?;
+            }
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Instant;
+use crate::build_cache::BUILD_DIR_ENV;
+use crate::paths;
+
+// 状态目录中汇总全部依赖的预热 crate
+const WARMUP_CRATE: &str = "warmup";
+// Cargo.toml 中参与预热的依赖表；评测会运行测试，dev-dependencies 同样需要编译
+const DEPENDENCY_TABLES: [&str; 2] = ["dependencies", "dev-dependencies"];
+
+// 各 Cargo 项目习题依赖的并集，按层给出：同名且声明相同的依赖只保留一份；
+// 同名但声明不同（如不同版本或 feature）的依赖放到后面的层中，各层分别编译，每层中同名依赖只有一个
+pub fn dependencies(exercises: &[PathBuf]) -> Vec<toml::Table> {
+    let mut layers: Vec<toml::Table> = Vec::new();
+    for exercise in exercises.iter().filter(|exercise| exercise.join("Cargo.toml").exists()) {
+        let Ok(manifest) = fs::read_to_string(exercise.join("Cargo.toml")) else {
+            continue;
+        };
+        let Ok(manifest) = toml::from_str::<toml::Table>(&manifest) else {
+            continue;
+        };
+        for table in DEPENDENCY_TABLES {
+            let Some(dependencies) = manifest.get(table).and_then(|table| table.as_table()) else {
+                continue;
+            };
+            for (name, spec) in dependencies {
+                let Some(spec) = normalize(exercise, spec) else {
+                    continue;
+                };
+                match layers.iter_mut().find(|layer| layer.get(name).is_none_or(|existing| *existing == spec)) {
+                    Some(layer) => {
+                        layer.insert(name.clone(), spec);
+                    }
+                    None => layers.push(toml::Table::from_iter([(name.clone(), spec)])),
+                }
+            }
+        }
+    }
+    layers
+}
+
+// 把依赖声明改写为预热 crate 中可用的形式：统一为表格，可选依赖同样编译，path 依赖改为绝对路径；
+// 继承工作区的依赖无法单独解析，跳过
+fn normalize(exercise: &Path, spec: &toml::Value) -> Option<toml::Value> {
+    let mut table = match spec {
+        toml::Value::String(version) => {
+            let mut table = toml::Table::new();
+            table.insert("version".to_string(), toml::Value::String(version.clone()));
+            table
+        }
+        toml::Value::Table(table) => table.clone(),
+        _ => return None,
+    };
+    if table.contains_key("workspace") {
+        return None;
+    }
+    table.remove("optional");
+    if let Some(path) = table.get("path").and_then(|path| path.as_str()) {
+        let path = fs::canonicalize(exercise.join(path)).ok()?;
+        table.insert("path".to_string(), toml::Value::String(path.to_string_lossy().to_string()));
+    }
+    Some(toml::Value::Table(table))
+}
+
+// 评测前把各 Cargo 项目习题依赖的并集编译到构建缓存共用的中间产物目录中，
+// 之后各习题的构建只需编译习题本身，计时反映的是学生代码而不是首次编译依赖。
+// 返回预热的依赖数
+pub fn warm_up(exercises: &[PathBuf], build_dir: &Path) -> Result<usize, String> {
+    let layers = dependencies(exercises);
+    if layers.is_empty() {
+        return Ok(0);
+    }
+
+    let count: usize = layers.iter().map(|layer| layer.len()).sum();
+    println!("Warming up {} dependencies of the Cargo exercises...", count);
+    let started = Instant::now();
+    for (index, dependencies) in layers.into_iter().enumerate() {
+        let crate_dir = paths::state_file(WARMUP_CRATE).join(index.to_string());
+        write_crate(&crate_dir, dependencies)?;
+        // build 编译构建和测试用的依赖，check 生成 clippy 使用的元数据
+        for command in ["build", "check"] {
+            let output = Command::new("cargo")
+                .arg(command)
+                .env(BUILD_DIR_ENV, build_dir)
+                .current_dir(&crate_dir)
+                .output()
+                .map_err(|e| format!("Failed to execute cargo {}: {}", command, e))?;
+            if !output.status.success() {
+                return Err(format!("cargo {} failed:\n{}", command, String::from_utf8_lossy(&output.stderr)));
+            }
+        }
+    }
+    println!("Dependencies ready in {:.1}s", started.elapsed().as_secs_f64());
+    Ok(count)
+}
+
+// 写出只声明依赖的空 crate
+fn write_crate(crate_dir: &Path, dependencies: toml::Table) -> Result<(), String> {
+    fs::create_dir_all(crate_dir.join("src")).map_err(|e| format!("Failed to create {}: {}", crate_dir.display(), e))?;
+    let mut package = toml::Table::new();
+    package.insert("name".to_string(), toml::Value::String("grader-warmup".to_string()));
+    package.insert("version".to_string(), toml::Value::String("0.0.0".to_string()));
+    package.insert("edition".to_string(), toml::Value::String("2021".to_string()));
+    let mut manifest = toml::Table::new();
+    manifest.insert("package".to_string(), toml::Value::Table(package));
+    manifest.insert("dependencies".to_string(), toml::Value::Table(dependencies));
+    // 状态目录可能位于某个工作区之下，声明为独立的工作区
+    manifest.insert("workspace".to_string(), toml::Value::Table(toml::Table::new()));
+    let manifest = toml::to_string(&manifest).map_err(|e| e.to_string())?;
+    fs::write(crate_dir.join("Cargo.toml"), manifest).map_err(|e| format!("Failed to write warm-up manifest: {}", e))?;
+    fs::write(crate_dir.join("src").join("lib.rs"), "").map_err(|e| format!("Failed to write warm-up crate: {}", e))
+}
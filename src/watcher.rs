@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+// 等待文件事件时，每隔这么长时间检查一次是否需要停止等待
+const CHECK_INTERVAL: Duration = Duration::from_millis(100);
+// 防抖：检测到变化后，等待这么长时间内不再有新的变化才开始评测，
+// 避免保存时自动格式化的编辑器连续写入多次而触发多轮评测
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+// 基于操作系统文件事件（inotify、FSEvents 等）监视目录，跳过构建产物和编辑器临时文件
+pub struct Watcher {
+    // 监视的目录及其绝对路径，绝对路径用来把事件中的路径还原为以监视目录开头的路径
+    roots: Vec<(PathBuf, PathBuf)>,
+    // 保持监视器存活，离开作用域时停止监视
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    // 上次 refresh 时已有的文件
+    known: HashSet<PathBuf>,
+    // 已收到但尚未报告的变化
+    pending: Vec<PathBuf>,
+}
+
+impl Watcher {
+    pub fn new(roots: &[PathBuf]) -> Result<Watcher, String> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|e| format!("Failed to start the file watcher: {}", e))?;
+        let mut watched = Vec::new();
+        for root in roots {
+            let canonical_root = root
+                .canonicalize()
+                .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+            watcher
+                .watch(&canonical_root, RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+            watched.push((root.clone(), canonical_root));
+        }
+        let mut watcher = Watcher {
+            roots: watched,
+            _watcher: watcher,
+            events,
+            known: HashSet::new(),
+            pending: Vec::new(),
+        };
+        watcher.refresh();
+        Ok(watcher)
+    }
+
+    // 重新记录当前的文件并丢弃已收到的事件，忽略评测过程本身造成的变化（如注入隐藏测试）
+    pub fn refresh(&mut self) {
+        self.known.clear();
+        for (root, _) in &self.roots {
+            visit(root, &mut self.known);
+        }
+        while self.events.try_recv().is_ok() {}
+        self.pending.clear();
+    }
+
+    // 已有的文件是否被修改或删除；评测期间用它判断是否需要取消评测，
+    // 不考虑新增的文件，因为评测过程本身会生成文件
+    pub fn modified(&mut self) -> bool {
+        while let Ok(event) = self.events.try_recv() {
+            self.record(event);
+        }
+        self.pending.iter().any(|path| self.known.contains(path))
+    }
+
+    // 阻塞直到有文件发生变化并且在防抖时间内不再变化，返回期间变化的所有文件
+    pub fn wait_for_changes(&mut self) -> Vec<PathBuf> {
+        self.wait_for_changes_until(|| false).unwrap_or_default()
+    }
+
+    // 同 wait_for_changes，但 stop 返回 true 时提前结束等待并返回 None
+    pub fn wait_for_changes_until(&mut self, mut stop: impl FnMut() -> bool) -> Option<Vec<PathBuf>> {
+        while self.pending.is_empty() {
+            if stop() {
+                return None;
+            }
+            match self.events.recv_timeout(CHECK_INTERVAL) {
+                Ok(event) => self.record(event),
+                Err(RecvTimeoutError::Timeout) => {}
+                // 监视器已停止，不会再有事件
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+        while let Ok(event) = self.events.recv_timeout(DEBOUNCE) {
+            self.record(event);
+        }
+        let mut changed = std::mem::take(&mut self.pending);
+        changed.sort();
+        changed.dedup();
+        Some(changed)
+    }
+
+    fn record(&mut self, event: notify::Result<Event>) {
+        let Ok(event) = event else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        for path in event.paths {
+            let Some((root, relative)) = self
+                .roots
+                .iter()
+                .find_map(|(root, canonical)| path.strip_prefix(canonical).ok().map(|relative| (root, relative)))
+            else {
+                continue;
+            };
+            let path = root.join(relative);
+            // 目录本身的事件（如其中新增了文件）没有意义，文件的事件会单独报告
+            if !is_ignored(relative) && !path.is_dir() {
+                self.pending.push(path);
+            }
+        }
+    }
+}
+
+// 受变化影响的习题：变化的文件就是某道单文件习题，或位于某个 Cargo 项目习题中；
+// 不属于任何习题的文件（如章节内共用的辅助文件）变化时，整个章节都要重新评测
+pub fn affected_exercises(exercises: &[PathBuf], changed: &[PathBuf]) -> Vec<PathBuf> {
+    let mut affected = Vec::new();
+    for path in changed {
+        match exercises.iter().find(|exercise| path == *exercise || path.starts_with(exercise)) {
+            Some(exercise) => {
+                if !affected.contains(exercise) {
+                    affected.push(exercise.clone());
+                }
+            }
+            None => return exercises.to_vec(),
+        }
+    }
+    // 保持章节中的原有顺序
+    exercises.iter().filter(|exercise| affected.contains(exercise)).cloned().collect()
+}
+
+fn visit(dir: &Path, files: &mut HashSet<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if !IGNORED_DIRS.contains(&name.as_str()) {
+                visit(&path, files);
+            }
+        } else if !is_ignored_file(&name) {
+            files.insert(path);
+        }
+    }
+}
+
+// 不监视的目录：构建产物和版本库元数据，评测本身就会改动它们
+const IGNORED_DIRS: &[&str] = &["target", ".git"];
+
+fn is_ignored(relative: &Path) -> bool {
+    relative.components().any(|component| match component {
+        Component::Normal(name) => IGNORED_DIRS.contains(&name.to_string_lossy().as_ref()),
+        _ => false,
+    }) || relative
+        .file_name()
+        .map(|name| is_ignored_file(&name.to_string_lossy()))
+        .unwrap_or(false)
+}
+
+// 编辑器的临时文件、交换文件和备份文件，以及评测时 cargo 生成的 Cargo.lock；
+// 它们的变化不代表习题被修改，如果不忽略会导致反复评测
+fn is_ignored_file(name: &str) -> bool {
+    name == "Cargo.lock"
+        || name == ".DS_Store"
+        // vim 保存前用来检查目录是否可写的文件
+        || name == "4913"
+        || name.ends_with('~')
+        // emacs 的锁文件和自动保存文件
+        || name.starts_with(".#")
+        || (name.starts_with('#') && name.ends_with('#'))
+        || [".swp", ".swo", ".swx", ".tmp", ".bak", ".orig"].iter().any(|ext| name.ends_with(ext))
+}
@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::Deserialize;
+use serde_json::json;
+use crate::paths;
+use crate::ExerciseResult;
+
+// 待投递的结果保存在状态目录的这个子目录中，投递成功后删除；本次运行未能投递的下次运行时重新投递
+const OUTBOX_DIR: &str = "outbox";
+// 单次请求的超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// 评测结束时最多等待后台投递这么久，未投递完的结果留在 outbox 中
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 本进程写入 outbox 的结果数，同一时刻写入的多个结果据此区分文件名
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebhookConfig {
+    // 每道题评测完成后推送结果的地址
+    pub url: String,
+    // 网络错误或服务器错误（5xx）后的重试次数；4xx 表示请求本身有误，不重试
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+// 每道题评测完成后推送结果，供课堂实时看板使用；结果先写入 outbox，由后台线程投递，评测不等待网络
+pub struct Webhook {
+    user_name: Option<String>,
+    run_id: Mutex<String>,
+    outbox: PathBuf,
+    sender: Mutex<Option<Sender<PathBuf>>>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+// 投递失败的原因
+enum Failure {
+    // 网络不可用或服务器暂时故障，本次运行不再尝试
+    Unavailable(String),
+    // 服务器拒绝了请求（4xx），重试也不会成功
+    Rejected(String),
+}
+
+impl Webhook {
+    // 启动后台投递线程，它先补发上次运行中积压的结果
+    pub fn new(config: WebhookConfig, user_name: Option<String>, run_id: &str) -> Self {
+        let outbox = paths::state_file(OUTBOX_DIR);
+        let (sender, receiver) = mpsc::channel();
+        let worker = {
+            let outbox = outbox.clone();
+            thread::spawn(move || deliver(&config, &outbox, receiver))
+        };
+        Webhook {
+            user_name,
+            run_id: Mutex::new(run_id.to_string()),
+            outbox,
+            sender: Mutex::new(Some(sender)),
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    // 续评中断的运行时沿用原运行 ID，看板据此把结果归到同一次运行
+    pub fn set_run_id(&self, run_id: &str) {
+        *lock(&self.run_id) = run_id.to_string();
+    }
+
+    // 推送单题结果；看板按 user 区分学生，按 run_id 和 exercise 去重补发的结果
+    pub fn notify(&self, result: &ExerciseResult) {
+        let payload = json!({
+            "user": self.user_name,
+            "run_id": *lock(&self.run_id),
+            "exercise": result.name,
+            "result": result.result,
+            "timestamp": unix_time(),
+        })
+        .to_string();
+        match save_to_outbox(&self.outbox, &payload) {
+            Ok(file) => {
+                if let Some(sender) = lock(&self.sender).as_ref() {
+                    let _ = sender.send(file);
+                }
+            }
+            Err(e) => eprintln!("Error saving webhook payload to outbox: {}", e),
+        }
+    }
+
+    // 评测结束：等待后台线程投递完已排队的结果，最多等待 DRAIN_TIMEOUT
+    pub fn finish(&self) {
+        lock(&self.sender).take();
+        let Some(worker) = lock(&self.worker).take() else {
+            return;
+        };
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+        while !worker.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(50));
+        }
+        if worker.is_finished() {
+            let _ = worker.join();
+        } else {
+            eprintln!("Webhook delivery is still in progress; the remaining results will be sent on the next run");
+        }
+    }
+}
+
+// 后台投递：先补发 outbox 中积压的结果，再依次投递新的结果；服务不可用时停止投递，结果留在 outbox 中
+fn deliver(config: &WebhookConfig, outbox: &Path, receiver: Receiver<PathBuf>) {
+    let mut files: Vec<PathBuf> = match fs::read_dir(outbox) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    let mut available = true;
+    for file in files.into_iter().chain(receiver) {
+        if !available {
+            continue;
+        }
+        // 同一结果可能既在积压的结果中又在队列中，已投递（已删除）的跳过
+        let Ok(payload) = fs::read_to_string(&file) else {
+            continue;
+        };
+        match post(config, &payload) {
+            Ok(()) => {
+                let _ = fs::remove_file(&file);
+            }
+            Err(Failure::Rejected(e)) => {
+                eprintln!("Webhook rejected {}: {}; it stays in the outbox", file.display(), e);
+            }
+            Err(Failure::Unavailable(e)) => {
+                eprintln!("Webhook delivery failed: {}; results will be sent on the next run", e);
+                available = false;
+            }
+        }
+    }
+}
+
+// 带指数退避的 POST 请求，只重试网络错误和 5xx
+fn post(config: &WebhookConfig, payload: &str) -> Result<(), Failure> {
+    let mut delay = Duration::from_millis(500);
+    let mut attempt = 0;
+    loop {
+        let response = ureq::post(&config.url)
+            .set("Content-Type", "application/json")
+            .timeout(REQUEST_TIMEOUT)
+            .send_string(payload);
+        match response {
+            Ok(_) => return Ok(()),
+            Err(ureq::Error::Status(code, _)) if code < 500 => {
+                return Err(Failure::Rejected(format!("server returned {}", code)));
+            }
+            Err(e) if attempt >= config.retries => return Err(Failure::Unavailable(e.to_string())),
+            Err(_) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+fn save_to_outbox(outbox: &Path, payload: &str) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(outbox)?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let file = outbox.join(format!("{}-{}.json", nanos, QUEUED.fetch_add(1, Ordering::Relaxed)));
+    fs::write(&file, payload)?;
+    Ok(file)
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}